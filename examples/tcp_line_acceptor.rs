@@ -0,0 +1,34 @@
+use lineriver::{Acceptor, AcceptorEvent};
+use std::collections::HashMap;
+use std::net::TcpListener;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Same as tcp_line_echo, using Acceptor for the poll boilerplate:
+    let mut acceptor = Acceptor::new(TcpListener::bind("127.0.0.1:7878")?)?;
+    let mut addrs = HashMap::new();
+    loop {
+        for event in acceptor.wait(None)? {
+            match event {
+                AcceptorEvent::Connected(key, addr) => {
+                    eprintln!("{}: connected", addr);
+                    addrs.insert(key, addr);
+                }
+                AcceptorEvent::Lines(key, lines) => {
+                    for line in lines {
+                        print!("{}: {}", addrs[&key], line);
+                    }
+                }
+                AcceptorEvent::Closed(key) => {
+                    eprintln!("{}: eof", addrs[&key]);
+                    addrs.remove(&key);
+                }
+                AcceptorEvent::Error(key, err) => {
+                    eprintln!("{}: {}", addrs[&key], err);
+                    addrs.remove(&key);
+                }
+                AcceptorEvent::AcceptError(err) => eprintln!("accept: {}", err),
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,321 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`Acceptor`], a [`Reactor`] that accepts
+//! connections from a listener, enabled by the `polling` feature.
+
+use std::fmt::Debug;
+use std::io::{self, Read};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::os::fd::{AsFd, AsRawFd};
+#[cfg(feature = "signal")]
+use std::os::raw::c_int;
+use std::os::unix::net::{self, UnixListener, UnixStream};
+use std::time::Duration;
+
+use crate::lineread::LineRead;
+use crate::linereader::LineReader;
+use crate::reactor::{Reactor, ReactorEvent};
+
+/// Listening socket that can be used with an [`Acceptor`].
+pub trait Listener: AsFd {
+    /// Type of the accepted connections.
+    type Stream: Read + AsFd + AsRawFd + Debug;
+    /// Type of the address of the peers.
+    type Addr: Debug;
+
+    /// Moves the listener into or out of non-blocking mode.
+    fn set_nonblocking(&self, nonblocking: bool) -> Result<(), io::Error>;
+
+    /// Accepts a new connection.
+    fn accept(&self) -> Result<(Self::Stream, Self::Addr), io::Error>;
+}
+
+impl Listener for TcpListener {
+    type Stream = TcpStream;
+    type Addr = SocketAddr;
+
+    fn set_nonblocking(&self, nonblocking: bool) -> Result<(), io::Error> {
+        TcpListener::set_nonblocking(self, nonblocking)
+    }
+
+    fn accept(&self) -> Result<(TcpStream, SocketAddr), io::Error> {
+        TcpListener::accept(self)
+    }
+}
+
+impl Listener for UnixListener {
+    type Stream = UnixStream;
+    type Addr = net::SocketAddr;
+
+    fn set_nonblocking(&self, nonblocking: bool) -> Result<(), io::Error> {
+        UnixListener::set_nonblocking(self, nonblocking)
+    }
+
+    fn accept(&self) -> Result<(UnixStream, net::SocketAddr), io::Error> {
+        UnixListener::accept(self)
+    }
+}
+
+/// Event returned by [`Acceptor::wait`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AcceptorEvent<A> {
+    /// A new connection was accepted from the given address.
+    Connected(usize, A),
+    /// Lines were received from a connection.
+    Lines(usize, Vec<String>),
+    /// The connection reached EOF, and was removed.
+    Closed(usize),
+    /// Reading from the connection failed, and it was removed.
+    Error(usize, io::Error),
+    /// Accepting a new connection failed; the listener keeps being
+    /// polled.
+    AcceptError(io::Error),
+    /// The connection exceeded the memory limit set with
+    /// [`Acceptor::with_memory_limit`], and was disconnected or
+    /// suspended, according to the [`LimitPolicy`].
+    LimitExceeded(usize),
+    /// The given signal was received; see [`Reactor::add_signal`].
+    #[cfg(feature = "signal")]
+    Signal(c_int),
+    /// The acceptor was woken up by a [`ReactorWaker`](crate::ReactorWaker)
+    /// of its reactor.
+    Wakeup,
+}
+
+/// What [`Acceptor`] does with a connection that exceeds its memory
+/// limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitPolicy {
+    /// Removes the connection, closing it.
+    Disconnect,
+    /// Stops reading from the connection until [`Acceptor::resume`] is
+    /// called; the peer is then throttled by the kernel buffers.
+    Pause,
+}
+
+/// Server helper that accepts connections from a [`Listener`] and
+/// reads lines from all of them.
+///
+/// [`TcpListener`] and [`UnixListener`] can be used as listeners; the
+/// latter is handy for local control sockets.
+///
+/// Connections get keys from the underlying [`Reactor`], and are
+/// removed automatically when they reach EOF.
+///
+/// Example:
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::net::TcpListener;
+/// use lineriver::{Acceptor, AcceptorEvent};
+///
+/// let mut acceptor = Acceptor::new(TcpListener::bind("127.0.0.1:7878")?)?;
+/// loop {
+///     for event in acceptor.wait(None)? {
+///         match event {
+///             AcceptorEvent::Connected(key, addr) => eprintln!("{}: {} connected", key, addr),
+///             AcceptorEvent::Lines(key, lines) => lines.iter().for_each(|l| print!("{}: {}", key, l)),
+///             AcceptorEvent::Closed(key) => eprintln!("{}: closed", key),
+///             AcceptorEvent::Error(key, err) => eprintln!("{}: {}", key, err),
+///             AcceptorEvent::AcceptError(err) => eprintln!("accept: {}", err),
+///             _ => {}
+///         }
+///     }
+/// }
+/// # }
+/// ```
+pub struct Acceptor<T: Listener> {
+    // Declared before the listener, so that it is dropped first.
+    reactor: Reactor<LineReader<T::Stream>>,
+    listener: T,
+    listener_key: usize,
+    limit: Option<(usize, LimitPolicy)>,
+}
+
+impl<T: Listener + Debug> Debug for Acceptor<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Acceptor")
+            .field("listener", &self.listener)
+            .field("reactor", &self.reactor)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Listener> Acceptor<T> {
+    /// Creates an acceptor, setting the listener as non-blocking.
+    pub fn new(listener: T) -> Result<Self, io::Error> {
+        listener.set_nonblocking(true)?;
+        let mut reactor = Reactor::new()?;
+        // Safety: the acceptor owns the listener, and drops the
+        // reactor before it.
+        let listener_key = unsafe { reactor.add_foreign(listener.as_fd())? };
+        Ok(Self {
+            reactor,
+            listener,
+            listener_key,
+            limit: None,
+        })
+    }
+
+    /// Limits the number of bytes buffered per connection, applying
+    /// `policy` to the connections that exceed it.
+    ///
+    /// As [`Self::wait`] delivers the complete lines right away, this
+    /// limits the size of the partial lines, protecting the server
+    /// from peers that send endless lines.
+    pub fn with_memory_limit(mut self, bytes: usize, policy: LimitPolicy) -> Self {
+        self.limit = Some((bytes, policy));
+        self
+    }
+
+    /// Returns the number of bytes buffered by the connection with the
+    /// given key.
+    pub fn buffered_bytes(&self, key: usize) -> Option<usize> {
+        self.reactor.get(key).map(LineReader::buffered_bytes)
+    }
+
+    /// Returns the number of bytes buffered by all connections.
+    pub fn total_buffered_bytes(&self) -> usize {
+        self.reactor
+            .iter()
+            .map(|(_, reader)| reader.buffered_bytes())
+            .sum()
+    }
+
+    /// Resumes reading from a connection paused for exceeding the
+    /// memory limit.
+    pub fn resume(&mut self, key: usize) -> Result<(), io::Error> {
+        self.reactor.resume(key)
+    }
+
+    /// Gets a reference to the listener.
+    pub fn listener(&self) -> &T {
+        &self.listener
+    }
+
+    /// Gets a reference to the underlying reactor.
+    pub fn reactor(&self) -> &Reactor<LineReader<T::Stream>> {
+        &self.reactor
+    }
+
+    /// Gets a mutable reference to the underlying reactor.
+    pub fn reactor_mut(&mut self) -> &mut Reactor<LineReader<T::Stream>> {
+        &mut self.reactor
+    }
+
+    /// Returns a reference to the connection with the given key.
+    pub fn get(&self, key: usize) -> Option<&LineReader<T::Stream>> {
+        self.reactor.get(key)
+    }
+
+    /// Returns a mutable reference to the connection with the given
+    /// key.
+    pub fn get_mut(&mut self, key: usize) -> Option<&mut LineReader<T::Stream>> {
+        self.reactor.get_mut(key)
+    }
+
+    /// Removes the connection with the given key, returning it.
+    pub fn disconnect(&mut self, key: usize) -> Result<Option<LineReader<T::Stream>>, io::Error> {
+        self.reactor.remove(key)
+    }
+
+    /// Returns the number of connections.
+    pub fn len(&self) -> usize {
+        self.reactor.len()
+    }
+
+    /// Returns `true` if there are no connections.
+    pub fn is_empty(&self) -> bool {
+        self.reactor.is_empty()
+    }
+
+    /// Waits for new connections or lines, returning the resulting
+    /// events.
+    ///
+    /// Waits at most `timeout`, or forever if `None`.
+    pub fn wait(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<AcceptorEvent<T::Addr>>, io::Error> {
+        let mut foreign = vec![];
+        let mut read = vec![];
+        let events = self.reactor.wait_with(timeout, &mut foreign, &mut read)?;
+        let mut result = vec![];
+        if foreign.contains(&self.listener_key) {
+            self.accept_all(&mut result);
+            self.reactor
+                .rearm_foreign(self.listener.as_fd(), self.listener_key)?;
+        }
+        for event in events {
+            match event {
+                ReactorEvent::Lines(key) => {
+                    result.push(AcceptorEvent::Lines(key, self.reactor[key].lines_get()));
+                }
+                ReactorEvent::Eof(key) => {
+                    if let Some(mut reader) = self.reactor.remove(key)? {
+                        let lines = reader.lines_get();
+                        if !lines.is_empty() {
+                            result.push(AcceptorEvent::Lines(key, lines));
+                        }
+                    }
+                    result.push(AcceptorEvent::Closed(key));
+                }
+                ReactorEvent::Error(key, err) => {
+                    self.reactor.remove(key)?;
+                    result.push(AcceptorEvent::Error(key, err));
+                }
+                #[cfg(feature = "signal")]
+                ReactorEvent::Signal(signal) => {
+                    result.push(AcceptorEvent::Signal(signal));
+                }
+                ReactorEvent::Wakeup => result.push(AcceptorEvent::Wakeup),
+            }
+        }
+        if let Some((limit, policy)) = self.limit {
+            for key in read {
+                if self.buffered_bytes(key).is_some_and(|b| b > limit) {
+                    match policy {
+                        LimitPolicy::Disconnect => {
+                            self.reactor.remove(key)?;
+                        }
+                        LimitPolicy::Pause => self.reactor.suspend(key)?,
+                    }
+                    result.push(AcceptorEvent::LimitExceeded(key));
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Accepts all pending connections, reporting the errors as
+    /// [`AcceptorEvent::AcceptError`].
+    fn accept_all(&mut self, result: &mut Vec<AcceptorEvent<T::Addr>>) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, addr)) => match self.add(stream, &addr) {
+                    Ok(key) => result.push(AcceptorEvent::Connected(key, addr)),
+                    Err(err) => result.push(AcceptorEvent::AcceptError(err)),
+                },
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return,
+                Err(ref err)
+                    if err.kind() == io::ErrorKind::ConnectionAborted
+                        || err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    // Errors like EMFILE persist, so we stop here and
+                    // let the re-armed listener retry on the next wait.
+                    result.push(AcceptorEvent::AcceptError(err));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Adds an accepted connection to the reactor.
+    fn add(&mut self, stream: T::Stream, addr: &T::Addr) -> Result<usize, io::Error> {
+        let reader = LineReader::new(stream)?.with_peer(format!("{:?}", addr));
+        self.reactor.add(reader)
+    }
+}
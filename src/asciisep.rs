@@ -0,0 +1,251 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`AsciiRecordReader`], which splits the data at the
+//! ASCII separator control characters instead of newlines.
+
+use std::fmt::Debug;
+use std::io::{self, Read};
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+
+use crate::blocking;
+
+const BUFFER_SIZE: usize = 8192;
+
+/// ASCII separator control characters, from the outermost to the
+/// innermost level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AsciiSeparator {
+    /// Group separator, `0x1D`.
+    Group,
+    /// Record separator, `0x1E`.
+    Record,
+    /// Unit (field) separator, `0x1F`.
+    Unit,
+}
+
+impl AsciiSeparator {
+    /// Returns the byte of the separator.
+    pub fn byte(self) -> u8 {
+        match self {
+            AsciiSeparator::Group => 0x1d,
+            AsciiSeparator::Record => 0x1e,
+            AsciiSeparator::Unit => 0x1f,
+        }
+    }
+
+    /// Returns the character of the separator.
+    pub fn char(self) -> char {
+        char::from(self.byte())
+    }
+}
+
+/// Non-blocking reader that splits the data at an ASCII separator
+/// control character, as used by some EDI and financial feeds.
+///
+/// Items are terminated by the record separator (`RS`, `0x1E`) by
+/// default, or by another separator set with [`Self::with_separator`],
+/// and the separator is not included in them. Newlines are regular
+/// data.
+///
+/// Items can be split further with [`Self::fields_get`], which splits
+/// records into units (`US`, `0x1F`), and with [`Self::groups_get`],
+/// the nested mode, which splits groups (`GS`, `0x1D`) into records
+/// and those into units.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), std::io::Error> {
+/// use lineriver::{AsciiRecordReader, AsciiSeparator};
+///
+/// let data = b"a\x1fb\x1ec\x1e\x1dd\x1e";
+/// let mut reader =
+///     AsciiRecordReader::from_nonblocking(&data[..]).with_separator(AsciiSeparator::Group);
+/// while reader.read_once()? {}
+/// assert_eq!(
+///     reader.groups_get(),
+///     vec![vec![vec!["a", "b"], vec!["c"]], vec![vec!["d"]]]
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AsciiRecordReader<R> {
+    reader: R,
+    separator: AsciiSeparator,
+    at_eof: bool,
+    buf: Vec<u8>,
+    used: usize,
+    records: Vec<String>,
+}
+
+impl<R: Read + AsRawFd + Debug> AsciiRecordReader<R> {
+    /// Creates a new AsciiRecordReader, setting the underlying
+    /// descriptor as non-blocking.
+    pub fn new(reader: R) -> Result<Self, io::Error> {
+        blocking::disable(reader.as_raw_fd())?;
+        Ok(Self::from_nonblocking(reader))
+    }
+}
+
+impl<R: Read> AsciiRecordReader<R> {
+    /// Creates a new AsciiRecordReader.
+    ///
+    /// Assumes the reader is already non-blocking, not configuring
+    /// anything in the underlying descriptor.
+    pub fn from_nonblocking(reader: R) -> Self {
+        Self {
+            reader,
+            separator: AsciiSeparator::Record,
+            at_eof: false,
+            buf: Vec::new(),
+            used: 0,
+            records: Vec::new(),
+        }
+    }
+
+    /// Terminates the items at `separator` instead of the record
+    /// separator.
+    pub fn with_separator(mut self, separator: AsciiSeparator) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Returns `true` if the reader reached EOF.
+    ///
+    /// Items may still be available after EOF.
+    pub fn eof(&self) -> bool {
+        self.at_eof
+    }
+
+    /// Performs a single read and splits all complete items.
+    ///
+    /// Returns `false` if the reader is at EOF. At EOF, the remaining
+    /// bytes become the last item, even without a separator. An item
+    /// that is not valid UTF-8 is returned as an
+    /// [`io::ErrorKind::InvalidData`] error.
+    pub fn read_once(&mut self) -> Result<bool, io::Error> {
+        if self.at_eof {
+            return Ok(false);
+        }
+        if self.buf.len() < self.used + BUFFER_SIZE {
+            self.buf.resize(self.used + BUFFER_SIZE, 0);
+        }
+        match self.reader.read(&mut self.buf[self.used..]) {
+            Ok(0) => {
+                self.at_eof = true;
+                if self.used > 0 {
+                    self.push_record(self.used, self.used)?;
+                }
+            }
+            Ok(len) => {
+                let separator = self.separator.byte();
+                let mut pos = self.used;
+                self.used += len;
+                while let Some(i) = memchr::memchr(separator, &self.buf[pos..self.used]) {
+                    self.push_record(pos + i, pos + i + 1)?;
+                    pos = 0;
+                }
+            }
+            Err(ref err)
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+        Ok(true)
+    }
+
+    /// Moves the first `len` bytes of the buffer to a new item,
+    /// dropping the bytes up to `end`.
+    fn push_record(&mut self, len: usize, end: usize) -> Result<(), io::Error> {
+        let rest = self.buf.split_off(end);
+        let mut record = mem::replace(&mut self.buf, rest);
+        record.truncate(len);
+        self.used -= end;
+        let record =
+            String::from_utf8(record).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Reads all available data, or until an item is available.
+    pub fn read_available(&mut self) -> Result<(), io::Error> {
+        while self.read_once()? && !self.has_records() {}
+        Ok(())
+    }
+
+    /// Returns `true` if there are complete items available.
+    pub fn has_records(&self) -> bool {
+        !self.records.is_empty()
+    }
+
+    /// Takes the complete items.
+    pub fn records_get(&mut self) -> Vec<String> {
+        mem::take(&mut self.records)
+    }
+
+    /// Takes the complete items, split into their units.
+    pub fn fields_get(&mut self) -> Vec<Vec<String>> {
+        self.records_get()
+            .iter()
+            .map(|record| split_units(record))
+            .collect()
+    }
+
+    /// Takes the complete items as groups of records, each split into
+    /// its units; meant to be used with the group separator.
+    ///
+    /// The record separator terminates records, so a group that ends
+    /// with one doesn't get an empty last record.
+    pub fn groups_get(&mut self) -> Vec<Vec<Vec<String>>> {
+        self.records_get()
+            .iter()
+            .map(|group| {
+                let group = group
+                    .strip_suffix(AsciiSeparator::Record.char())
+                    .unwrap_or(group);
+                group
+                    .split(AsciiSeparator::Record.char())
+                    .map(split_units)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the underlying reader, dropping any buffered data.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+fn split_units(record: &str) -> Vec<String> {
+    record
+        .split(AsciiSeparator::Unit.char())
+        .map(str::to_owned)
+        .collect()
+}
+
+impl<R: AsRawFd> AsRawFd for AsciiRecordReader<R> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.reader.as_raw_fd()
+    }
+}
+
+impl<R: AsFd> AsFd for AsciiRecordReader<R> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.reader.as_fd()
+    }
+}
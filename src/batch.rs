@@ -0,0 +1,170 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineBatcher`], a [`LineRead`] wrapper that
+//! delivers lines in batches.
+
+use std::collections::VecDeque;
+use std::io;
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::time::{Duration, Instant};
+
+use crate::lineread::{FullLatch, LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+
+/// [`LineRead`] wrapper that delivers lines in batches of up to
+/// `max_lines`, or after `max_delay` has passed since the first line
+/// of the batch arrived, whichever comes first.
+///
+/// This amortizes downstream costs, like database inserts or HTTP
+/// posts, in log shipping pipelines. Lines are only reported by
+/// [`LineRead::has_lines`] and [`LineRead::lines_get`] when a batch
+/// is complete; [`Self::batch_get`] retrieves one batch at a time.
+///
+/// The delay is checked when the wrapper is used, so it should be
+/// polled periodically even when there is no data.
+#[derive(Debug)]
+pub struct LineBatcher<L> {
+    inner: L,
+    max_lines: usize,
+    max_delay: Duration,
+    pending: Vec<String>,
+    first_at: Option<Instant>,
+    batches: VecDeque<VecDeque<String>>,
+    full: FullLatch,
+}
+
+impl<L: LineRead> LineBatcher<L> {
+    /// Creates a new `LineBatcher` over `inner`.
+    ///
+    /// `max_lines` is the maximum number of lines in a batch, and
+    /// can't be `0`.
+    pub fn new(inner: L, max_lines: usize, max_delay: Duration) -> Self {
+        assert!(max_lines > 0, "max_lines must be greater than 0");
+        Self {
+            inner,
+            max_lines,
+            max_delay,
+            pending: Default::default(),
+            first_at: None,
+            batches: Default::default(),
+            full: FullLatch::default(),
+        }
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &L {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut L {
+        &mut self.inner
+    }
+
+    /// Returns the underlying reader, dropping the lines that were
+    /// not retrieved yet.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+
+    /// Returns the next complete batch, if there is one.
+    pub fn batch_get(&mut self) -> Option<Vec<String>> {
+        self.pull();
+        self.batches.pop_front().map(Vec::from)
+    }
+
+    /// Moves lines from the inner reader to the pending batch,
+    /// completing batches as needed.
+    fn pull(&mut self) {
+        self.full
+            .update(self.inner.is_full(), !self.batches.is_empty());
+        for line in self.inner.lines_get() {
+            if self.pending.is_empty() {
+                self.first_at = Some(Instant::now());
+            }
+            self.pending.push(line);
+            if self.pending.len() == self.max_lines {
+                self.complete();
+            }
+        }
+        if !self.pending.is_empty()
+            && (self.inner.eof()
+                || self
+                    .first_at
+                    .is_some_and(|first_at| first_at.elapsed() >= self.max_delay))
+        {
+            self.complete();
+        }
+    }
+
+    fn complete(&mut self) {
+        self.batches.push_back(mem::take(&mut self.pending).into());
+        self.first_at = None;
+    }
+}
+
+impl<L: LineRead> LineRead for LineBatcher<L> {
+    fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        let result = self.inner.read_once();
+        self.pull();
+        result
+    }
+
+    /// Returns the lines of all complete batches.
+    fn lines_get(&mut self) -> Vec<String> {
+        self.pull();
+        self.batches.drain(..).flatten().collect()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        let batch = self.batches.front_mut()?;
+        let line = batch.pop_front()?;
+        if batch.is_empty() {
+            self.batches.pop_front();
+        }
+        Some(line)
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.pull();
+        !self.batches.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.full
+            .is_full(self.inner.is_full(), !self.batches.is_empty())
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: AsRawFd> AsRawFd for LineBatcher<L> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd> AsFd for LineBatcher<L> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd> LineReadRawFd for LineBatcher<L> {}
+
+impl<L: LineRead + AsFd> LineReadFd for LineBatcher<L> {}
+
+impl<L: LineRead + AsFd + AsRawFd> LineReadRawAndFd for LineBatcher<L> {}
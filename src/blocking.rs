@@ -6,6 +6,7 @@ use libc::{F_GETFL, F_SETFL, O_NONBLOCK};
 use std::fmt::Debug;
 use std::io;
 use std::os::fd::AsRawFd;
+use std::time::Duration;
 
 #[tracing::instrument]
 fn fcntl(
@@ -27,3 +28,42 @@ pub fn disable<R: AsRawFd + Debug>(reader: R) -> Result<(), io::Error> {
     fcntl(fd, F_SETFL, flags | O_NONBLOCK)?;
     Ok(())
 }
+
+/// Waits until `fd` is readable, or until `timeout` expires.
+///
+/// Returns `false` on timeout or if interrupted by a signal.
+pub fn wait_readable(fd: std::os::fd::RawFd, timeout: Duration) -> Result<bool, io::Error> {
+    wait_fd(fd, libc::POLLIN, timeout)
+}
+
+/// Waits until `fd` is writable, or until `timeout` expires.
+///
+/// Returns `false` on timeout or if interrupted by a signal.
+pub fn wait_writable(fd: std::os::fd::RawFd, timeout: Duration) -> Result<bool, io::Error> {
+    wait_fd(fd, libc::POLLOUT, timeout)
+}
+
+#[tracing::instrument]
+fn wait_fd(
+    fd: std::os::fd::RawFd,
+    events: libc::c_short,
+    timeout: Duration,
+) -> Result<bool, io::Error> {
+    let mut pollfd = libc::pollfd {
+        fd,
+        events,
+        revents: 0,
+    };
+    // Round up, so that we don't wake up right before the deadline:
+    let millis = timeout.as_micros().div_ceil(1000);
+    let millis = libc::c_int::try_from(millis).unwrap_or(libc::c_int::MAX);
+    let result = unsafe { libc::poll(&mut pollfd, 1, millis) };
+    if result < 0 {
+        let err = io::Error::last_os_error();
+        if err.kind() == io::ErrorKind::Interrupted {
+            return Ok(false);
+        }
+        return Err(err);
+    }
+    Ok(result > 0)
+}
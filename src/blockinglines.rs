@@ -0,0 +1,107 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`BlockingLines`], an iterator that waits for the
+//! lines of a non-blocking [`LineRead`](crate::LineRead).
+
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+
+use crate::blocking;
+use crate::lineread::LineReadRawFd;
+
+/// Iterator over the lines of a [`LineReadRawFd`] that waits for
+/// them, for code that wants the classic `for line in reader`
+/// ergonomics.
+///
+/// Between reads, it waits for the file descriptor to become readable
+/// with `poll(2)`, so it doesn't busy-wait. The reader itself stays
+/// non-blocking, and can be recovered with [`Self::into_inner`].
+///
+/// The iterator ends at EOF, or after yielding the first error.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), std::io::Error> {
+/// use std::process::{Command, Stdio};
+/// use lineriver::{BlockingLines, LineReader};
+///
+/// let child = Command::new("seq").arg("3").stdout(Stdio::piped()).spawn()?;
+/// let reader = LineReader::new(child.stdout.unwrap())?;
+/// let mut total = 0;
+/// for line in BlockingLines::new(reader) {
+///     total += line?.trim_end().parse::<u32>().unwrap();
+/// }
+/// assert_eq!(total, 6);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BlockingLines<L> {
+    inner: L,
+    lines: VecDeque<String>,
+    error: Option<io::Error>,
+    done: bool,
+}
+
+impl<L: LineReadRawFd> BlockingLines<L> {
+    /// Creates a new `BlockingLines` over `inner`.
+    pub fn new(inner: L) -> Self {
+        Self {
+            inner,
+            lines: Default::default(),
+            error: None,
+            done: false,
+        }
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &L {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut L {
+        &mut self.inner
+    }
+
+    /// Returns the underlying reader, dropping the lines that were
+    /// already read but not yielded yet.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+}
+
+impl<L: LineReadRawFd> Iterator for BlockingLines<L> {
+    type Item = Result<String, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(line) = self.lines.pop_front() {
+                return Some(Ok(line));
+            }
+            if self.done {
+                return self.error.take().map(Err);
+            }
+            if let Err(err) = self.inner.read_once() {
+                // Yield the lines read before the error first:
+                self.lines.extend(self.inner.lines_get());
+                self.error = Some(err);
+                self.done = true;
+                continue;
+            }
+            if self.inner.has_lines() {
+                self.lines.extend(self.inner.lines_get());
+            } else if self.inner.eof() {
+                self.done = true;
+            } else if let Err(err) = blocking::wait_readable(self.inner.as_raw_fd(), Duration::MAX)
+            {
+                self.error = Some(err);
+                self.done = true;
+            }
+        }
+    }
+}
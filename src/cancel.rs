@@ -0,0 +1,41 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`CancelToken`], used to interrupt loops that read
+//! from a [`LineReader`](crate::LineReader) from another thread.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Token that can be used to cancel reading loops from another thread.
+///
+/// All clones of a token share the same state: cancelling any one of
+/// them cancels all of them.
+#[derive(Debug, Default, Clone)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Creates a new token, not cancelled.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Cancels the token, making loops that check it return as soon
+    /// as possible.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Clears the cancellation, so that the token can be reused.
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::Release);
+    }
+
+    /// Returns `true` if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
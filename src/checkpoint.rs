@@ -0,0 +1,53 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`Checkpoint`], a snapshot of the splitter state
+//! of a [`LineReader`](crate::LineReader) that can be used to resume
+//! reading later.
+
+use std::io::{self, Seek, SeekFrom};
+
+/// Snapshot of the splitter state of a
+/// [`LineReader`](crate::LineReader), returned by
+/// [`LineReader::checkpoint`](crate::LineReader::checkpoint) and
+/// restored by
+/// [`LineReader::with_checkpoint`](crate::LineReader::with_checkpoint).
+///
+/// With the `serde` feature, the checkpoint implements `Serialize` and
+/// `Deserialize`, so it can be persisted across restarts.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Checkpoint {
+    /// Number of bytes read from the source, counting from where the
+    /// reader started.
+    pub offset: u64,
+    /// Number of lines already delivered to the caller.
+    pub lines: u64,
+    /// Bytes read from the source but not delivered yet: queued lines
+    /// and the partial line.
+    pub pending: Vec<u8>,
+}
+
+impl Checkpoint {
+    /// Creates a checkpoint to resume reading from `offset` of the
+    /// source, with nothing pending, like the one returned by
+    /// [`LineReader::committed_checkpoint`](crate::LineReader::committed_checkpoint).
+    pub fn at_offset(offset: u64) -> Self {
+        Self {
+            offset,
+            ..Default::default()
+        }
+    }
+
+    /// Seeks the source to the offset of the checkpoint, so that it can
+    /// be passed to a new reader with the checkpoint.
+    ///
+    /// Only valid if the original reader started at offset 0 of the
+    /// source.
+    pub fn seek<S: Seek>(&self, source: &mut S) -> Result<(), io::Error> {
+        source.seek(SeekFrom::Start(self.offset))?;
+        Ok(())
+    }
+}
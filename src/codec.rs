@@ -0,0 +1,169 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`CodecReader`], which drives a
+//! [`tokio_util::codec::Decoder`] with a non-blocking reader, enabled
+//! by the `codec` feature.
+
+use std::fmt::Debug;
+use std::io::{self, Read};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+
+use bytes::BytesMut;
+use tokio_util::codec::Decoder;
+
+use crate::blocking;
+
+const BUFFER_SIZE: usize = 8192;
+
+/// Non-blocking reader that splits the data into frames using a
+/// [`Decoder`], without a tokio runtime.
+///
+/// This allows the reuse of existing decoders, like
+/// [`LengthDelimitedCodec`](tokio_util::codec::LengthDelimitedCodec),
+/// with the same read loop used for [`LineReader`](crate::LineReader).
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lineriver::testing::ScriptedReader;
+/// use lineriver::CodecReader;
+/// use tokio_util::codec::LinesCodec;
+///
+/// let script = ScriptedReader::new().data(b"1\n2\n3");
+/// let mut reader = CodecReader::from_nonblocking(script, LinesCodec::new());
+/// while !reader.eof() {
+///     reader.read_available()?;
+/// }
+/// assert_eq!(reader.frames_get(), vec!["1", "2", "3"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CodecReader<R, D: Decoder> {
+    reader: R,
+    decoder: D,
+    at_eof: bool,
+    buf: BytesMut,
+    frames: Vec<D::Item>,
+}
+
+impl<R: Read + AsRawFd + Debug, D: Decoder> CodecReader<R, D> {
+    /// Creates a new CodecReader, setting the underlying descriptor
+    /// as non-blocking.
+    pub fn new(reader: R, decoder: D) -> Result<Self, io::Error> {
+        blocking::disable(reader.as_raw_fd())?;
+        Ok(Self::from_nonblocking(reader, decoder))
+    }
+}
+
+impl<R: Read, D: Decoder> CodecReader<R, D> {
+    /// Creates a new CodecReader.
+    ///
+    /// Assumes the reader is already non-blocking, not configuring
+    /// anything in the underlying descriptor.
+    pub fn from_nonblocking(reader: R, decoder: D) -> Self {
+        Self {
+            reader,
+            decoder,
+            at_eof: false,
+            buf: BytesMut::new(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the reader reached EOF.
+    ///
+    /// Frames may still be available after EOF.
+    pub fn eof(&self) -> bool {
+        self.at_eof
+    }
+
+    /// Performs a single read and decodes all complete frames.
+    ///
+    /// Returns `false` if the reader is at EOF. At EOF, the remaining
+    /// bytes are passed to [`Decoder::decode_eof`].
+    pub fn read_once(&mut self) -> Result<bool, D::Error> {
+        if self.at_eof {
+            return Ok(false);
+        }
+        let used = self.buf.len();
+        self.buf.resize(used + BUFFER_SIZE, 0);
+        let r = self.reader.read(&mut self.buf[used..]);
+        self.buf.truncate(used + *r.as_ref().unwrap_or(&0));
+        match r {
+            Ok(0) => {
+                self.at_eof = true;
+                while let Some(frame) = self.decoder.decode_eof(&mut self.buf)? {
+                    self.frames.push(frame);
+                }
+            }
+            Ok(_) => {
+                while let Some(frame) = self.decoder.decode(&mut self.buf)? {
+                    self.frames.push(frame);
+                }
+            }
+            Err(ref err)
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+        Ok(true)
+    }
+
+    /// Reads all available data, or until a frame is available.
+    pub fn read_available(&mut self) -> Result<(), D::Error> {
+        while self.read_once()? && !self.has_frames() {}
+        Ok(())
+    }
+
+    /// Returns `true` if there are decoded frames available.
+    pub fn has_frames(&self) -> bool {
+        !self.frames.is_empty()
+    }
+
+    /// Takes the decoded frames.
+    pub fn frames_get(&mut self) -> Vec<D::Item> {
+        std::mem::take(&mut self.frames)
+    }
+
+    /// Gets a reference to the decoder.
+    pub fn decoder(&self) -> &D {
+        &self.decoder
+    }
+
+    /// Gets a mutable reference to the decoder.
+    pub fn decoder_mut(&mut self) -> &mut D {
+        &mut self.decoder
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the underlying reader and decoder, dropping any
+    /// buffered data.
+    pub fn into_inner(self) -> (R, D) {
+        (self.reader, self.decoder)
+    }
+}
+
+impl<R: AsRawFd, D: Decoder> AsRawFd for CodecReader<R, D> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.reader.as_raw_fd()
+    }
+}
+
+impl<R: AsFd, D: Decoder> AsFd for CodecReader<R, D> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.reader.as_fd()
+    }
+}
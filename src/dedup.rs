@@ -0,0 +1,183 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineDedup`], a [`LineRead`] wrapper that
+//! collapses runs of identical consecutive lines, like `uniq`.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+
+use crate::lineread::{FullLatch, LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+
+/// Function that creates the summary line of a run.
+type SummaryFn = dyn Fn(&str, usize) -> String + Send;
+
+/// [`LineRead`] wrapper that collapses runs of identical consecutive
+/// lines.
+///
+/// The first line of a run is returned right away, and the repeated
+/// ones are suppressed. When the run ends - a different line arrives
+/// or the inner reader reaches EOF - a summary line with the repeat
+/// count is returned, in the style of syslog's
+/// `"last message repeated 3 times\n"`. The summary line can be
+/// customized with [`Self::with_summary`].
+///
+/// This tames log storms from misbehaving subprocesses.
+pub struct LineDedup<L> {
+    inner: L,
+    summary: Box<SummaryFn>,
+    last: Option<String>,
+    repeats: usize,
+    suppressed: u64,
+    lines: VecDeque<String>,
+    full: FullLatch,
+}
+
+impl<L: LineRead> LineDedup<L> {
+    /// Creates a new `LineDedup` over `inner`.
+    pub fn new(inner: L) -> Self {
+        Self {
+            inner,
+            summary: Box::new(|_, repeats| format!("last message repeated {} times\n", repeats)),
+            last: None,
+            repeats: 0,
+            suppressed: 0,
+            lines: Default::default(),
+            full: FullLatch::default(),
+        }
+    }
+
+    /// Sets the function that creates the summary line, which gets the
+    /// repeated line and how many times it was repeated.
+    pub fn with_summary<F>(mut self, summary: F) -> Self
+    where
+        F: Fn(&str, usize) -> String + Send + 'static,
+    {
+        self.summary = Box::new(summary);
+        self
+    }
+
+    /// Returns the total number of lines suppressed so far.
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &L {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut L {
+        &mut self.inner
+    }
+
+    /// Returns the underlying reader, dropping the lines that were
+    /// not retrieved yet.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+
+    fn end_run(&mut self) {
+        if self.repeats > 0 {
+            if let Some(last) = &self.last {
+                self.lines.push_back((self.summary)(last, self.repeats));
+            }
+            self.repeats = 0;
+        }
+    }
+
+    /// Moves lines from the inner reader to our buffer, suppressing
+    /// the repeated ones.
+    fn pull(&mut self) {
+        self.full
+            .update(self.inner.is_full(), !self.lines.is_empty());
+        for line in self.inner.lines_get() {
+            if self.last.as_ref() == Some(&line) {
+                self.repeats += 1;
+                self.suppressed += 1;
+                continue;
+            }
+            self.end_run();
+            self.last = Some(line.clone());
+            self.lines.push_back(line);
+        }
+        if self.inner.eof() {
+            self.end_run();
+        }
+    }
+}
+
+impl<L: LineRead> LineRead for LineDedup<L> {
+    fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        let result = self.inner.read_once();
+        self.pull();
+        result
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.pull();
+        mem::take(&mut self.lines).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        self.lines.pop_front()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.pull();
+        !self.lines.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.full
+            .is_full(self.inner.is_full(), !self.lines.is_empty())
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: fmt::Debug> fmt::Debug for LineDedup<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LineDedup")
+            .field("inner", &self.inner)
+            .field("last", &self.last)
+            .field("repeats", &self.repeats)
+            .field("suppressed", &self.suppressed)
+            .field("lines", &self.lines)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<L: AsRawFd> AsRawFd for LineDedup<L> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd> AsFd for LineDedup<L> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd> LineReadRawFd for LineDedup<L> {}
+
+impl<L: LineRead + AsFd> LineReadFd for LineDedup<L> {}
+
+impl<L: LineRead + AsFd + AsRawFd> LineReadRawAndFd for LineDedup<L> {}
@@ -0,0 +1,167 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineDispatcher`], which distributes lines across a
+//! pool of worker threads.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::mpsc::{self, SendError, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// Function that extracts the affinity key of a line.
+type KeyFn = dyn for<'a> Fn(&'a str) -> &'a str + Send;
+
+/// Distributes lines across a pool of worker threads, each one with a
+/// bounded queue.
+///
+/// This keeps CPU-heavy per-line processing out of the poll loop. By
+/// default lines are distributed round-robin; with
+/// [`Self::with_key`], lines with the same key always go to the same
+/// worker, so that related lines are processed in order.
+///
+/// Example:
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use lineriver::LineDispatcher;
+///
+/// let total = Arc::new(AtomicUsize::new(0));
+/// let counter = total.clone();
+/// let mut dispatcher = LineDispatcher::new(4, 16, move |line| {
+///     counter.fetch_add(line.len(), Ordering::Relaxed);
+/// });
+/// for line in ["a\n", "bb\n", "ccc\n"] {
+///     dispatcher.dispatch(line.to_string()).unwrap();
+/// }
+/// dispatcher.join().unwrap();
+/// assert_eq!(total.load(Ordering::Relaxed), 9);
+/// ```
+pub struct LineDispatcher {
+    senders: Vec<SyncSender<String>>,
+    workers: Vec<JoinHandle<()>>,
+    key: Option<Box<KeyFn>>,
+    next: usize,
+}
+
+impl fmt::Debug for LineDispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LineDispatcher")
+            .field("workers", &self.workers.len())
+            .field("key", &self.key.is_some())
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+impl LineDispatcher {
+    /// Creates a dispatcher with `workers` threads, each with a queue
+    /// that holds up to `queue_len` lines, that call `handler` with
+    /// each line.
+    ///
+    /// Panics if `workers` is 0.
+    pub fn new<F>(workers: usize, queue_len: usize, handler: F) -> Self
+    where
+        F: Fn(String) + Send + Sync + 'static,
+    {
+        assert!(workers > 0, "LineDispatcher needs at least one worker");
+        let handler = Arc::new(handler);
+        let (senders, workers) = (0..workers)
+            .map(|_| {
+                let (sender, receiver) = mpsc::sync_channel::<String>(queue_len);
+                let handler = Arc::clone(&handler);
+                let worker = thread::spawn(move || {
+                    for line in receiver {
+                        handler(line);
+                    }
+                });
+                (sender, worker)
+            })
+            .unzip();
+        Self {
+            senders,
+            workers,
+            key: None,
+            next: 0,
+        }
+    }
+
+    /// Sends lines with the same key, as extracted by `key`, to the
+    /// same worker.
+    pub fn with_key<K>(mut self, key: K) -> Self
+    where
+        K: for<'a> Fn(&'a str) -> &'a str + Send + 'static,
+    {
+        self.key = Some(Box::new(key));
+        self
+    }
+
+    /// Returns the number of workers.
+    pub fn workers(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Returns the index of the worker that gets `line`.
+    fn worker_for(&mut self, line: &str) -> usize {
+        match &self.key {
+            Some(key) => {
+                let mut hasher = DefaultHasher::new();
+                key(line).hash(&mut hasher);
+                (hasher.finish() % self.senders.len() as u64) as usize
+            }
+            None => {
+                let worker = self.next;
+                self.next = (self.next + 1) % self.senders.len();
+                worker
+            }
+        }
+    }
+
+    /// Sends `line` to a worker, blocking while its queue is full.
+    ///
+    /// Fails, giving the line back, if the worker is gone because the
+    /// handler panicked.
+    pub fn dispatch(&mut self, line: String) -> Result<(), SendError<String>> {
+        let worker = self.worker_for(&line);
+        self.senders[worker].send(line)
+    }
+
+    /// Sends `line` to a worker without blocking.
+    ///
+    /// Fails, giving the line back, if the queue of the worker is full
+    /// or if the worker is gone. Without a key, the other workers are
+    /// tried before failing with [`TrySendError::Full`].
+    pub fn try_dispatch(&mut self, line: String) -> Result<(), TrySendError<String>> {
+        let mut worker = self.worker_for(&line);
+        let mut line = line;
+        for _ in 0..self.senders.len() {
+            match self.senders[worker].try_send(line) {
+                Err(TrySendError::Full(back)) if self.key.is_none() => {
+                    line = back;
+                    worker = self.worker_for(&line);
+                }
+                result => return result,
+            }
+        }
+        Err(TrySendError::Full(line))
+    }
+
+    /// Waits for the workers to process all queued lines and finish.
+    ///
+    /// Returns the payload of the first handler panic, if any.
+    pub fn join(self) -> thread::Result<()> {
+        drop(self.senders);
+        let mut result = Ok(());
+        for worker in self.workers {
+            let joined = worker.join();
+            if result.is_ok() {
+                result = joined;
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`DuctReader`], which reads the output of a
+//! [`duct`] expression, so that shell-like pipelines can be consumed
+//! line-by-line by a [`LineReader`].
+//!
+//! Requires the `duct` feature.
+
+use std::io::{self, Read};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+
+use crate::linereader::LineReader;
+
+/// Reader of the standard output of a running [`duct::Expression`].
+///
+/// Unlike [`duct::ReaderHandle`], it exposes the file descriptor of
+/// the pipe, so that it can be used in a non-blocking [`LineReader`]
+/// and polled along with other readers.
+///
+/// As with [`duct::ReaderHandle`], reaching EOF waits for the
+/// expression to finish, and a non-zero exit status is returned as an
+/// error unless the expression is
+/// [`unchecked`](duct::Expression::unchecked).
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lineriver::{LineRead, LineReader};
+///
+/// let expression = duct::cmd!("echo", "a\nb").pipe(duct::cmd!("sort", "-r"));
+/// let mut reader = LineReader::from_duct(&expression)?;
+/// let mut lines = vec![];
+/// while !reader.eof() {
+///     reader.read_available()?;
+///     lines.extend(reader.lines_get());
+/// }
+/// assert_eq!(lines, vec!["b\n", "a\n"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DuctReader {
+    reader: io::PipeReader,
+    handle: ::duct::Handle,
+}
+
+impl DuctReader {
+    /// Starts the expression with its standard output redirected to a
+    /// pipe read by the returned `DuctReader`.
+    pub fn start(expression: &::duct::Expression) -> Result<Self, io::Error> {
+        let (reader, writer) = io::pipe()?;
+        let handle = expression.stdout_file(writer).start()?;
+        Ok(Self { reader, handle })
+    }
+
+    /// Returns the [`duct::Handle`] of the running expression, which
+    /// can be used to kill it or check its status.
+    pub fn handle(&self) -> &::duct::Handle {
+        &self.handle
+    }
+}
+
+impl Read for DuctReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.reader.read(buf)?;
+        if len == 0 && !buf.is_empty() {
+            self.handle.wait()?;
+        }
+        Ok(len)
+    }
+}
+
+impl AsRawFd for DuctReader {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.reader.as_raw_fd()
+    }
+}
+
+impl AsFd for DuctReader {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.reader.as_fd()
+    }
+}
+
+impl LineReader<DuctReader> {
+    /// Starts a [`duct::Expression`] and creates a non-blocking
+    /// LineReader for its standard output, with [`DuctReader`].
+    pub fn from_duct(expression: &::duct::Expression) -> Result<Self, io::Error> {
+        LineReader::new(DuctReader::start(expression)?)
+    }
+}
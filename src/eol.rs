@@ -0,0 +1,110 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`EolDetector`], which detects the line-ending
+//! style of a stream.
+
+/// Number of line endings after which [`EolDetector`] stops looking.
+const EOL_SAMPLE: u64 = 128;
+
+/// Line-ending style of a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EolStyle {
+    /// Unix line endings: `\n`.
+    Lf,
+    /// DOS/network line endings: `\r\n`.
+    Crlf,
+    /// Classic Mac line endings: `\r`.
+    Cr,
+    /// More than one style.
+    Mixed,
+}
+
+/// Detects the line-ending style of a stream from its first line
+/// endings.
+///
+/// [`LineReader`](crate::LineReader) uses one to report the style of
+/// the stream it reads in
+/// [`LineReader::eol_style`](crate::LineReader::eol_style).
+///
+/// Example:
+///
+/// ```
+/// use lineriver::{EolDetector, EolStyle};
+///
+/// let mut detector = EolDetector::default();
+/// detector.feed(b"1\r\n2\r");
+/// detector.feed(b"\n");
+/// assert_eq!(detector.style(), Some(EolStyle::Crlf));
+/// detector.feed(b"3\n");
+/// assert_eq!(detector.style(), Some(EolStyle::Mixed));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EolDetector {
+    lf: u64,
+    crlf: u64,
+    cr: u64,
+    pending_cr: bool,
+}
+
+impl EolDetector {
+    /// Returns `true` once enough line endings were seen, after which
+    /// [`Self::feed`] doesn't look at the data anymore.
+    pub fn is_done(&self) -> bool {
+        self.lf + self.crlf + self.cr >= EOL_SAMPLE
+    }
+
+    /// Looks for line endings in the next chunk of data of the
+    /// stream.
+    pub fn feed(&mut self, data: &[u8]) {
+        if self.is_done() {
+            return;
+        }
+        for pos in memchr::memchr2_iter(b'\r', b'\n', data) {
+            if data[pos] == b'\n' {
+                let preceded_by_cr = if pos == 0 {
+                    self.pending_cr
+                } else {
+                    data[pos - 1] == b'\r'
+                };
+                if preceded_by_cr {
+                    self.crlf += 1;
+                } else {
+                    self.lf += 1;
+                }
+            } else {
+                match data.get(pos + 1) {
+                    Some(b'\n') => {}
+                    Some(_) => self.cr += 1,
+                    // Depends on the next chunk:
+                    None => {}
+                }
+            }
+        }
+        if let Some(last) = data.last() {
+            if self.pending_cr && data[0] != b'\n' {
+                self.cr += 1;
+            }
+            self.pending_cr = *last == b'\r';
+        }
+    }
+
+    /// Returns the style of the line endings seen so far, or `None` if
+    /// no line ending was seen yet.
+    pub fn style(&self) -> Option<EolStyle> {
+        match (self.lf > 0, self.crlf > 0, self.cr > 0) {
+            (false, false, false) => None,
+            (true, false, false) => Some(EolStyle::Lf),
+            (false, true, false) => Some(EolStyle::Crlf),
+            (false, false, true) => Some(EolStyle::Cr),
+            _ => Some(EolStyle::Mixed),
+        }
+    }
+
+    /// Returns the number of `\n`, `\r\n` and `\r` line endings seen,
+    /// in this order.
+    pub fn counts(&self) -> (u64, u64, u64) {
+        (self.lf, self.crlf, self.cr)
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`ReadError`], the error type that adds the
+//! identity of the source to the errors returned by readers, and
+//! [`LineTooLong`].
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::os::fd::RawFd;
+
+/// Error with the identity of the reader that returned it: label, file
+/// descriptor and peer address, when known.
+///
+/// [`LineReader`](crate::LineReader) returns [`io::Error`]s that wrap
+/// a `ReadError`, so that the error kind is kept; use
+/// [`ReadError::from_io`] to get to the context.
+#[derive(Debug)]
+pub struct ReadError {
+    pub(crate) label: Option<String>,
+    pub(crate) fd: Option<RawFd>,
+    pub(crate) peer: Option<String>,
+    pub(crate) source: io::Error,
+}
+
+impl ReadError {
+    /// Returns the `ReadError` wrapped by `err`, if there is one.
+    pub fn from_io(err: &io::Error) -> Option<&ReadError> {
+        err.get_ref()?.downcast_ref::<ReadError>()
+    }
+
+    /// Returns the label of the reader.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Returns the file descriptor of the reader.
+    pub fn fd(&self) -> Option<RawFd> {
+        self.fd
+    }
+
+    /// Returns the address of the peer of the reader.
+    pub fn peer(&self) -> Option<&str> {
+        self.peer.as_deref()
+    }
+
+    /// Returns the original error.
+    pub fn io_error(&self) -> &io::Error {
+        &self.source
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut context = vec![];
+        if let Some(label) = &self.label {
+            context.push(label.clone());
+        }
+        if let Some(fd) = self.fd {
+            context.push(format!("fd {}", fd));
+        }
+        if let Some(peer) = &self.peer {
+            context.push(format!("peer {}", peer));
+        }
+        write!(f, "{}: {}", context.join(", "), self.source)
+    }
+}
+
+impl Error for ReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Error returned by [`LineReader`](crate::LineReader) when a line is
+/// longer than the maximum set with
+/// [`with_max_line_len`](crate::LineReader::with_max_line_len).
+///
+/// It's wrapped in an [`io::Error`] of kind
+/// [`io::ErrorKind::InvalidData`]; use [`LineTooLong::from_io`] to get
+/// to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineTooLong {
+    pub(crate) max: usize,
+}
+
+impl LineTooLong {
+    /// Returns the `LineTooLong` wrapped by `err`, if there is one,
+    /// also looking inside a [`ReadError`].
+    pub fn from_io(err: &io::Error) -> Option<&LineTooLong> {
+        let inner = match ReadError::from_io(err) {
+            Some(context) => context.io_error(),
+            None => err,
+        };
+        inner.get_ref()?.downcast_ref::<LineTooLong>()
+    }
+
+    /// Returns the maximum line length that was exceeded.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+impl fmt::Display for LineTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line longer than {} bytes", self.max)
+    }
+}
+
+impl Error for LineTooLong {}
+
+impl From<LineTooLong> for io::Error {
+    fn from(err: LineTooLong) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
@@ -0,0 +1,305 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`Expect`], an expect-style session helper over a
+//! [`LineStream`].
+
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+use std::time::{Duration, Instant};
+
+use crate::blocking;
+use crate::lineread::LineRead;
+use crate::linestream::LineStream;
+
+/// Pattern that [`Expect::wait_for`] matches against lines.
+pub trait Pattern {
+    /// Returns `true` if `line` matches the pattern.
+    fn matches(&self, line: &str) -> bool;
+}
+
+/// Matches lines that contain the string.
+impl Pattern for &str {
+    fn matches(&self, line: &str) -> bool {
+        line.contains(*self)
+    }
+}
+
+/// Matches lines that contain the string.
+impl Pattern for String {
+    fn matches(&self, line: &str) -> bool {
+        line.contains(self.as_str())
+    }
+}
+
+/// Matches lines for which the function returns `true`.
+impl<F: Fn(&str) -> bool> Pattern for F {
+    fn matches(&self, line: &str) -> bool {
+        self(line)
+    }
+}
+
+/// Matches lines in which the regex matches.
+#[cfg(feature = "regex")]
+impl Pattern for regex::Regex {
+    fn matches(&self, line: &str) -> bool {
+        self.is_match(line)
+    }
+}
+
+/// Prompt that signals that the program is waiting for input, matched
+/// against the partial line by [`Expect::wait_for_prompt`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Prompt {
+    /// The partial line ends with the string, like `"$ "`.
+    Suffix(String),
+    /// The regex matches the partial line.
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Prompt {
+    /// Returns `true` if `partial` matches the prompt.
+    pub fn matches(&self, partial: &[u8]) -> bool {
+        match self {
+            Prompt::Suffix(suffix) => partial.ends_with(suffix.as_bytes()),
+            #[cfg(feature = "regex")]
+            Prompt::Regex(regex) => regex.is_match(&String::from_utf8_lossy(partial)),
+        }
+    }
+}
+
+/// Result of a successful [`Expect::wait_for`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectMatch {
+    /// Lines received before the matched one.
+    pub before: Vec<String>,
+    /// The line that matched.
+    pub matched: String,
+}
+
+/// Expect-style helper to automate line-based programs and devices:
+/// send a line, then wait for a line that matches a pattern.
+///
+/// Waiting is done with `poll(2)` on the descriptors of the stream,
+/// which must be non-blocking.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::process::{Command, Stdio};
+/// use std::time::Duration;
+/// use lineriver::{Expect, LineStream};
+///
+/// let mut child = Command::new("cat")
+///     .stdin(Stdio::piped())
+///     .stdout(Stdio::piped())
+///     .spawn()?;
+/// let stream = LineStream::new(child.stdout.take().unwrap(), child.stdin.take().unwrap())?;
+/// let mut expect = Expect::new(stream);
+/// expect.send_line("hello", Duration::from_secs(5))?;
+/// let found = expect.wait_for("hello", Duration::from_secs(5))?;
+/// assert_eq!(found.matched, "hello\n");
+/// # drop(expect);
+/// # child.wait()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Expect<R, W> {
+    stream: LineStream<R, W>,
+    lines: VecDeque<String>,
+    prompt: Option<Prompt>,
+    suppress_echo: bool,
+    echoes: VecDeque<String>,
+}
+
+impl<R: Read + AsRawFd + Debug, W: Write + AsRawFd> Expect<R, W> {
+    /// Creates a new session over `stream`.
+    pub fn new(stream: LineStream<R, W>) -> Self {
+        Self {
+            stream,
+            lines: VecDeque::new(),
+            prompt: None,
+            suppress_echo: false,
+            echoes: VecDeque::new(),
+        }
+    }
+
+    /// Drops the echoed copies of the lines sent, as produced by PTYs
+    /// and telnet, so that only the actual responses are returned.
+    ///
+    /// A received line is considered an echo if it is equal to the
+    /// oldest line sent and not yet echoed, ignoring line terminators
+    /// and a leading prompt.
+    pub fn with_echo_suppression(mut self, suppress_echo: bool) -> Self {
+        self.suppress_echo = suppress_echo;
+        self
+    }
+
+    /// Moves the lines from the stream to the session, dropping
+    /// echoes.
+    fn ingest(&mut self) {
+        for line in self.stream.lines_get() {
+            if self.is_echo(&line) {
+                self.echoes.pop_front();
+            } else {
+                self.lines.push_back(line);
+            }
+        }
+    }
+
+    fn is_echo(&self, line: &str) -> bool {
+        let Some(sent) = self.echoes.front() else {
+            return false;
+        };
+        let line = line.trim_end_matches(['\r', '\n']);
+        let Some(prefix) = line.strip_suffix(sent.as_str()) else {
+            return false;
+        };
+        prefix.is_empty()
+            || self
+                .prompt
+                .as_ref()
+                .is_some_and(|p| p.matches(prefix.as_bytes()))
+    }
+
+    /// Sets the prompt used by [`Self::wait_for_prompt`] and
+    /// [`Self::at_prompt`].
+    pub fn with_prompt(mut self, prompt: Prompt) -> Self {
+        self.prompt = Some(prompt);
+        self
+    }
+
+    /// Returns `true` if the partial line matches the prompt, which
+    /// means the program is waiting for input.
+    pub fn at_prompt(&self) -> bool {
+        self.prompt
+            .as_ref()
+            .is_some_and(|p| p.matches(self.stream.reader().partial_line()))
+    }
+
+    /// Waits at most `timeout` for the prompt, returning the complete
+    /// lines received before it.
+    ///
+    /// The prompt itself stays in the partial line, so it ends up
+    /// prefixing the next line, usually the echo of the next command.
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if no
+    /// prompt was set, and the same errors as [`Self::wait_for`]
+    /// otherwise.
+    pub fn wait_for_prompt(&mut self, timeout: Duration) -> Result<Vec<String>, io::Error> {
+        if self.prompt.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no prompt configured",
+            ));
+        }
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            self.ingest();
+            if self.at_prompt() {
+                return Ok(self.lines.drain(..).collect());
+            }
+            if self.stream.eof() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            blocking::wait_readable(self.stream.as_raw_fd(), remaining(deadline)?)?;
+            self.stream.read_once()?;
+        }
+    }
+
+    /// Sends a line, waiting at most `timeout` for it to be written.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::TimedOut`] if the line
+    /// could not be written in time; it stays queued in the stream.
+    pub fn send_line(&mut self, line: &str, timeout: Duration) -> Result<(), io::Error> {
+        let deadline = Instant::now().checked_add(timeout);
+        self.stream.write_line(line)?;
+        if self.suppress_echo {
+            self.echoes
+                .push_back(line.trim_end_matches(['\r', '\n']).to_string());
+        }
+        while !self.stream.flush()? {
+            let fd = self.stream.writer().as_raw_fd();
+            blocking::wait_writable(fd, remaining(deadline)?)?;
+        }
+        Ok(())
+    }
+
+    /// Waits at most `timeout` for a line that matches `pattern`,
+    /// returning it along with the lines received before it.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::TimedOut`] on timeout,
+    /// or [`io::ErrorKind::UnexpectedEof`] if the stream reaches EOF
+    /// without a match. In both cases the lines received are kept for
+    /// the next call.
+    pub fn wait_for<P: Pattern>(
+        &mut self,
+        pattern: P,
+        timeout: Duration,
+    ) -> Result<ExpectMatch, io::Error> {
+        let deadline = Instant::now().checked_add(timeout);
+        self.ingest();
+        let mut checked = 0;
+        loop {
+            if let Some(pos) = self
+                .lines
+                .iter()
+                .skip(checked)
+                .position(|l| pattern.matches(l))
+            {
+                let mut before: Vec<String> = self.lines.drain(..checked + pos + 1).collect();
+                let matched = before.pop().unwrap_or_default();
+                return Ok(ExpectMatch { before, matched });
+            }
+            checked = self.lines.len();
+            if self.stream.eof() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            blocking::wait_readable(self.stream.as_raw_fd(), remaining(deadline)?)?;
+            self.stream.read_once()?;
+            self.ingest();
+        }
+    }
+
+    /// Takes the lines received but not consumed by
+    /// [`Self::wait_for`].
+    pub fn lines_get(&mut self) -> Vec<String> {
+        self.ingest();
+        self.lines.drain(..).collect()
+    }
+
+    /// Gets a reference to the underlying stream.
+    pub fn get_ref(&self) -> &LineStream<R, W> {
+        &self.stream
+    }
+
+    /// Gets a mutable reference to the underlying stream.
+    pub fn get_mut(&mut self) -> &mut LineStream<R, W> {
+        &mut self.stream
+    }
+
+    /// Returns the underlying stream, dropping the lines not consumed.
+    pub fn into_inner(self) -> LineStream<R, W> {
+        self.stream
+    }
+}
+
+/// Returns the time left until `deadline`, or an error of kind
+/// [`io::ErrorKind::TimedOut`] if it has passed; `None` is a deadline
+/// too far away to be represented, which never passes.
+fn remaining(deadline: Option<Instant>) -> Result<Duration, io::Error> {
+    let Some(deadline) = deadline else {
+        return Ok(Duration::MAX);
+    };
+    let now = Instant::now();
+    if now >= deadline {
+        return Err(io::ErrorKind::TimedOut.into());
+    }
+    Ok(deadline - now)
+}
@@ -0,0 +1,708 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has the [`LineReadExt`] extension trait, with
+//! combinators that wrap [`LineRead`] objects.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::time::{Duration, SystemTime};
+
+use crate::lineread::{FullLatch, LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+use crate::timestamp::TimestampFormat;
+
+/// Extension trait with chainable combinators for [`LineRead`]
+/// objects.
+///
+/// Each combinator returns a wrapper that also implements
+/// [`LineRead`], so that line pipelines can be built like iterator
+/// adapters:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lineriver::testing::ScriptedReader;
+/// use lineriver::{LineRead, LineReadExt, LineReader};
+///
+/// let script = ScriptedReader::new().data(b"a\n#b\nc\nd\n");
+/// let mut reader = LineReader::from_nonblocking(script)?
+///     .filter_lines(|line| !line.starts_with('#'))
+///     .map_lines(|line| line.to_uppercase())
+///     .take_lines(2)
+///     .tag("upper");
+/// reader.read_available()?;
+/// assert_eq!(reader.lines_get(), vec!["A\n", "C\n"]);
+/// assert!(reader.eof());
+/// assert_eq!(reader.label(), Some("upper"));
+/// # Ok(())
+/// # }
+/// ```
+pub trait LineReadExt: LineRead + Sized {
+    /// Transforms each line with `f`.
+    fn map_lines<F>(self, f: F) -> MapLines<Self, F>
+    where
+        F: FnMut(String) -> String,
+    {
+        MapLines { inner: self, f }
+    }
+
+    /// Keeps only the lines for which `predicate` returns `true`.
+    fn filter_lines<P>(self, predicate: P) -> FilterLines<Self, P>
+    where
+        P: FnMut(&str) -> bool,
+    {
+        FilterLines {
+            inner: self,
+            predicate,
+            lines: VecDeque::new(),
+            full: FullLatch::default(),
+        }
+    }
+
+    /// Sets the label returned by [`LineRead::label`].
+    fn tag(self, label: impl Into<String>) -> Tag<Self> {
+        Tag {
+            inner: self,
+            label: label.into(),
+        }
+    }
+
+    /// Reads from both `self` and `other`, returning the lines of
+    /// both; EOF is reached when both reach it.
+    ///
+    /// Lines come out in arrival order by default; see
+    /// [`Merge::with_time_order`] to sort them by their timestamps.
+    fn merge<L: LineRead>(self, other: L) -> Merge<Self, L> {
+        Merge {
+            first: self,
+            second: other,
+            order: None,
+        }
+    }
+
+    /// Reads lines from `self` until EOF, and then from `next`.
+    ///
+    /// Like [`std::io::Read::chain`], the streams are concatenated: if
+    /// the last line of `self` doesn't end with a newline, it is joined
+    /// with the first line of `next`.
+    fn chain<L: LineRead>(self, next: L) -> Chain<Self, L> {
+        Chain {
+            first: self,
+            second: next,
+            carry: None,
+            lines: VecDeque::new(),
+            full: FullLatch::default(),
+        }
+    }
+
+    /// Returns at most `n` lines, reaching EOF after that.
+    fn take_lines(self, n: usize) -> TakeLines<Self> {
+        TakeLines {
+            inner: self,
+            remaining: n,
+            lines: VecDeque::new(),
+            full: FullLatch::default(),
+        }
+    }
+}
+
+impl<L: LineRead> LineReadExt for L {}
+
+/// [`LineRead`] wrapper returned by [`LineReadExt::map_lines`].
+pub struct MapLines<L, F> {
+    inner: L,
+    f: F,
+}
+
+impl<L, F> MapLines<L, F> {
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+}
+
+impl<L: LineRead, F: FnMut(String) -> String> LineRead for MapLines<L, F> {
+    fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        self.inner.read_once()
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.inner
+            .lines_get()
+            .into_iter()
+            .map(&mut self.f)
+            .collect()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.inner.pop_line().map(&mut self.f)
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.inner.has_lines()
+    }
+
+    fn is_full(&self) -> bool {
+        self.inner.is_full()
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: fmt::Debug, F> fmt::Debug for MapLines<L, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapLines")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<L: AsRawFd, F> AsRawFd for MapLines<L, F> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd, F> AsFd for MapLines<L, F> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd, F: FnMut(String) -> String> LineReadRawFd for MapLines<L, F> {}
+
+impl<L: LineRead + AsFd, F: FnMut(String) -> String> LineReadFd for MapLines<L, F> {}
+
+impl<L: LineRead + AsFd + AsRawFd, F: FnMut(String) -> String> LineReadRawAndFd for MapLines<L, F> {}
+
+/// [`LineRead`] wrapper returned by [`LineReadExt::filter_lines`].
+pub struct FilterLines<L, P> {
+    inner: L,
+    predicate: P,
+    lines: VecDeque<String>,
+    full: FullLatch,
+}
+
+impl<L, P> FilterLines<L, P> {
+    /// Returns the underlying reader, dropping the lines that were not
+    /// retrieved yet.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+}
+
+impl<L: LineRead, P: FnMut(&str) -> bool> FilterLines<L, P> {
+    /// Moves the matching lines from the inner reader to our buffer.
+    fn pull(&mut self) {
+        self.full
+            .update(self.inner.is_full(), !self.lines.is_empty());
+        let predicate = &mut self.predicate;
+        self.lines.extend(
+            self.inner
+                .lines_get()
+                .into_iter()
+                .filter(|line| predicate(line)),
+        );
+    }
+}
+
+impl<L: LineRead, P: FnMut(&str) -> bool> LineRead for FilterLines<L, P> {
+    fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        let result = self.inner.read_once();
+        self.pull();
+        result
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.pull();
+        mem::take(&mut self.lines).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        self.lines.pop_front()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.pull();
+        !self.lines.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.full
+            .is_full(self.inner.is_full(), !self.lines.is_empty())
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: fmt::Debug, P> fmt::Debug for FilterLines<L, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilterLines")
+            .field("inner", &self.inner)
+            .field("lines", &self.lines)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<L: AsRawFd, P> AsRawFd for FilterLines<L, P> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd, P> AsFd for FilterLines<L, P> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd, P: FnMut(&str) -> bool> LineReadRawFd for FilterLines<L, P> {}
+
+impl<L: LineRead + AsFd, P: FnMut(&str) -> bool> LineReadFd for FilterLines<L, P> {}
+
+impl<L: LineRead + AsFd + AsRawFd, P: FnMut(&str) -> bool> LineReadRawAndFd for FilterLines<L, P> {}
+
+/// [`LineRead`] wrapper returned by [`LineReadExt::tag`].
+#[derive(Debug)]
+pub struct Tag<L> {
+    inner: L,
+    label: String,
+}
+
+impl<L> Tag<L> {
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+}
+
+impl<L: LineRead> LineRead for Tag<L> {
+    fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        self.inner.read_once()
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.inner.lines_get()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.inner.pop_line()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.inner.has_lines()
+    }
+
+    fn is_full(&self) -> bool {
+        self.inner.is_full()
+    }
+
+    fn label(&self) -> Option<&str> {
+        Some(&self.label)
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: AsRawFd> AsRawFd for Tag<L> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd> AsFd for Tag<L> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd> LineReadRawFd for Tag<L> {}
+
+impl<L: LineRead + AsFd> LineReadFd for Tag<L> {}
+
+impl<L: LineRead + AsFd + AsRawFd> LineReadRawAndFd for Tag<L> {}
+
+/// [`LineRead`] wrapper returned by [`LineReadExt::merge`].
+///
+/// It has no file descriptor of its own; it is meant to be driven by
+/// a loop that calls [`LineRead::read_once`] when either reader may
+/// have data.
+#[derive(Debug)]
+pub struct Merge<A, B> {
+    first: A,
+    second: B,
+    order: Option<TimeOrder>,
+}
+
+/// State of the time-ordered mode of [`Merge`].
+#[derive(Debug)]
+struct TimeOrder {
+    format: TimestampFormat,
+    window: Duration,
+    /// Lines waiting for the window to pass, sorted by timestamp and
+    /// then by arrival.
+    held: Vec<(SystemTime, String)>,
+    /// Timestamp of the last line of each source, inherited by the
+    /// lines that don't have one.
+    last: [Option<SystemTime>; 2],
+    /// Most recent timestamp seen in any source.
+    newest: Option<SystemTime>,
+    /// Lines ready to be returned.
+    lines: VecDeque<String>,
+    full: FullLatch,
+}
+
+impl TimeOrder {
+    fn push(&mut self, source: usize, lines: Vec<String>) {
+        for line in lines {
+            let Some(time) = self.format.parse_prefix(&line).or(self.last[source]) else {
+                // Nothing to order by yet: let it through.
+                self.lines.push_back(line);
+                continue;
+            };
+            self.last[source] = Some(time);
+            self.newest = self.newest.max(Some(time));
+            let pos = self.held.partition_point(|(t, _)| *t <= time);
+            self.held.insert(pos, (time, line));
+        }
+    }
+
+    fn release(&mut self, all: bool) {
+        let limit = self
+            .newest
+            .and_then(|newest| newest.checked_sub(self.window));
+        let count = match limit {
+            _ if all => self.held.len(),
+            Some(limit) => self.held.partition_point(|(t, _)| *t <= limit),
+            None => 0,
+        };
+        self.lines
+            .extend(self.held.drain(..count).map(|(_, line)| line));
+    }
+}
+
+impl<A, B> Merge<A, B> {
+    /// Returns the lines of both readers ordered by the timestamps
+    /// at their beginning, in the given format, instead of by arrival.
+    ///
+    /// Lines are held until a line at least `window` newer shows up
+    /// in either reader, which allows each source to be up to
+    /// `window` late relative to the others. Everything is released
+    /// when both readers reach EOF. Lines without a timestamp take
+    /// the one of the previous line of the same reader, so that
+    /// multi-line messages are kept together; those at the beginning
+    /// of a reader are returned right away.
+    pub fn with_time_order(mut self, format: TimestampFormat, window: Duration) -> Self {
+        self.order = Some(TimeOrder {
+            format,
+            window,
+            held: Vec::new(),
+            last: [None, None],
+            newest: None,
+            lines: VecDeque::new(),
+            full: FullLatch::default(),
+        });
+        self
+    }
+
+    /// Returns the two underlying readers, dropping the lines held
+    /// by the time-ordered mode.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: LineRead, B: LineRead> Merge<A, B> {
+    /// Moves lines from the underlying readers to the time-ordered
+    /// buffer and releases the ones that are past the window.
+    fn pull(&mut self) {
+        if let Some(order) = &mut self.order {
+            let inner_full = self.first.is_full() || self.second.is_full();
+            order.full.update(inner_full, !order.lines.is_empty());
+            order.push(0, self.first.lines_get());
+            order.push(1, self.second.lines_get());
+            order.release(self.first.eof() && self.second.eof());
+        }
+    }
+}
+
+impl<A: LineRead, B: LineRead> LineRead for Merge<A, B> {
+    fn eof(&self) -> bool {
+        self.first.eof() && self.second.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        let first = self.first.read_once()?;
+        let second = self.second.read_once()?;
+        self.pull();
+        Ok(first || second)
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.pull();
+        if let Some(order) = &mut self.order {
+            return mem::take(&mut order.lines).into();
+        }
+        let mut lines = self.first.lines_get();
+        lines.extend(self.second.lines_get());
+        lines
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        if let Some(order) = &mut self.order {
+            return order.lines.pop_front();
+        }
+        self.first.pop_line().or_else(|| self.second.pop_line())
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.pull();
+        if let Some(order) = &self.order {
+            return !order.lines.is_empty();
+        }
+        self.first.has_lines() || self.second.has_lines()
+    }
+
+    fn is_full(&self) -> bool {
+        let inner_full = self.first.is_full() || self.second.is_full();
+        match &self.order {
+            Some(order) => order.full.is_full(inner_full, !order.lines.is_empty()),
+            None => inner_full,
+        }
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.first.label().or_else(|| self.second.label())
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.first.is_terminated(line) || self.second.is_terminated(line)
+    }
+}
+
+/// [`LineRead`] wrapper returned by [`LineReadExt::chain`].
+///
+/// It has no file descriptor of its own, as the reader being read
+/// changes when the first one reaches EOF.
+#[derive(Debug)]
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+    carry: Option<String>,
+    lines: VecDeque<String>,
+    full: FullLatch,
+}
+
+impl<A, B> Chain<A, B> {
+    /// Returns the two underlying readers, dropping the lines that
+    /// were not retrieved yet.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: LineRead, B: LineRead> Chain<A, B> {
+    /// Moves lines from the underlying readers to our buffer, holding
+    /// back the partial last line of the first one.
+    fn pull(&mut self) {
+        let inner_full = self.first.is_full() || self.second.is_full();
+        self.full.update(inner_full, !self.lines.is_empty());
+        let mut lines = self.first.lines_get();
+        if self.first.eof()
+            && lines
+                .last()
+                .is_some_and(|line| !self.first.is_terminated(line))
+        {
+            self.carry = lines.pop();
+        }
+        self.lines.extend(lines);
+        let mut lines = self.second.lines_get();
+        if !lines.is_empty() || self.second.eof() {
+            if let Some(mut carry) = self.carry.take() {
+                match lines.first_mut() {
+                    Some(first) => {
+                        carry.push_str(first);
+                        *first = carry;
+                    }
+                    None => lines.push(carry),
+                }
+            }
+        }
+        self.lines.extend(lines);
+    }
+}
+
+impl<A: LineRead, B: LineRead> LineRead for Chain<A, B> {
+    fn eof(&self) -> bool {
+        self.first.eof() && self.second.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        let result = if self.first.eof() {
+            self.second.read_once()
+        } else {
+            self.first.read_once().map(|_| true)
+        };
+        self.pull();
+        result
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.pull();
+        mem::take(&mut self.lines).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        self.lines.pop_front()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.pull();
+        !self.lines.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.full.is_full(
+            self.first.is_full() || self.second.is_full(),
+            !self.lines.is_empty(),
+        )
+    }
+
+    fn label(&self) -> Option<&str> {
+        if self.first.eof() {
+            self.second.label()
+        } else {
+            self.first.label()
+        }
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.second.is_terminated(line)
+    }
+}
+
+/// [`LineRead`] wrapper returned by [`LineReadExt::take_lines`].
+#[derive(Debug)]
+pub struct TakeLines<L> {
+    inner: L,
+    remaining: usize,
+    lines: VecDeque<String>,
+    full: FullLatch,
+}
+
+impl<L> TakeLines<L> {
+    /// Returns the underlying reader, dropping the lines that were not
+    /// retrieved yet.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+}
+
+impl<L: LineRead> TakeLines<L> {
+    /// Moves up to `remaining` lines from the inner reader to our
+    /// buffer.
+    fn pull(&mut self) {
+        self.full
+            .update(self.inner.is_full(), !self.lines.is_empty());
+        if self.remaining == 0 {
+            return;
+        }
+        let mut lines = self.inner.lines_get();
+        lines.truncate(self.remaining);
+        self.remaining -= lines.len();
+        self.lines.extend(lines);
+    }
+}
+
+impl<L: LineRead> LineRead for TakeLines<L> {
+    fn eof(&self) -> bool {
+        self.remaining == 0 || self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        if self.remaining == 0 {
+            return Ok(false);
+        }
+        let result = self.inner.read_once();
+        self.pull();
+        result.map(|more| more && self.remaining > 0)
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.pull();
+        mem::take(&mut self.lines).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        self.lines.pop_front()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.pull();
+        !self.lines.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.full
+            .is_full(self.inner.is_full(), !self.lines.is_empty())
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: AsRawFd> AsRawFd for TakeLines<L> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd> AsFd for TakeLines<L> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd> LineReadRawFd for TakeLines<L> {}
+
+impl<L: LineRead + AsFd> LineReadFd for TakeLines<L> {}
+
+impl<L: LineRead + AsFd + AsRawFd> LineReadRawAndFd for TakeLines<L> {}
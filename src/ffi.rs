@@ -0,0 +1,173 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has the C bindings of [`LineReader`], enabled by the
+//! `ffi` feature.
+//!
+//! A shared library can be built with:
+//!
+//! ```sh
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! The corresponding C declarations are:
+//!
+//! ```c
+//! typedef struct lineriver_reader lineriver_reader;
+//! lineriver_reader *lineriver_create(int fd);
+//! int lineriver_read_available(lineriver_reader *reader);
+//! int lineriver_next_line(lineriver_reader *reader, const char **line, size_t *len);
+//! int lineriver_eof(const lineriver_reader *reader);
+//! void lineriver_destroy(lineriver_reader *reader);
+//! ```
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::os::fd::{FromRawFd, RawFd};
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use crate::blocking;
+use crate::lineread::LineRead;
+use crate::linereader::LineReader;
+
+/// Opaque handle used by the C bindings.
+#[derive(Debug)]
+pub struct LineriverReader {
+    reader: LineReader<File>,
+    pending: VecDeque<String>,
+    current: Vec<u8>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+unsafe fn set_errno(errno: c_int) {
+    *libc::__errno_location() = errno;
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+unsafe fn set_errno(errno: c_int) {
+    *libc::__error() = errno;
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd"
+)))]
+unsafe fn set_errno(_errno: c_int) {}
+
+/// Creates a reader that takes ownership of the file descriptor `fd`,
+/// setting it as non-blocking.
+///
+/// Returns a null pointer on error, with `errno` set; `fd` is then
+/// left open, and still owned by the caller.
+///
+/// # Safety
+///
+/// `fd` must be an open file descriptor not owned by anything else; on
+/// success, it is closed by [`lineriver_destroy`].
+#[no_mangle]
+pub unsafe extern "C" fn lineriver_create(fd: c_int) -> *mut LineriverReader {
+    // Done before taking ownership, so that a failure doesn't close
+    // the descriptor:
+    if let Err(err) = blocking::disable(fd as RawFd) {
+        if let Some(errno) = err.raw_os_error() {
+            set_errno(errno);
+        }
+        return ptr::null_mut();
+    }
+    let file = File::from_raw_fd(fd as RawFd);
+    let reader = match LineReader::new(file) {
+        Ok(reader) => reader,
+        // Can't fail, the descriptor is already non-blocking:
+        Err(_) => return ptr::null_mut(),
+    };
+    Box::into_raw(Box::new(LineriverReader {
+        reader,
+        pending: VecDeque::new(),
+        current: Vec::new(),
+    }))
+}
+
+/// Reads all available data.
+///
+/// Returns 1 if the file descriptor can still have more data, 0 if it
+/// reached EOF, and -1 on error, with `errno` set when the error came
+/// from the operating system.
+///
+/// # Safety
+///
+/// `reader` must be a pointer returned by [`lineriver_create`] that was
+/// not destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn lineriver_read_available(reader: *mut LineriverReader) -> c_int {
+    let handle = &mut *reader;
+    match handle.reader.read_available() {
+        Ok(()) => c_int::from(!handle.reader.eof()),
+        Err(err) => {
+            if let Some(errno) = err.raw_os_error() {
+                set_errno(errno);
+            }
+            -1
+        }
+    }
+}
+
+/// Gets the next complete line, including the newline.
+///
+/// Returns 1 and sets `line` and `len` if a line is available, 0
+/// otherwise. The line is also NUL-terminated, and stays valid until
+/// the next call to this function or to [`lineriver_destroy`].
+///
+/// # Safety
+///
+/// `reader` must be a pointer returned by [`lineriver_create`] that was
+/// not destroyed, and `line` and `len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn lineriver_next_line(
+    reader: *mut LineriverReader,
+    line: *mut *const c_char,
+    len: *mut usize,
+) -> c_int {
+    let handle = &mut *reader;
+    if handle.pending.is_empty() {
+        handle.pending.extend(handle.reader.lines_get());
+    }
+    let Some(next) = handle.pending.pop_front() else {
+        return 0;
+    };
+    handle.current = next.into_bytes();
+    *len = handle.current.len();
+    handle.current.push(0);
+    *line = handle.current.as_ptr() as *const c_char;
+    1
+}
+
+/// Returns 1 if the reader reached EOF, 0 otherwise.
+///
+/// Lines may still be available after EOF.
+///
+/// # Safety
+///
+/// `reader` must be a pointer returned by [`lineriver_create`] that was
+/// not destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn lineriver_eof(reader: *const LineriverReader) -> c_int {
+    c_int::from((*reader).reader.eof())
+}
+
+/// Destroys the reader, closing its file descriptor.
+///
+/// # Safety
+///
+/// `reader` must be a pointer returned by [`lineriver_create`] that was
+/// not destroyed, or null.
+#[no_mangle]
+pub unsafe extern "C" fn lineriver_destroy(reader: *mut LineriverReader) {
+    if !reader.is_null() {
+        drop(Box::from_raw(reader));
+    }
+}
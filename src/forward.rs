@@ -0,0 +1,246 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineForwarder`], a sink that ships lines to a
+//! remote syslog or plain TCP endpoint without blocking.
+
+use std::collections::VecDeque;
+use std::ffi::CStr;
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::time::SystemTime;
+
+use crate::lineread::LineRead;
+use crate::tcp::TcpOptions;
+use crate::timestamp;
+
+/// RFC 5424 header fields used by [`LineForwarder::with_syslog`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyslogConfig {
+    /// Facility code, `1` (user-level) by default.
+    pub facility: u8,
+    /// Severity code, `6` (informational) by default.
+    pub severity: u8,
+    /// Hostname, the one of this machine by default.
+    pub hostname: String,
+    /// Application name, `-` (nil) by default.
+    pub app_name: String,
+    /// Process ID, the one of this process by default.
+    pub procid: String,
+}
+
+impl Default for SyslogConfig {
+    fn default() -> Self {
+        let mut buf = [0 as libc::c_char; 256];
+        let hostname = unsafe {
+            if libc::gethostname(buf.as_mut_ptr(), buf.len() - 1) == 0 {
+                CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned()
+            } else {
+                "-".to_string()
+            }
+        };
+        Self {
+            facility: 1,
+            severity: 6,
+            hostname,
+            app_name: "-".to_string(),
+            procid: std::process::id().to_string(),
+        }
+    }
+}
+
+impl SyslogConfig {
+    /// Formats `line` as an RFC 5424 message.
+    fn format(&self, line: &str) -> String {
+        format!(
+            "<{}>1 {} {} {} {} - - {}",
+            self.facility as u32 * 8 + self.severity as u32,
+            timestamp::format_rfc3339(SystemTime::now()),
+            self.hostname,
+            self.app_name,
+            self.procid,
+            line.trim_end_matches(['\n', '\r'])
+        )
+    }
+}
+
+#[derive(Debug)]
+enum Transport {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+/// Sink that ships lines to a remote endpoint without blocking.
+///
+/// The endpoint can be a plain TCP server, which gets the lines as
+/// they are, or a syslog server over TCP or UDP, which gets RFC 5424
+/// messages. Over TCP, syslog messages use the octet-counting framing
+/// of RFC 6587; over UDP, each message is a datagram.
+///
+/// Lines that can't be sent right away are queued and sent by the next
+/// [`Self::flush`]. Together with a [`LineRead`] source and the
+/// filters in this crate, this makes a read-filter-forward pipeline.
+#[derive(Debug)]
+pub struct LineForwarder {
+    transport: Transport,
+    syslog: Option<SyslogConfig>,
+    pending: VecDeque<Vec<u8>>,
+    written: usize,
+}
+
+impl LineForwarder {
+    /// Connects to a TCP endpoint.
+    ///
+    /// The connection is established in blocking mode, and then the
+    /// socket is set as non-blocking.
+    pub fn tcp<A: ToSocketAddrs>(addr: A) -> Result<Self, io::Error> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nonblocking(true)?;
+        Ok(Self::new(Transport::Tcp(stream)))
+    }
+
+    /// Connects to a TCP endpoint with the given socket options.
+    ///
+    /// Like [`Self::tcp`], the connection is established in blocking
+    /// mode, and then the socket is set as non-blocking.
+    pub fn tcp_with_options<A: ToSocketAddrs>(
+        addr: A,
+        options: &TcpOptions,
+    ) -> Result<Self, io::Error> {
+        let stream = options.connect(addr)?;
+        stream.set_nonblocking(true)?;
+        Ok(Self::new(Transport::Tcp(stream)))
+    }
+
+    /// Creates a non-blocking UDP socket that sends datagrams to
+    /// `addr`.
+    ///
+    /// UDP is only useful for syslog, so [`Self::with_syslog`] should
+    /// be used.
+    pub fn udp<A: ToSocketAddrs>(addr: A) -> Result<Self, io::Error> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address"))?;
+        let bind = if addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind)?;
+        socket.connect(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self::new(Transport::Udp(socket)))
+    }
+
+    fn new(transport: Transport) -> Self {
+        Self {
+            transport,
+            syslog: None,
+            pending: Default::default(),
+            written: 0,
+        }
+    }
+
+    /// Sends the lines as RFC 5424 syslog messages with the given
+    /// header fields.
+    pub fn with_syslog(mut self, config: SyslogConfig) -> Self {
+        self.syslog = Some(config);
+        self
+    }
+
+    /// Queues a line and tries to send everything that is pending.
+    pub fn send_line(&mut self, line: &str) -> Result<(), io::Error> {
+        self.queue(line);
+        self.flush()?;
+        Ok(())
+    }
+
+    /// Formats a line and adds it to the pending data.
+    fn queue(&mut self, line: &str) {
+        let message = match (&self.syslog, &self.transport) {
+            (Some(syslog), Transport::Tcp(_)) => {
+                let message = syslog.format(line);
+                format!("{} {}", message.len(), message)
+            }
+            (Some(syslog), Transport::Udp(_)) => syslog.format(line),
+            (None, _) if line.ends_with('\n') => line.to_string(),
+            (None, _) => format!("{}\n", line),
+        };
+        self.pending.push_back(message.into_bytes());
+    }
+
+    /// Takes the lines available in `linereader` and sends them,
+    /// returning how many lines were taken.
+    ///
+    /// All lines are queued before sending, so that they are kept if
+    /// sending fails, and sent by the next [`Self::flush`].
+    pub fn forward<L: LineRead + ?Sized>(
+        &mut self,
+        linereader: &mut L,
+    ) -> Result<usize, io::Error> {
+        let lines = linereader.lines_get();
+        for line in &lines {
+            self.queue(line);
+        }
+        self.flush()?;
+        Ok(lines.len())
+    }
+
+    /// Sends as much of the pending data as possible without
+    /// blocking.
+    ///
+    /// Returns `true` if there is no more pending data, and an
+    /// [`io::ErrorKind::WriteZero`] error if the TCP stream accepts no
+    /// bytes, keeping the data pending.
+    pub fn flush(&mut self) -> Result<bool, io::Error> {
+        while let Some(message) = self.pending.front() {
+            let result = match &mut self.transport {
+                Transport::Tcp(stream) => stream.write(&message[self.written..]),
+                Transport::Udp(socket) => socket.send(message),
+            };
+            match result {
+                Ok(0) if matches!(self.transport, Transport::Tcp(_)) => {
+                    return Err(io::ErrorKind::WriteZero.into())
+                }
+                Ok(len) => {
+                    self.written += len;
+                    if matches!(self.transport, Transport::Udp(_)) || self.written == message.len()
+                    {
+                        self.pending.pop_front();
+                        self.written = 0;
+                    }
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Returns the number of bytes waiting to be sent.
+    pub fn pending_bytes(&self) -> usize {
+        self.pending.iter().map(Vec::len).sum::<usize>() - self.written
+    }
+}
+
+impl AsRawFd for LineForwarder {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        match &self.transport {
+            Transport::Tcp(stream) => stream.as_raw_fd(),
+            Transport::Udp(socket) => socket.as_raw_fd(),
+        }
+    }
+}
+
+impl AsFd for LineForwarder {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        match &self.transport {
+            Transport::Tcp(stream) => stream.as_fd(),
+            Transport::Udp(socket) => socket.as_fd(),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineGrep`], a [`LineRead`] wrapper that only
+//! returns the lines that match a set of regular expressions.
+//!
+//! Requires the `regex` feature.
+
+use std::collections::VecDeque;
+use std::io;
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+
+use regex::Regex;
+
+use crate::lineread::{FullLatch, LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+
+/// A line that matched one of the regular expressions of a
+/// [`LineGrep`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepMatch {
+    /// The complete line.
+    pub line: String,
+    /// Index of the first regular expression that matched the line.
+    pub regex: usize,
+    /// Capture groups of the regular expression; the first one is
+    /// the whole match.
+    pub groups: Vec<Option<String>>,
+}
+
+/// [`LineRead`] wrapper that only returns the lines that match at
+/// least one of a set of regular expressions, akin to `tail | grep`.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lineriver::testing::ScriptedReader;
+/// use lineriver::{LineGrep, LineRead, LineReader};
+///
+/// let script = ScriptedReader::new().data(b"info: a\nerror: b\n");
+/// let linereader = LineReader::from_nonblocking(script)?;
+/// let mut grep = LineGrep::new(linereader, regex::Regex::new("^error")?);
+/// grep.read_once()?;
+/// assert_eq!(grep.lines_get(), vec!["error: b\n"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LineGrep<L> {
+    inner: L,
+    regexes: Vec<Regex>,
+    matches: VecDeque<(String, usize)>,
+    full: FullLatch,
+}
+
+impl<L: LineRead> LineGrep<L> {
+    /// Creates a new `LineGrep` that returns the lines of `inner`
+    /// that match `regex`.
+    pub fn new(inner: L, regex: Regex) -> Self {
+        Self::with_regexes(inner, [regex])
+    }
+
+    /// Creates a new `LineGrep` that returns the lines of `inner`
+    /// that match any one of `regexes`.
+    pub fn with_regexes(inner: L, regexes: impl IntoIterator<Item = Regex>) -> Self {
+        Self {
+            inner,
+            regexes: regexes.into_iter().collect(),
+            matches: Default::default(),
+            full: FullLatch::default(),
+        }
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &L {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut L {
+        &mut self.inner
+    }
+
+    /// Returns the underlying reader, dropping the lines that were
+    /// already filtered.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+
+    /// Returns the matching lines along with their capture groups.
+    ///
+    /// Like [`LineRead::lines_get`], this transfers the lines to the
+    /// caller. Capture groups are only computed here, so there is no
+    /// cost for callers that just use `lines_get`.
+    pub fn matches_get(&mut self) -> Vec<GrepMatch> {
+        self.pull();
+        mem::take(&mut self.matches)
+            .into_iter()
+            .map(|(line, regex)| {
+                let groups = self.regexes[regex]
+                    .captures(&line)
+                    .map(|c| {
+                        c.iter()
+                            .map(|m| m.map(|m| m.as_str().to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                GrepMatch {
+                    line,
+                    regex,
+                    groups,
+                }
+            })
+            .collect()
+    }
+
+    /// Moves the matching lines from the inner reader to our buffer.
+    fn pull(&mut self) {
+        self.full
+            .update(self.inner.is_full(), !self.matches.is_empty());
+        for line in self.inner.lines_get() {
+            if let Some(regex) = self.regexes.iter().position(|r| r.is_match(&line)) {
+                self.matches.push_back((line, regex));
+            }
+        }
+    }
+}
+
+impl<L: LineRead> LineRead for LineGrep<L> {
+    fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        let result = self.inner.read_once();
+        self.pull();
+        result
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.pull();
+        mem::take(&mut self.matches)
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        self.matches.pop_front().map(|(line, _)| line)
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.pull();
+        !self.matches.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.full
+            .is_full(self.inner.is_full(), !self.matches.is_empty())
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: AsRawFd> AsRawFd for LineGrep<L> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd> AsFd for LineGrep<L> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd> LineReadRawFd for LineGrep<L> {}
+
+impl<L: LineRead + AsFd> LineReadFd for LineGrep<L> {}
+
+impl<L: LineRead + AsFd + AsRawFd> LineReadRawAndFd for LineGrep<L> {}
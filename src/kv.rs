@@ -0,0 +1,111 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`kv_pairs`], a zero-copy parser for the
+//! `key=value` pairs found in structured-ish log lines.
+
+use std::borrow::Cow;
+
+/// Returns an iterator over the `key=value` pairs of `line`.
+///
+/// The rules are the ones used by logfmt:
+/// - pairs are separated by whitespace;
+/// - values can be double-quoted, in which case they can have
+///   whitespace and the escapes `\"`, `\\`, `\n`, `\r` and `\t`;
+/// - a key without `=` has an empty value;
+/// - an unterminated quoted value extends to the end of the line.
+///
+/// Keys and values borrow from `line`; values are only allocated when
+/// they have escapes.
+///
+/// Example:
+///
+/// ```
+/// use lineriver::kv_pairs;
+///
+/// let pairs = kv_pairs("level=info msg=\"hello world\" ok\n").collect::<Vec<_>>();
+/// assert_eq!(pairs[0], ("level", "info".into()));
+/// assert_eq!(pairs[1], ("msg", "hello world".into()));
+/// assert_eq!(pairs[2], ("ok", "".into()));
+/// ```
+pub fn kv_pairs(line: &str) -> KvPairs<'_> {
+    KvPairs { rest: line }
+}
+
+/// Iterator over the `key=value` pairs of a line, created by
+/// [`kv_pairs`].
+#[derive(Debug, Clone)]
+pub struct KvPairs<'a> {
+    rest: &'a str,
+}
+
+impl<'a> KvPairs<'a> {
+    /// Parses a quoted value; `self.rest` starts after the opening
+    /// quote.
+    fn quoted(&mut self) -> Cow<'a, str> {
+        let rest = self.rest;
+        let mut escaped = false;
+        let mut has_escapes = false;
+        let mut end = rest.len();
+        for (i, c) in rest.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+                has_escapes = true;
+            } else if c == '"' {
+                end = i;
+                break;
+            }
+        }
+        self.rest = rest.get(end + 1..).unwrap_or("");
+        let raw = &rest[..end];
+        if !has_escapes {
+            return Cow::Borrowed(raw);
+        }
+        let mut value = String::with_capacity(raw.len());
+        let mut chars = raw.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                value.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some(other) => value.push(other),
+                None => value.push('\\'),
+            }
+        }
+        Cow::Owned(value)
+    }
+}
+
+impl<'a> Iterator for KvPairs<'a> {
+    type Item = (&'a str, Cow<'a, str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rest = self.rest.trim_start();
+        if self.rest.is_empty() {
+            return None;
+        }
+        let keyend = self
+            .rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(self.rest.len());
+        let key = &self.rest[..keyend];
+        self.rest = &self.rest[keyend..];
+        let Some(rest) = self.rest.strip_prefix('=') else {
+            return Some((key, Cow::Borrowed("")));
+        };
+        if let Some(rest) = rest.strip_prefix('"') {
+            self.rest = rest;
+            return Some((key, self.quoted()));
+        }
+        let valueend = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        self.rest = &rest[valueend..];
+        Some((key, Cow::Borrowed(&rest[..valueend])))
+    }
+}
@@ -67,10 +67,246 @@
 //! [github]: https://github.com/lpenz/lineriver
 //! [`tcp_line_echo`]: https://github.com/lpenz/lineriver/blob/main/examples/tcp_line_echo.rs
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod splitter;
+pub use self::splitter::*;
+
+#[cfg(feature = "std")]
 mod blocking;
 
+#[cfg(feature = "std")]
 pub mod linereader;
+#[cfg(feature = "std")]
 pub use self::linereader::*;
 
+#[cfg(feature = "std")]
 pub mod lineread;
+#[cfg(feature = "std")]
 pub use self::lineread::*;
+
+#[cfg(feature = "std")]
+pub mod ext;
+#[cfg(feature = "std")]
+pub use self::ext::*;
+
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub use self::error::*;
+
+#[cfg(feature = "std")]
+pub mod linewriter;
+#[cfg(feature = "std")]
+pub use self::linewriter::*;
+
+#[cfg(feature = "std")]
+pub mod linestream;
+#[cfg(feature = "std")]
+pub use self::linestream::*;
+
+#[cfg(feature = "std")]
+pub mod expect;
+#[cfg(feature = "std")]
+pub use self::expect::*;
+
+#[cfg(feature = "std")]
+pub mod meta;
+#[cfg(feature = "std")]
+pub use self::meta::*;
+
+#[cfg(feature = "std")]
+pub mod eol;
+#[cfg(feature = "std")]
+pub use self::eol::*;
+
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub use self::stats::*;
+
+#[cfg(feature = "std")]
+pub mod state;
+#[cfg(feature = "std")]
+pub use self::state::*;
+
+#[cfg(feature = "std")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub use self::checkpoint::*;
+
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "std")]
+pub use self::shared::*;
+
+#[cfg(feature = "std")]
+pub mod cancel;
+#[cfg(feature = "std")]
+pub use self::cancel::*;
+
+#[cfg(feature = "std")]
+pub mod split;
+#[cfg(feature = "std")]
+pub use self::split::*;
+
+#[cfg(feature = "std")]
+pub mod dispatch;
+#[cfg(feature = "std")]
+pub use self::dispatch::*;
+
+#[cfg(feature = "std")]
+pub mod testing;
+
+#[cfg(feature = "std")]
+pub mod record;
+#[cfg(feature = "std")]
+pub use self::record::*;
+
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub use self::batch::*;
+
+#[cfg(feature = "std")]
+pub mod dedup;
+#[cfg(feature = "std")]
+pub use self::dedup::*;
+
+#[cfg(feature = "std")]
+pub mod ratelimit;
+#[cfg(feature = "std")]
+pub use self::ratelimit::*;
+
+#[cfg(feature = "std")]
+pub mod sample;
+#[cfg(feature = "std")]
+pub use self::sample::*;
+
+#[cfg(feature = "std")]
+pub mod mirror;
+#[cfg(feature = "std")]
+pub use self::mirror::*;
+
+#[cfg(feature = "std")]
+pub mod kv;
+#[cfg(feature = "std")]
+pub use self::kv::*;
+
+#[cfg(feature = "std")]
+pub mod timestamp;
+#[cfg(feature = "std")]
+pub use self::timestamp::*;
+
+#[cfg(feature = "std")]
+pub mod forward;
+#[cfg(feature = "std")]
+pub use self::forward::*;
+
+#[cfg(feature = "std")]
+pub mod tcp;
+#[cfg(feature = "std")]
+pub use self::tcp::*;
+
+#[cfg(feature = "std")]
+pub mod pump;
+#[cfg(feature = "std")]
+pub use self::pump::*;
+
+#[cfg(feature = "std")]
+pub mod pipe;
+#[cfg(feature = "std")]
+pub use self::pipe::*;
+
+#[cfg(feature = "std")]
+pub mod blockinglines;
+#[cfg(feature = "std")]
+pub use self::blockinglines::*;
+
+#[cfg(feature = "std")]
+pub mod linereaderarray;
+#[cfg(feature = "std")]
+pub use self::linereaderarray::*;
+
+#[cfg(feature = "std")]
+pub mod linereaderblocking;
+#[cfg(feature = "std")]
+pub use self::linereaderblocking::*;
+
+#[cfg(feature = "std")]
+pub mod replay;
+#[cfg(feature = "std")]
+pub use self::replay::*;
+
+#[cfg(feature = "std")]
+pub mod osrecord;
+#[cfg(feature = "std")]
+pub use self::osrecord::*;
+
+#[cfg(feature = "std")]
+pub mod asciisep;
+#[cfg(feature = "std")]
+pub use self::asciisep::*;
+
+#[cfg(feature = "std")]
+pub mod source;
+#[cfg(feature = "std")]
+pub use self::source::*;
+
+#[cfg(feature = "std")]
+pub mod systemd;
+#[cfg(feature = "std")]
+pub use self::systemd::*;
+
+#[cfg(feature = "codec")]
+pub mod codec;
+#[cfg(feature = "codec")]
+pub use self::codec::*;
+
+#[cfg(feature = "duct")]
+pub mod ductreader;
+#[cfg(feature = "duct")]
+pub use self::ductreader::*;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "log")]
+pub mod logbridge;
+#[cfg(feature = "log")]
+pub use self::logbridge::*;
+
+#[cfg(feature = "mio")]
+pub mod miotokens;
+#[cfg(feature = "mio")]
+pub use self::miotokens::*;
+
+#[cfg(feature = "polling")]
+pub mod reactor;
+#[cfg(feature = "polling")]
+pub use self::reactor::*;
+
+#[cfg(feature = "polling")]
+pub mod acceptor;
+#[cfg(feature = "polling")]
+pub use self::acceptor::*;
+
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(feature = "rayon")]
+pub use self::parallel::*;
+
+#[cfg(feature = "regex")]
+pub mod grep;
+#[cfg(feature = "regex")]
+pub use self::grep::*;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "wasm")]
+pub use self::wasm::*;
@@ -4,8 +4,14 @@
 
 //! This module has the generic trait [`LineRead`].
 
+use std::any::Any;
 use std::io;
+use std::ops::ControlFlow;
 use std::os::fd::{AsFd, AsRawFd};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::blocking;
 
 /// Trait for buffered non-blocking readeres that return only complete
 /// lines.
@@ -52,11 +58,123 @@ pub trait LineRead {
     /// effectively clearing the internal buffer.
     fn lines_get(&mut self) -> Vec<String>;
 
+    /// Calls [`Self::read_available`] and returns the lines read, as
+    /// [`Self::lines_get`] does.
+    ///
+    /// This is what the body of most poll loops does. If the read
+    /// fails, the lines stay in the internal buffer.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let mut reader = LineReader::from_nonblocking(&b"1\n2\n"[..])?;
+    /// assert_eq!(reader.read_and_get()?, vec!["1\n", "2\n"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn read_and_get(&mut self) -> Result<Vec<String>, io::Error> {
+        self.read_available()?;
+        Ok(self.lines_get())
+    }
+
+    /// Returns the internal line buffer as shared [`Arc<str>`] lines.
+    ///
+    /// The lines can then be handed to multiple consumers or threads
+    /// without cloning the text.
+    fn lines_get_shared(&mut self) -> Vec<Arc<str>> {
+        self.lines_get().into_iter().map(Arc::from).collect()
+    }
+
+    /// Calls `f` with each line in the internal line buffer, clearing
+    /// it.
+    ///
+    /// Callers that just process and drop the lines avoid handling the
+    /// `Vec` returned by [`Self::lines_get`];
+    /// [`LineReader`](crate::LineReader) also reuses its buffer instead
+    /// of allocating a new one.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let mut reader = LineReader::from_nonblocking(&b"1\n22\n"[..])?;
+    /// reader.read_available()?;
+    /// let mut total = 0;
+    /// reader.consume_lines(&mut |line| total += line.len());
+    /// assert_eq!(total, 5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn consume_lines(&mut self, f: &mut dyn FnMut(&str)) {
+        for line in self.lines_get() {
+            f(&line);
+        }
+    }
+
+    /// Removes and returns the first line of the internal buffer, for
+    /// callers that consume lines one at a time.
+    ///
+    /// Returns `None` if there are no complete lines; it doesn't read
+    /// from the underlying reader.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let mut reader = LineReader::from_nonblocking(&b"HELO\nDATA\n"[..])?;
+    /// reader.read_available()?;
+    /// assert_eq!(reader.pop_line().as_deref(), Some("HELO\n"));
+    /// assert_eq!(reader.lines_get(), vec!["DATA\n"]);
+    /// assert_eq!(reader.pop_line(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn pop_line(&mut self) -> Option<String>;
+
     /// Returns `true` if there are complete lines in the internal buffer.
     ///
     /// If this returns `true`, [`Self::lines_get`] won't return an
     /// empty vector.
     fn has_lines(&mut self) -> bool;
+
+    /// Returns `true` if the reader doesn't read anything because its
+    /// line queue reached its cap, until lines are retrieved.
+    ///
+    /// The wrappers in this crate are full while the reader they wrap
+    /// is, and keep being full until the lines they took from it are
+    /// retrieved.
+    ///
+    /// See
+    /// [`LineReader::with_max_queued_lines`](crate::LineReader::with_max_queued_lines).
+    fn is_full(&self) -> bool {
+        false
+    }
+
+    /// Returns the label that identifies the reader, if any.
+    ///
+    /// See [`LineReader::with_label`](crate::LineReader::with_label).
+    fn label(&self) -> Option<&str> {
+        None
+    }
+
+    /// Returns `true` if `line` ends with the line delimiter of the
+    /// reader, which means it is complete, rather than the partial
+    /// line returned at EOF.
+    ///
+    /// The default checks for `'\n'`; see
+    /// [`LineReader::with_delimiter`](crate::LineReader::with_delimiter)
+    /// and
+    /// [`LineReader::with_terminator`](crate::LineReader::with_terminator).
+    fn is_terminated(&self, line: &str) -> bool {
+        line.ends_with('\n')
+    }
 }
 
 /// Trait for buffered non-blocking readeres that return only complete
@@ -64,7 +182,142 @@ pub trait LineRead {
 ///
 /// This trait can be used to create a collection of LineReaders that
 /// use different underlying types, by using trait objects.
-pub trait LineReadRawFd: LineRead + AsRawFd {}
+pub trait LineReadRawFd: LineRead + AsRawFd {
+    /// Keeps reading until EOF or until `deadline`, returning all
+    /// lines collected.
+    ///
+    /// Waits for data with `poll(2)` on the raw file descriptor, so
+    /// it doesn't spin. This is useful to grab the rest of the output
+    /// of a child process that exited, without hanging forever if a
+    /// grandchild kept the pipe open. A partial line is kept in the
+    /// reader if the deadline expires.
+    fn drain(&mut self, deadline: Instant) -> Result<Vec<String>, io::Error> {
+        let mut lines = vec![];
+        loop {
+            self.read_once()?;
+            lines.extend(self.lines_get());
+            let now = Instant::now();
+            if self.eof() || now >= deadline {
+                return Ok(lines);
+            }
+            blocking::wait_readable(self.as_raw_fd(), deadline - now)?;
+        }
+    }
+
+    /// Keeps reading until EOF or until the optional overall `timeout`
+    /// expires, returning all lines collected.
+    ///
+    /// This is [`Self::drain`] with a relative timeout that can be
+    /// left out, for scripts and tests that want all the output of
+    /// something without hanging forever on it. Without a timeout,
+    /// waits for EOF.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use std::process::{Command, Stdio};
+    /// use std::time::Duration;
+    /// use lineriver::{LineReadRawFd, LineReader};
+    ///
+    /// let child = Command::new("seq").arg("3").stdout(Stdio::piped()).spawn()?;
+    /// let mut reader = LineReader::new(child.stdout.unwrap())?;
+    /// let lines = reader.collect_all(Some(Duration::from_secs(10)))?;
+    /// assert_eq!(lines, vec!["1\n", "2\n", "3\n"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn collect_all(&mut self, timeout: Option<Duration>) -> Result<Vec<String>, io::Error> {
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        if let Some(deadline) = deadline {
+            return self.drain(deadline);
+        }
+        let mut lines = vec![];
+        loop {
+            self.read_once()?;
+            lines.extend(self.lines_get());
+            if self.eof() {
+                return Ok(lines);
+            }
+            blocking::wait_readable(self.as_raw_fd(), Duration::MAX)?;
+        }
+    }
+
+    /// Runs the read loop, calling `f` with each line until it returns
+    /// [`ControlFlow::Break`], EOF is reached, or the optional overall
+    /// `timeout` expires.
+    ///
+    /// Waits for data with `poll(2)` between reads, so it never
+    /// busy-waits; simple applications that only handle one reader
+    /// don't need a polling crate this way.
+    ///
+    /// Returns [`ControlFlow::Break`] if `f` stopped the loop, and
+    /// [`ControlFlow::Continue`] otherwise; [`LineRead::eof`] then
+    /// tells if the reader reached EOF or the timeout expired. Lines
+    /// already read after the one that made `f` stop the loop are
+    /// dropped.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use std::ops::ControlFlow;
+    /// use std::process::{Command, Stdio};
+    /// use lineriver::{LineReadRawFd, LineReader};
+    ///
+    /// let child = Command::new("seq").arg("5").stdout(Stdio::piped()).spawn()?;
+    /// let mut reader = LineReader::new(child.stdout.unwrap())?;
+    /// let mut sum = 0;
+    /// let flow = reader.for_each_line(None, |line| {
+    ///     sum += line.trim_end().parse::<u32>().unwrap();
+    ///     if sum > 5 {
+    ///         ControlFlow::Break(())
+    ///     } else {
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// })?;
+    /// assert!(flow.is_break());
+    /// assert_eq!(sum, 6);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn for_each_line<F>(
+        &mut self,
+        timeout: Option<Duration>,
+        mut f: F,
+    ) -> Result<ControlFlow<()>, io::Error>
+    where
+        Self: Sized,
+        F: FnMut(String) -> ControlFlow<()>,
+    {
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        loop {
+            self.read_once()?;
+            if self.has_lines() {
+                for line in self.lines_get() {
+                    if f(line).is_break() {
+                        return Ok(ControlFlow::Break(()));
+                    }
+                }
+                continue;
+            }
+            if self.eof() {
+                return Ok(ControlFlow::Continue(()));
+            }
+            let wait = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(ControlFlow::Continue(()));
+                    }
+                    deadline - now
+                }
+                None => Duration::MAX,
+            };
+            blocking::wait_readable(self.as_raw_fd(), wait)?;
+        }
+    }
+}
 
 /// Trait for buffered non-blocking readeres that return only complete
 /// lines and is backed by an entity that has a file descriptor.
@@ -80,3 +333,84 @@ pub trait LineReadFd: LineRead + AsFd {}
 /// This trait can be used to create a collection of LineReaders that
 /// use different underlying types, by using trait objects.
 pub trait LineReadRawAndFd: LineRead + AsFd + AsRawFd {}
+
+/// Trait for [`LineRead`] objects that can be downcast back to their
+/// concrete type.
+///
+/// It is implemented for every `'static` [`LineRead`] type. Code that
+/// holds `Box<dyn LineReadAny>` trait objects can then recover the
+/// concrete reader when it needs type-specific operations, like the
+/// `peer_addr()` of the underlying socket. These methods are not in
+/// `LineRead` itself because that would require all implementors to
+/// be `'static`.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), std::io::Error> {
+/// use std::os::unix::net::UnixStream;
+/// use lineriver::{LineReadAny, LineReader};
+///
+/// let (_tx, rx) = UnixStream::pair()?;
+/// let readers: Vec<Box<dyn LineReadAny>> = vec![Box::new(LineReader::new(rx)?)];
+/// let reader = readers[0]
+///     .downcast_ref::<LineReader<UnixStream>>()
+///     .unwrap();
+/// assert!(reader.get_ref().peer_addr().is_ok());
+/// # Ok(())
+/// # }
+/// ```
+pub trait LineReadAny: LineRead + Any {
+    /// Returns the reader as a `&dyn Any`.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the reader as a `&mut dyn Any`.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: LineRead + Any> LineReadAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl dyn LineReadAny {
+    /// Returns a reference to the concrete reader, if it is a `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    /// Returns a mutable reference to the concrete reader, if it is a
+    /// `T`.
+    pub fn downcast_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.as_any_mut().downcast_mut()
+    }
+}
+
+/// Backpressure state of the wrappers that move the lines of the
+/// reader they wrap to a queue of their own.
+///
+/// The inner reader stops being full as soon as its lines are moved,
+/// so the wrapper remembers that it was, and reports itself full until
+/// its own queue is drained; see [`LineRead::is_full`].
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct FullLatch {
+    full: bool,
+}
+
+impl FullLatch {
+    /// Updates the latch before the lines of the inner reader are
+    /// moved; `queued` tells if the wrapper still has lines to return.
+    pub(crate) fn update(&mut self, inner_full: bool, queued: bool) {
+        self.full = (self.full && queued) || inner_full;
+    }
+
+    /// Returns `true` if the wrapper is full.
+    pub(crate) fn is_full(&self, inner_full: bool, queued: bool) -> bool {
+        inner_full || (self.full && queued)
+    }
+}
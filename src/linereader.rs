@@ -4,24 +4,151 @@
 
 //! This module has the main type of this crate: [`LineReader`].
 
-use std::fmt::Debug;
-use std::io::{self, Read};
+use std::collections::VecDeque;
+use std::fmt::{self, Debug};
+use std::io::{self, Read, Seek, SeekFrom};
 use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
-use std::{mem, str};
+use std::time::{Duration, Instant, SystemTime};
+use std::{iter, mem, str};
 
 use crate::blocking;
-use crate::lineread::{LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+use crate::cancel::CancelToken;
+use crate::checkpoint::Checkpoint;
+use crate::eol::{EolDetector, EolStyle};
+use crate::error::{LineTooLong, ReadError};
+use crate::expect::Pattern;
+use crate::lineread::{FullLatch, LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+use crate::meta::{self, LineMeta};
+use crate::state::ReaderState;
+use crate::stats::LineStats;
+use crate::timestamp::TimestampFormat;
 
 const BUFFER_SIZE: usize = 8192;
 
+/// Callback for [`LineReader::on_data`].
+type DataHook = dyn FnMut(&[u8]) + Send + Sync;
+
+/// Callback for [`LineReader::on_line`].
+type LineHook = dyn FnMut(String) + Send + Sync;
+
+/// Callback for [`LineReader::on_eof`].
+type EofHook = dyn FnMut() + Send + Sync;
+
+/// Callback for [`LineReader::on_error`].
+type ErrorHook = dyn FnMut(&io::Error) + Send + Sync;
+
+/// The callbacks registered in a [`LineReader`].
+#[derive(Default)]
+struct Hooks {
+    on_data: Option<Box<DataHook>>,
+    on_line: Option<Box<LineHook>>,
+    on_eof: Option<Box<EofHook>>,
+    on_error: Option<Box<ErrorHook>>,
+}
+
+impl Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("on_data", &self.on_data.is_some())
+            .field("on_line", &self.on_line.is_some())
+            .field("on_eof", &self.on_eof.is_some())
+            .field("on_error", &self.on_error.is_some())
+            .finish()
+    }
+}
+
+/// What [`LineReader`] does with lines longer than the maximum set with
+/// [`LineReader::with_max_line_len`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LongLinePolicy {
+    /// Makes [`LineRead::read_once`] return an
+    /// [`io::ErrorKind::InvalidData`] error with a [`LineTooLong`],
+    /// which breaks the reader.
+    Error,
+    /// Emits the first bytes of the line, up to the maximum, as a line
+    /// without a delimiter, and discards the rest of it.
+    Truncate,
+}
+
 /// Buffered non-blocking reader that returns only complete lines.
-#[derive(Debug)]
+///
+/// `LineReader<R>` is [`Send`] if `R` is `Send`, and [`Sync`] if `R`
+/// is `Sync`, so it can be moved to other threads. See
+/// [`SharedLineReader`](crate::SharedLineReader) for a handle that can
+/// be used by multiple threads at the same time.
+///
+/// With the `metrics` feature, the reader reports the following
+/// metrics through the [metrics] facade:
+/// - `lineriver_bytes_read` (counter): bytes read;
+/// - `lineriver_lines_read` (counter): complete lines emitted;
+/// - `lineriver_wouldblock` (counter): reads that found no data;
+/// - `lineriver_buffer_bytes` (gauge): bytes of the partial line in
+///   the buffer after the last read.
+///
+/// [metrics]: https://docs.rs/metrics
+///
+/// With the `redact` feature, the tracing spans and the [`Debug`]
+/// output of the reader record only sizes and counters, never the
+/// contents of the underlying reader or of the buffered lines.
+#[cfg_attr(not(feature = "redact"), derive(Debug))]
 pub struct LineReader<R> {
     reader: R,
     at_eof: bool,
     buf: Vec<u8>,
     used: usize,
-    lines: Vec<String>,
+    lines: VecDeque<String>,
+    metas: VecDeque<LineMeta>,
+    eintr_retries: usize,
+    broken: bool,
+    error: Option<io::Error>,
+    cancel: Option<CancelToken>,
+    paused: bool,
+    max_queued: Option<usize>,
+    raw: bool,
+    idle_flush: Option<Duration>,
+    partial_since: Option<Instant>,
+    last_data: Instant,
+    stall_timeout: Option<Duration>,
+    stall_reported: bool,
+    history: VecDeque<String>,
+    history_len: usize,
+    skip: usize,
+    take: Option<usize>,
+    checksums: bool,
+    timestamps: Option<TimestampFormat>,
+    stats: Option<LineStats>,
+    hooks: Hooks,
+    hooked: bool,
+    label: Option<String>,
+    fd: Option<std::os::fd::RawFd>,
+    peer: Option<String>,
+    bytes_read: u64,
+    lines_delivered: u64,
+    resplit: bool,
+    borrowed: bool,
+    consumed: usize,
+    eol: EolDetector,
+    generation: u64,
+    line_offset: u64,
+    committed: u64,
+    delimiters: Delimiters,
+    max_line: Option<(usize, LongLinePolicy)>,
+    discarding: bool,
+}
+
+#[cfg(feature = "redact")]
+impl<R> Debug for LineReader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LineReader")
+            .field("label", &self.label)
+            .field("reader", &std::any::type_name::<R>())
+            .field("at_eof", &self.at_eof)
+            .field("used", &self.used)
+            .field("num_lines", &self.lines.len())
+            .field("broken", &self.broken)
+            .field("paused", &self.paused)
+            .finish_non_exhaustive()
+    }
 }
 
 #[tracing::instrument(skip(buf))]
@@ -32,20 +159,225 @@ fn u8array_to_string(buf: &[u8]) -> Result<String, io::Error> {
     }
 }
 
+/// Records the time between the completion of a line and its
+/// delivery.
+fn record_latency(stats: &mut LineStats, now: Instant, completed: Instant) {
+    let latency = now.duration_since(completed).as_micros();
+    stats
+        .latency_us
+        .record(latency.try_into().unwrap_or(u64::MAX));
+}
+
+/// Returns the length of the longest prefix of `buf` with at most
+/// `max` bytes that doesn't split a UTF-8 character.
+fn utf8_prefix_len(buf: &[u8], max: usize) -> Result<usize, io::Error> {
+    match str::from_utf8(&buf[..max.min(buf.len())]) {
+        Ok(prefix) => Ok(prefix.len()),
+        Err(e) if e.error_len().is_none() => Ok(e.valid_up_to()),
+        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+    }
+}
+
+/// Returns the position of the first of the `delimiters` in
+/// `haystack`.
+fn find_delimiter(delimiters: &[u8], haystack: &[u8]) -> Option<usize> {
+    match *delimiters {
+        [a] => memchr::memchr(a, haystack),
+        [a, b] => memchr::memchr2(a, b, haystack),
+        [a, b, c] => memchr::memchr3(a, b, c, haystack),
+        _ => haystack.iter().position(|byte| delimiters.contains(byte)),
+    }
+}
+
+/// Returns the position of the last of the `delimiters` in
+/// `haystack`.
+fn rfind_delimiter(delimiters: &[u8], haystack: &[u8]) -> Option<usize> {
+    match *delimiters {
+        [a] => memchr::memrchr(a, haystack),
+        [a, b] => memchr::memrchr2(a, b, haystack),
+        [a, b, c] => memchr::memrchr3(a, b, c, haystack),
+        _ => haystack.iter().rposition(|byte| delimiters.contains(byte)),
+    }
+}
+
+/// Where lines end: at any of a set of bytes, or at a sequence of
+/// bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Delimiters {
+    Any(Vec<u8>),
+    Sequence(Vec<u8>),
+}
+
+impl Delimiters {
+    /// Returns the length of the first line in `haystack`, including
+    /// its delimiter.
+    fn find(&self, haystack: &[u8]) -> Option<usize> {
+        match self {
+            Delimiters::Any(delimiters) => find_delimiter(delimiters, haystack).map(|i| i + 1),
+            Delimiters::Sequence(terminator) => {
+                memchr::memmem::find(haystack, terminator).map(|i| i + terminator.len())
+            }
+        }
+    }
+
+    /// Returns the length of the complete lines in `haystack`.
+    fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        match self {
+            Delimiters::Any(delimiters) => rfind_delimiter(delimiters, haystack).map(|i| i + 1),
+            // Searching backwards could match a different occurrence
+            // of a self-overlapping terminator:
+            Delimiters::Sequence(_) => {
+                let mut end = None;
+                while let Some(len) = self.find(&haystack[end.unwrap_or(0)..]) {
+                    end = Some(end.unwrap_or(0) + len);
+                }
+                end
+            }
+        }
+    }
+
+    /// Splits `haystack` into lines, keeping the delimiters; the last
+    /// one may be incomplete.
+    fn split<'a>(&'a self, mut haystack: &'a [u8]) -> impl Iterator<Item = &'a [u8]> + 'a {
+        iter::from_fn(move || {
+            if haystack.is_empty() {
+                return None;
+            }
+            let len = self.find(haystack).unwrap_or(haystack.len());
+            let (line, rest) = haystack.split_at(len);
+            haystack = rest;
+            Some(line)
+        })
+    }
+
+    /// Returns `true` if `line` ends with a delimiter.
+    fn terminates(&self, line: &[u8]) -> bool {
+        match self {
+            Delimiters::Any(delimiters) => line.last().is_some_and(|b| delimiters.contains(b)),
+            Delimiters::Sequence(terminator) => line.ends_with(terminator),
+        }
+    }
+
+    /// Returns how many bytes before the newly read data a delimiter
+    /// that straddles two reads may start.
+    fn lookback(&self) -> usize {
+        match self {
+            Delimiters::Any(_) => 0,
+            Delimiters::Sequence(terminator) => terminator.len() - 1,
+        }
+    }
+}
+
 impl<R: Read + AsRawFd + Debug> LineReader<R> {
     /// Creates a new LineReader, setting the underlying
     /// descriptor as non-blocking.
-    #[tracing::instrument]
+    #[cfg_attr(not(feature = "redact"), tracing::instrument)]
+    #[cfg_attr(feature = "redact", tracing::instrument(skip(reader)))]
     pub fn new(reader: R) -> Result<Self, io::Error> {
         let fd = reader.as_raw_fd();
         blocking::disable(fd)?;
-        Ok(Self {
-            reader,
-            at_eof: false,
-            buf: Default::default(),
-            used: 0,
-            lines: Default::default(),
-        })
+        let mut linereader = Self::from_nonblocking(reader)?;
+        linereader.fd = Some(fd);
+        Ok(linereader)
+    }
+
+    /// Replaces the underlying reader, after a reconnect for instance,
+    /// setting the new descriptor as non-blocking and returning the
+    /// old reader.
+    ///
+    /// The partial line of the old reader is discarded, the EOF and
+    /// error states are cleared, and the generation returned by
+    /// [`Self::generation`] is incremented. Lines already queued are
+    /// kept; [`LineMeta::generation`] tells which reader they came
+    /// from, so that consumers can discard stale ones.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use std::io::Write;
+    /// use std::os::unix::net::UnixStream;
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let (mut old, rd) = UnixStream::pair()?;
+    /// let mut reader = LineReader::new(rd)?;
+    /// old.write_all(b"stale\npartial")?;
+    /// reader.read_once()?;
+    /// let (mut new, rd) = UnixStream::pair()?;
+    /// reader.replace_reader(rd)?;
+    /// new.write_all(b"fresh\n")?;
+    /// reader.read_once()?;
+    /// let lines = reader.lines_get_with_meta();
+    /// assert_eq!(lines[0].0, "stale\n");
+    /// assert_eq!(lines[0].1.generation, 0);
+    /// assert_eq!(lines[1].0, "fresh\n");
+    /// assert_eq!(lines[1].1.generation, 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn replace_reader(&mut self, reader: R) -> Result<R, io::Error> {
+        let fd = reader.as_raw_fd();
+        blocking::disable(fd)?;
+        self.fd = Some(fd);
+        self.generation += 1;
+        self.buf.clear();
+        self.used = 0;
+        self.consumed = 0;
+        self.resplit = false;
+        self.partial_since = None;
+        self.discarding = false;
+        self.eol = Default::default();
+        self.at_eof = false;
+        self.broken = false;
+        self.error = None;
+        self.last_data = Instant::now();
+        self.stall_reported = false;
+        Ok(mem::replace(&mut self.reader, reader))
+    }
+}
+
+impl<R: Read + AsRawFd + Debug + Send + 'static> LineReader<R> {
+    /// Creates a new LineReader with [`Self::new`] and boxes it as a
+    /// [`LineRead`] trait object.
+    ///
+    /// Helps building collections of readers with distinct underlying
+    /// types:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use std::os::unix::net::UnixStream;
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let (_tx, socket) = UnixStream::pair()?;
+    /// let file = std::fs::File::open("/dev/null")?;
+    /// let readers: Vec<Box<dyn LineRead + Send>> =
+    ///     vec![LineReader::boxed(socket)?, LineReader::boxed(file)?];
+    /// # assert_eq!(readers.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn boxed(reader: R) -> Result<Box<dyn LineRead + Send>, io::Error> {
+        Ok(Box::new(Self::new(reader)?))
+    }
+
+    /// Creates a new LineReader with [`Self::new`] and boxes it as a
+    /// [`LineReadRawFd`] trait object.
+    pub fn boxed_raw_fd(reader: R) -> Result<Box<dyn LineReadRawFd + Send>, io::Error> {
+        Ok(Box::new(Self::new(reader)?))
+    }
+}
+
+impl<R: Read + AsFd + AsRawFd + Debug + Send + 'static> LineReader<R> {
+    /// Creates a new LineReader with [`Self::new`] and boxes it as a
+    /// [`LineReadFd`] trait object.
+    pub fn boxed_fd(reader: R) -> Result<Box<dyn LineReadFd + Send>, io::Error> {
+        Ok(Box::new(Self::new(reader)?))
+    }
+
+    /// Creates a new LineReader with [`Self::new`] and boxes it as a
+    /// [`LineReadRawAndFd`] trait object.
+    pub fn boxed_raw_and_fd(reader: R) -> Result<Box<dyn LineReadRawAndFd + Send>, io::Error> {
+        Ok(Box::new(Self::new(reader)?))
     }
 }
 
@@ -54,7 +386,8 @@ impl<R: Read + Debug> LineReader<R> {
     ///
     /// Assumes the reader is already non-blocking, not configuring
     /// anything in the underlying descriptor.
-    #[tracing::instrument]
+    #[cfg_attr(not(feature = "redact"), tracing::instrument)]
+    #[cfg_attr(feature = "redact", tracing::instrument(skip(reader)))]
     pub fn from_nonblocking(reader: R) -> Result<Self, io::Error> {
         Ok(Self {
             reader,
@@ -62,83 +395,1606 @@ impl<R: Read + Debug> LineReader<R> {
             buf: Default::default(),
             used: 0,
             lines: Default::default(),
+            metas: Default::default(),
+            eintr_retries: 0,
+            broken: false,
+            error: None,
+            cancel: None,
+            paused: false,
+            max_queued: None,
+            raw: false,
+            idle_flush: None,
+            partial_since: None,
+            last_data: Instant::now(),
+            stall_timeout: None,
+            stall_reported: false,
+            history: Default::default(),
+            history_len: 0,
+            skip: 0,
+            take: None,
+            checksums: false,
+            timestamps: None,
+            stats: None,
+            hooks: Default::default(),
+            hooked: false,
+            label: None,
+            fd: None,
+            peer: None,
+            bytes_read: 0,
+            lines_delivered: 0,
+            resplit: false,
+            borrowed: false,
+            consumed: 0,
+            eol: Default::default(),
+            generation: 0,
+            line_offset: 0,
+            committed: 0,
+            delimiters: Delimiters::Any(vec![b'\n']),
+            max_line: None,
+            discarding: false,
+        })
+    }
+
+    /// Retries a read interrupted by a signal (`EINTR`) up to
+    /// `retries` times before giving up.
+    ///
+    /// By default (`0`) an interrupted read just makes
+    /// [`LineRead::read_once`] return, which can make
+    /// [`LineRead::read_available`] stop early in processes that
+    /// handle a lot of signals.
+    pub fn with_eintr_retries(mut self, retries: usize) -> Self {
+        self.eintr_retries = retries;
+        self
+    }
+
+    /// Sets a [`CancelToken`] that interrupts
+    /// [`LineRead::read_available`] when cancelled.
+    ///
+    /// This allows a supervisor thread to stop a long drain without
+    /// closing the file descriptor under the reader.
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Returns `true` if the reader has a [`CancelToken`] that was
+    /// cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    /// Pauses reading: [`LineRead::read_once`] and
+    /// [`LineRead::read_available`] don't read anything until
+    /// [`Self::resume`] is called.
+    ///
+    /// This can be used to implement flow control without removing
+    /// the file descriptor from the poller, as the data is left in
+    /// the kernel buffers. Lines already read can still be retrieved
+    /// with [`LineRead::lines_get`].
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes reading after a [`Self::pause`].
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns `true` if the reader is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Splits the lines at `delimiter` instead of at newlines, like
+    /// `\0` for the output of `find -print0`.
+    ///
+    /// The delimiter is kept at the end of each line, and the trailing
+    /// bytes after the last one are returned as a line at EOF. This is
+    /// the single-byte case of [`Self::with_delimiters`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delimiter` is not ASCII, as it could split UTF-8
+    /// sequences.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let mut reader = LineReader::from_nonblocking(&b"a b\0c\nd\0e"[..])?.with_delimiter(b'\0');
+    /// while reader.read_once()? {}
+    /// assert_eq!(reader.lines_get(), vec!["a b\0", "c\nd\0", "e"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_delimiter(self, delimiter: u8) -> Self {
+        self.with_delimiters(&[delimiter])
+    }
+
+    /// Splits the lines at any of the given bytes, instead of only at
+    /// newlines; `b"\n;"`, for instance, splits at both.
+    ///
+    /// The delimiter that terminated each line is kept at its end, and
+    /// is also reported in [`LineMeta::delimiter`], for mixed-format
+    /// streams where the terminator itself is meaningful.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delimiters` is empty or has non-ASCII bytes, which
+    /// could split UTF-8 sequences.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let mut reader = LineReader::from_nonblocking(&b"a;b\nc"[..])?.with_delimiters(b"\n;");
+    /// while reader.read_once()? {}
+    /// let lines = reader.lines_get_with_meta();
+    /// assert_eq!(lines[0].0, "a;");
+    /// assert_eq!(lines[0].1.delimiter, Some(b';'));
+    /// assert_eq!(lines[1].0, "b\n");
+    /// assert_eq!(lines[1].1.delimiter, Some(b'\n'));
+    /// assert_eq!(lines[2].0, "c");
+    /// assert_eq!(lines[2].1.delimiter, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_delimiters(mut self, delimiters: &[u8]) -> Self {
+        assert!(!delimiters.is_empty(), "delimiters must not be empty");
+        assert!(delimiters.is_ascii(), "delimiters must be ASCII");
+        self.delimiters = Delimiters::Any(delimiters.to_vec());
+        self
+    }
+
+    /// Splits the lines at the `terminator` sequence instead of at
+    /// newlines, like `"\r\n"` for network protocols such as SMTP,
+    /// HTTP and IRC, where a lone `\r` or `\n` is not a line end.
+    ///
+    /// The terminator is kept at the end of each line, even when it
+    /// arrives split across reads. [`LineMeta::delimiter`] has its
+    /// last byte.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `terminator` is empty or has non-ASCII bytes, which
+    /// could split UTF-8 sequences.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let mut reader =
+    ///     LineReader::from_nonblocking(&b"HELO a\r\nDATA\nx\r\n"[..])?.with_terminator("\r\n");
+    /// while reader.read_once()? {}
+    /// assert_eq!(reader.lines_get(), vec!["HELO a\r\n", "DATA\nx\r\n"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_terminator(mut self, terminator: impl Into<Vec<u8>>) -> Self {
+        let terminator = terminator.into();
+        assert!(!terminator.is_empty(), "terminator must not be empty");
+        assert!(terminator.is_ascii(), "terminator must be ASCII");
+        self.delimiters = match terminator.len() {
+            1 => Delimiters::Any(terminator),
+            _ => Delimiters::Sequence(terminator),
+        };
+        self
+    }
+
+    /// Caps the number of lines queued in the reader: once `lines`
+    /// lines are waiting to be retrieved, [`LineRead::read_once`]
+    /// doesn't read anything until some are taken.
+    ///
+    /// [`LineRead::is_full`] then returns `true`, which makes a
+    /// [`Reactor`](crate::Reactor) stop polling the descriptor, so that
+    /// the backpressure reaches the kernel buffers instead of growing
+    /// the memory of the process.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lines` is 0.
+    pub fn with_max_queued_lines(mut self, lines: usize) -> Self {
+        assert!(lines > 0, "lines must be greater than 0");
+        self.max_queued = Some(lines);
+        self
+    }
+
+    /// Limits the length of the lines, including the delimiter, so that
+    /// a peer that never sends one can't make the buffer grow without
+    /// bounds.
+    ///
+    /// Lines longer than `max` bytes are handled according to
+    /// `policy`. Truncation happens at a UTF-8 character boundary. The
+    /// [`LineMeta::offset`] of a truncated line includes the bytes
+    /// discarded along with it, so that the offsets of the next lines
+    /// stay right.
+    /// With [borrowed lines](Self::with_borrowed_lines), only the
+    /// partial line is checked, and it always returns the error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::{LineRead, LineReader, LongLinePolicy};
+    ///
+    /// let mut reader = LineReader::from_nonblocking(&b"short\nvery long\nok\n"[..])?
+    ///     .with_max_line_len(6, LongLinePolicy::Truncate);
+    /// while reader.read_once()? {}
+    /// assert_eq!(reader.lines_get(), vec!["short\n", "very l", "ok\n"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_max_line_len(mut self, max: usize, policy: LongLinePolicy) -> Self {
+        assert!(max > 0, "max must be greater than 0");
+        self.max_line = Some((max, policy));
+        self
+    }
+
+    /// Emits a partial line that has been sitting in the buffer for
+    /// longer than `duration`, instead of waiting for its newline.
+    ///
+    /// This makes prompts and other output without a trailing newline
+    /// visible. The check is done by [`LineRead::read_once`], so it
+    /// should be called periodically even when there is no data.
+    ///
+    /// As with the last line at EOF, partial lines are the ones that
+    /// don't end with a newline. An incomplete UTF-8 sequence at the
+    /// end of the buffer is kept for the next line.
+    pub fn with_idle_flush(mut self, duration: Duration) -> Self {
+        self.idle_flush = Some(duration);
+        self
+    }
+
+    /// Considers the reader stalled when no data arrives for
+    /// `timeout`; see [`Self::is_stalled`] and [`Self::take_stalled`].
+    pub fn with_stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = Some(timeout);
+        self
+    }
+
+    /// Returns how long ago data was last read, or since the reader
+    /// was created if no data was read yet.
+    pub fn idle_for(&self) -> Duration {
+        self.last_data.elapsed()
+    }
+
+    /// Returns `true` if a stall timeout was set and no data arrived
+    /// for that long.
+    ///
+    /// Supervisors can use this to restart wedged subprocesses or to
+    /// reconnect dead sockets. A reader at EOF is never stalled.
+    pub fn is_stalled(&self) -> bool {
+        !self.at_eof
+            && self
+                .stall_timeout
+                .is_some_and(|timeout| self.idle_for() >= timeout)
+    }
+
+    /// Returns `true` only once per silence period, when the reader
+    /// becomes stalled.
+    ///
+    /// This is the event-style alternative to [`Self::is_stalled`]:
+    /// it returns `true` again only after new data arrives and the
+    /// reader stalls again.
+    pub fn take_stalled(&mut self) -> bool {
+        if self.stall_reported || !self.is_stalled() {
+            return false;
+        }
+        self.stall_reported = true;
+        true
+    }
+
+    /// Keeps the last `len` lines in a history buffer, even after they
+    /// are retrieved with [`LineRead::lines_get`].
+    ///
+    /// See [`Self::recent_lines`].
+    pub fn with_history(mut self, len: usize) -> Self {
+        self.history_len = len;
+        self
+    }
+
+    /// Returns the lines in the history buffer, oldest first.
+    ///
+    /// This is useful for crash reports, for instance, that want the
+    /// last lines printed by a process before it died.
+    pub fn recent_lines(&self) -> impl Iterator<Item = &str> {
+        self.history.iter().map(String::as_str)
+    }
+
+    /// Discards the first `lines` lines, like headers or banners.
+    pub fn with_skip(mut self, lines: usize) -> Self {
+        self.skip = lines;
+        self
+    }
+
+    /// Stops after `lines` lines were emitted (not counting the ones
+    /// discarded by [`Self::with_skip`]), reporting EOF from then on.
+    ///
+    /// The lines and partial data read after that are discarded.
+    pub fn with_take(mut self, lines: usize) -> Self {
+        self.take = Some(lines);
+        self.at_eof = lines == 0;
+        self
+    }
+
+    /// Computes a CRC-32 checksum of each line, available in the
+    /// [`LineMeta`] returned by [`Self::lines_get_with_meta`].
+    ///
+    /// This allows downstream pipelines to deduplicate and track the
+    /// integrity of lines without hashing them again.
+    pub fn with_checksums(mut self) -> Self {
+        self.checksums = true;
+        self
+    }
+
+    /// Extracts the timestamp at the beginning of each line, in the
+    /// given format, making it available in the [`LineMeta`] returned
+    /// by [`Self::lines_get_with_meta`].
+    ///
+    /// Lines without a timestamp get their arrival time instead.
+    pub fn with_timestamps(mut self, format: TimestampFormat) -> Self {
+        self.timestamps = Some(format);
+        self
+    }
+
+    /// Returns an iterator that takes the available lines one by one,
+    /// without allocating a vector for them like
+    /// [`LineRead::lines_get`].
+    ///
+    /// Lines are removed from the buffer as they are iterated, with
+    /// [`LineRead::pop_line`]; the ones left when the iterator is
+    /// dropped stay in the buffer.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let mut reader = LineReader::from_nonblocking(&b"1\n2\n3\n"[..])?;
+    /// reader.read_available()?;
+    /// let mut total = 0;
+    /// for line in reader.lines_drain().take(2) {
+    ///     total += line.trim_end().parse::<u32>().unwrap();
+    /// }
+    /// assert_eq!(total, 3);
+    /// assert_eq!(reader.lines_get(), vec!["3\n"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn lines_drain(&mut self) -> impl Iterator<Item = String> + '_ {
+        iter::from_fn(move || self.pop_line())
+    }
+
+    /// Returns the internal line buffer along with the metadata of
+    /// each line.
+    ///
+    /// Like [`LineRead::lines_get`], this transfers ownership of the
+    /// buffer to the caller.
+    pub fn lines_get_with_meta(&mut self) -> Vec<(String, LineMeta)> {
+        let (lines, metas) = self.take_lines();
+        lines.into_iter().zip(metas).collect()
+    }
+
+    /// Reads once and returns the lines up to the first one for which
+    /// `sentinel` returns `true`, leaving the lines after it buffered.
+    ///
+    /// The sentinel line is returned at the end if `include` is `true`,
+    /// and dropped otherwise. [`SentinelScope::complete`] tells if the
+    /// sentinel was found; if not, all lines available are returned,
+    /// and the method should be called again to get the rest of the
+    /// scope. This is meant for protocols that terminate sections with
+    /// lines like `"END"` or `"."`.
+    pub fn read_until_sentinel<P>(
+        &mut self,
+        mut sentinel: P,
+        include: bool,
+    ) -> Result<SentinelScope, io::Error>
+    where
+        P: FnMut(&str) -> bool,
+    {
+        if !self.lines.iter().any(|line| sentinel(line)) {
+            self.read_once()?;
+        }
+        Ok(self.take_scope(sentinel, include))
+    }
+
+    /// Takes the buffered lines up to the sentinel, without reading;
+    /// see [`Self::read_until_sentinel`].
+    fn take_scope<P>(&mut self, mut sentinel: P, include: bool) -> SentinelScope
+    where
+        P: FnMut(&str) -> bool,
+    {
+        let Some(position) = self.lines.iter().position(|line| sentinel(line)) else {
+            return SentinelScope {
+                lines: self.take_lines().0,
+                complete: false,
+            };
+        };
+        let rest = self.lines.split_off(position + 1);
+        let rest_metas = self.metas.split_off(position + 1);
+        let mut lines = self.take_lines().0;
+        self.lines = rest;
+        self.metas = rest_metas;
+        if !include {
+            lines.pop();
+        }
+        SentinelScope {
+            lines,
+            complete: true,
+        }
+    }
+
+    /// Returns a [`LineRead`] view of this reader that yields the lines
+    /// up to, and including, the first one that matches `pattern`, and
+    /// then reports EOF.
+    ///
+    /// The lines after the matching one stay buffered in this reader,
+    /// which resumes exactly where the view stopped. This allows handing
+    /// a protocol phase to a sub-parser that takes a `LineRead`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lineriver::testing::ScriptedReader;
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// fn parse_headers(reader: &mut dyn LineRead) -> std::io::Result<Vec<String>> {
+    ///     let mut headers = vec![];
+    ///     while !reader.eof() {
+    ///         reader.read_once()?;
+    ///         headers.extend(reader.lines_get());
+    ///     }
+    ///     Ok(headers)
+    /// }
+    ///
+    /// let script = ScriptedReader::new().data(b"Host: x\n\nbody\n");
+    /// let mut reader = LineReader::from_nonblocking(script)?;
+    /// let headers = parse_headers(&mut reader.take_until(|line: &str| line == "\n"))?;
+    /// assert_eq!(headers, vec!["Host: x\n", "\n"]);
+    /// assert_eq!(reader.lines_get(), vec!["body\n"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_until<P: Pattern>(&mut self, pattern: P) -> TakeUntil<'_, R, P> {
+        TakeUntil {
+            reader: self,
+            pattern,
+            done: false,
+            lines: VecDeque::new(),
+            full: FullLatch::default(),
+        }
+    }
+
+    /// Collects statistics about the lines: a histogram of their
+    /// lengths, and one of the time between their completion and
+    /// their retrieval. See [`Self::stats`].
+    pub fn with_stats(mut self) -> Self {
+        self.stats = Some(Default::default());
+        self
+    }
+
+    /// Returns the statistics collected, if enabled by
+    /// [`Self::with_stats`].
+    pub fn stats(&self) -> Option<&LineStats> {
+        self.stats.as_ref()
+    }
+
+    /// Registers a callback that gets every chunk of bytes read, before
+    /// any splitting or decoding.
+    ///
+    /// This can be used to capture exactly what arrived on the wire
+    /// when diagnosing encoding or framing problems with a peer.
+    pub fn on_data<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(&[u8]) + Send + Sync + 'static,
+    {
+        self.hooks.on_data = Some(Box::new(hook));
+        self
+    }
+
+    /// Copies every chunk of bytes read to `tap`, before any splitting
+    /// or decoding; see [`Self::on_data`].
+    ///
+    /// Errors writing to the tap are ignored, so that the capture never
+    /// disturbs the reader.
+    pub fn with_tap<W>(self, mut tap: W) -> Self
+    where
+        W: io::Write + Send + Sync + 'static,
+    {
+        self.on_data(move |data| {
+            let _ = tap.write_all(data).and_then(|_| tap.flush());
         })
     }
 
-    #[tracing::instrument(skip(self),fields(self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
+    /// Registers a callback that gets each line as soon as it is
+    /// complete.
+    ///
+    /// This is a push-style alternative to [`LineRead::lines_get`]:
+    /// lines given to the callback are not kept in the line buffer.
+    /// [`LineRead::read_available`] then returns once it gave lines to
+    /// the callback, instead of when lines are available.
+    pub fn on_line<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(String) + Send + Sync + 'static,
+    {
+        self.hooks.on_line = Some(Box::new(hook));
+        self
+    }
+
+    /// Registers a callback that is called once, when the reader
+    /// reaches EOF.
+    pub fn on_eof<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut() + Send + Sync + 'static,
+    {
+        self.hooks.on_eof = Some(Box::new(hook));
+        self
+    }
+
+    /// Registers a callback that is called with the error that breaks
+    /// the reader, before it is returned by [`LineRead::read_once`].
+    pub fn on_error<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(&io::Error) + Send + Sync + 'static,
+    {
+        self.hooks.on_error = Some(Box::new(hook));
+        self
+    }
+
+    /// Keeps the bytes read in the internal buffer instead of splitting
+    /// them into owned lines, so that they can be borrowed by
+    /// [`Self::lines_iter`].
+    ///
+    /// Consumers that discard most lines avoid allocating them this
+    /// way. Lines borrowed this way skip the callbacks and the line
+    /// metadata; [`LineRead::lines_get`] still works, by copying the
+    /// lines out of the buffer. As the lines are not validated when
+    /// read, [`LineRead::lines_get`], [`LineRead::consume_lines`] and
+    /// [`LineRead::pop_line`] replace invalid UTF-8 with `U+FFFD`;
+    /// [`Self::lines_iter`] and [`Self::lines_get_str`] report it as an
+    /// error instead.
+    pub fn with_borrowed_lines(mut self, borrowed: bool) -> Self {
+        self.borrowed = borrowed;
+        self
+    }
+
+    /// Returns an iterator over the complete lines in the internal
+    /// buffer, borrowed from it.
+    ///
+    /// Each line yielded is consumed; callers that want to keep one
+    /// have to copy it. At EOF, the last line is yielded even if it
+    /// doesn't end with a newline. Invalid UTF-8 is yielded as an
+    /// [`io::ErrorKind::InvalidData`] error.
+    ///
+    /// Only the lines of readers created with
+    /// [`Self::with_borrowed_lines`] stay in the buffer; other readers
+    /// yield nothing.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let mut reader = LineReader::from_nonblocking(&b"skip\nkeep\nskip\n"[..])?
+    ///     .with_borrowed_lines(true);
+    /// while reader.read_once()? {}
+    /// let kept = reader
+    ///     .lines_iter()
+    ///     .filter(|line| matches!(line, Ok(line) if line.starts_with("keep")))
+    ///     .map(|line| line.map(str::to_owned))
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// assert_eq!(kept, vec!["keep\n"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn lines_iter(&mut self) -> LinesIter<'_> {
+        let used = if self.borrowed { self.used } else { 0 };
+        LinesIter {
+            buf: &self.buf[..used],
+            pos: &mut self.consumed,
+            eof: self.at_eof,
+            delimiters: &self.delimiters,
+        }
+    }
+
+    /// Returns an iterator over the available lines that doesn't
+    /// consume them, for hot paths that only inspect lines.
+    ///
+    /// With [`Self::with_borrowed_lines`], the lines are borrowed from
+    /// the read buffer, without allocating; iteration stops at the
+    /// first line that is not valid UTF-8. Use [`Self::lines_clear`] to
+    /// drop the lines once done.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let mut reader = LineReader::from_nonblocking(&b"GET /\nHost: x\n\nbody"[..])?
+    ///     .with_borrowed_lines(true);
+    /// reader.read_once()?;
+    /// assert_eq!(reader.lines_peek().position(|line| line == "\n"), Some(2));
+    /// reader.lines_clear();
+    /// assert!(!reader.has_lines());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn lines_peek(&self) -> impl Iterator<Item = &str> + '_ {
+        let borrowed = if self.borrowed {
+            &self.buf[self.consumed..self.consumed + self.complete_len()]
+        } else {
+            &[]
+        };
+        self.lines.iter().map(String::as_str).chain(
+            self.delimiters
+                .split(borrowed)
+                .map_while(|line| str::from_utf8(line).ok()),
+        )
+    }
+
+    /// Drops the available lines, as if they were retrieved.
+    pub fn lines_clear(&mut self) {
+        if self.borrowed {
+            self.consumed += self.complete_len();
+            return;
+        }
+        self.take_lines();
+    }
+
+    /// Keeps the raw bytes of the lines and only validates them as
+    /// UTF-8 when they are requested as strings.
+    ///
+    /// Applications that mostly forward bytes can then take the lines
+    /// with [`Self::lines_get_bytes`], which never fails on invalid
+    /// UTF-8 and doesn't pay for the validation. The lines are kept in
+    /// the internal buffer, as with [`Self::with_borrowed_lines`].
+    pub fn with_deferred_utf8(self, deferred: bool) -> Self {
+        self.with_borrowed_lines(deferred)
+    }
+
+    /// Returns the length of the complete lines in the buffer of a
+    /// reader that keeps the raw bytes, after the consumed ones.
+    fn complete_len(&self) -> usize {
+        let rest = &self.buf[self.consumed..self.used];
+        if self.at_eof {
+            rest.len()
+        } else {
+            self.delimiters.rfind(rest).unwrap_or(0)
+        }
+    }
+
+    /// Passes the complete lines of a reader that keeps the raw bytes
+    /// to `f`, consuming them; invalid UTF-8 is replaced with
+    /// `U+FFFD`, so that no data is dropped.
+    fn consume_borrowed(&mut self, f: &mut dyn FnMut(&str)) {
+        let end = self.consumed + self.complete_len();
+        for line in self.delimiters.split(&self.buf[self.consumed..end]) {
+            f(&String::from_utf8_lossy(line));
+        }
+        self.consumed = end;
+    }
+
+    /// Takes the available lines as raw bytes, without validating
+    /// them as UTF-8.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let mut reader = LineReader::from_nonblocking(&b"1\n\xff\n"[..])?
+    ///     .with_deferred_utf8(true);
+    /// while reader.read_once()? {}
+    /// assert_eq!(reader.lines_get_bytes(), vec![b"1\n".to_vec(), b"\xff\n".to_vec()]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn lines_get_bytes(&mut self) -> Vec<Vec<u8>> {
+        if !self.borrowed {
+            return self
+                .lines_get()
+                .into_iter()
+                .map(String::into_bytes)
+                .collect();
+        }
+        let end = self.consumed + self.complete_len();
+        let lines = self
+            .delimiters
+            .split(&self.buf[self.consumed..end])
+            .map(<[u8]>::to_vec)
+            .collect::<Vec<_>>();
+        self.consumed = end;
+        lines
+    }
+
+    /// Takes the available lines as strings, validating them as UTF-8.
+    ///
+    /// If any of the lines is not valid UTF-8, returns an
+    /// [`io::ErrorKind::InvalidData`] error and keeps all the lines,
+    /// which can then be taken with [`Self::lines_get_bytes`].
+    pub fn lines_get_str(&mut self) -> Result<Vec<String>, io::Error> {
+        if !self.borrowed {
+            return Ok(self.lines_get());
+        }
+        let end = self.consumed + self.complete_len();
+        let valid = str::from_utf8(&self.buf[self.consumed..end])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        // The delimiters are ASCII, so the lines end at char boundaries:
+        let mut start = 0;
+        let lines = self
+            .delimiters
+            .split(valid.as_bytes())
+            .map(|line| {
+                start += line.len();
+                valid[start - line.len()..start].to_owned()
+            })
+            .collect::<Vec<_>>();
+        self.consumed = end;
+        Ok(lines)
+    }
+
+    /// Removes the bytes consumed by [`Self::lines_iter`] from the
+    /// buffer.
+    fn compact(&mut self) {
+        let consumed = mem::take(&mut self.consumed);
+        if consumed > 0 {
+            self.buf.drain(..consumed);
+            self.used -= consumed;
+        }
+    }
+
+    /// Stops splitting lines: from now on, the buffered bytes and the
+    /// bytes read are returned as raw chunks by
+    /// [`raw_get`](Self::raw_get).
+    ///
+    /// Lines that are still queued are converted back to bytes and
+    /// placed before the partial line, so that no data is lost. Used by
+    /// protocols that switch from line mode to byte mode on the same
+    /// stream, like HTTP bodies or SMTP `DATA`.
+    pub fn switch_to_raw(&mut self) {
+        if self.raw {
+            return;
+        }
+        let mut raw = mem::take(&mut self.lines)
+            .into_iter()
+            .collect::<String>()
+            .into_bytes();
+        raw.extend_from_slice(&self.buf[self.consumed..self.used]);
+        self.metas.clear();
+        self.consumed = 0;
+        self.used = raw.len();
+        self.buf = raw;
+        self.partial_since = None;
+        self.raw = true;
+    }
+
+    /// Returns `true` if the reader was switched to raw mode.
+    pub fn is_raw(&self) -> bool {
+        self.raw
+    }
+
+    /// Takes the bytes buffered in raw mode.
+    ///
+    /// Returns an empty vector if the reader is not in raw mode.
+    pub fn raw_get(&mut self) -> Vec<u8> {
+        if !self.raw {
+            return Vec::new();
+        }
+        let chunk = self.buf[..self.used].to_vec();
+        self.used = 0;
+        chunk
+    }
+
+    /// Converts the reader into a [`BufRead`](io::BufRead) that
+    /// first yields the buffered bytes, complete lines and partial
+    /// line, and then the bytes read from the underlying reader.
+    ///
+    /// Lets parsers that consume a `BufRead` pick up where the line
+    /// reader stopped. The underlying reader is still non-blocking, so
+    /// reads can fail with [`io::ErrorKind::WouldBlock`] once the
+    /// buffered bytes are exhausted.
+    pub fn into_buf_read(self) -> io::BufReader<io::Chain<io::Cursor<Vec<u8>>, R>> {
+        let mut buffered: Vec<u8> = self.lines.iter().flat_map(|line| line.bytes()).collect();
+        buffered.extend_from_slice(&self.buf[self.consumed..self.used]);
+        io::BufReader::new(io::Cursor::new(buffered).chain(self.reader))
+    }
+
+    /// Sets a label that identifies the reader, like
+    /// `"worker-3 stderr"`.
+    ///
+    /// The label shows up in the [`Debug`] output, in the tracing
+    /// spans, and in the [`ReadError`] context of the errors returned
+    /// by the reader.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Sets the address of the peer, added to the [`ReadError`]
+    /// context of the errors returned by the reader.
+    pub fn with_peer(mut self, peer: impl fmt::Display) -> Self {
+        self.peer = Some(peer.to_string());
+        self
+    }
+
+    /// Wraps `err` in a [`ReadError`] with the identity of the reader,
+    /// if it has any.
+    fn add_context(&self, err: io::Error) -> io::Error {
+        if self.label.is_none() && self.fd.is_none() && self.peer.is_none() {
+            return err;
+        }
+        let kind = err.kind();
+        io::Error::new(
+            kind,
+            ReadError {
+                label: self.label.clone(),
+                fd: self.fd,
+                peer: self.peer.clone(),
+                source: err,
+            },
+        )
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// Reading directly from the underlying reader bypasses the line
+    /// buffer.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the partial line: the bytes after the last newline,
+    /// which may be a prompt waiting for input.
+    pub fn partial_line(&self) -> &[u8] {
+        &self.buf[self.partial_start()..self.used]
+    }
+
+    /// Returns the position of the partial line in the buffer, which
+    /// is after the complete lines of a reader that keeps the raw
+    /// bytes.
+    fn partial_start(&self) -> usize {
+        if !self.borrowed {
+            return 0;
+        }
+        let rest = &self.buf[self.consumed..self.used];
+        self.consumed + self.delimiters.rfind(rest).unwrap_or(0)
+    }
+
+    /// Returns the number of bytes held by the reader: the queued
+    /// lines plus the partial line.
+    pub fn buffered_bytes(&self) -> usize {
+        self.used - self.consumed + self.lines.iter().map(String::len).sum::<usize>()
+    }
+
+    /// Returns the size of the memory allocated for the read buffer,
+    /// which grows to fit the longest partial line seen plus a read.
+    pub fn buffer_capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Returns the number of complete lines waiting to be retrieved.
+    pub fn queued_lines(&self) -> usize {
+        if self.borrowed {
+            let end = self.consumed + self.complete_len();
+            return self.delimiters.split(&self.buf[self.consumed..end]).count();
+        }
+        self.lines.len()
+    }
+
+    /// Returns the total number of bytes read from the underlying
+    /// reader.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Returns the line-ending style detected in the first lines read,
+    /// or `None` if no line ending was read yet.
+    pub fn eol_style(&self) -> Option<EolStyle> {
+        self.eol.style()
+    }
+
+    /// Returns the generation of the underlying reader: the number of
+    /// times it was replaced with [`Self::replace_reader`].
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns a [`Checkpoint`] of the splitter state, which can be
+    /// used to resume reading later with
+    /// [`with_checkpoint`](Self::with_checkpoint).
+    pub fn checkpoint(&self) -> Checkpoint {
+        let mut pending: Vec<u8> = self.lines.iter().flat_map(|line| line.bytes()).collect();
+        pending.extend_from_slice(&self.buf[self.consumed..self.used]);
+        Checkpoint {
+            offset: self.bytes_read,
+            lines: self.lines_delivered,
+            pending,
+        }
+    }
+
+    /// Restores the splitter state from a [`Checkpoint`].
+    ///
+    /// The reader must be positioned at the offset of the checkpoint;
+    /// see [`Checkpoint::seek`]. The pending bytes are split into lines
+    /// in the next read.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the
+    /// checkpoint has more pending bytes than its offset, which means
+    /// it is corrupt.
+    pub fn with_checkpoint(mut self, checkpoint: Checkpoint) -> Result<Self, io::Error> {
+        let Some(line_offset) = checkpoint
+            .offset
+            .checked_sub(checkpoint.pending.len() as u64)
+        else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "checkpoint with more pending bytes than its offset",
+            ));
+        };
+        self.used = checkpoint.pending.len();
+        self.buf = checkpoint.pending;
+        self.bytes_read = checkpoint.offset;
+        self.line_offset = line_offset;
+        self.committed = self.line_offset;
+        self.lines_delivered = checkpoint.lines;
+        self.resplit = self.used > 0;
+        Ok(self)
+    }
+
+    /// Acknowledges that the lines up to `offset` were durably
+    /// processed, for at-least-once consumption.
+    ///
+    /// The offset of each line is in [`LineMeta::offset`]. Offsets
+    /// lower than the one already committed are ignored, so lines can
+    /// be acknowledged out of order.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use std::io::Cursor;
+    /// use lineriver::{LineRead, LineReader};
+    ///
+    /// let data = b"1\n2\n3\n";
+    /// let mut reader = LineReader::from_nonblocking(Cursor::new(data))?;
+    /// reader.read_once()?;
+    /// for (line, meta) in reader.lines_get_with_meta() {
+    ///     if line == "3\n" {
+    ///         break; // "crash" before processing this one
+    ///     }
+    ///     reader.commit(meta.offset);
+    /// }
+    /// // Restart from the last committed offset:
+    /// let checkpoint = reader.committed_checkpoint();
+    /// let mut source = Cursor::new(data);
+    /// checkpoint.seek(&mut source)?;
+    /// let mut reader = LineReader::from_nonblocking(source)?.with_checkpoint(checkpoint)?;
+    /// reader.read_available()?;
+    /// assert_eq!(reader.lines_get(), vec!["3\n"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn commit(&mut self, offset: u64) {
+        self.committed = self.committed.max(offset);
+    }
+
+    /// Returns the last offset committed with [`Self::commit`].
+    pub fn committed(&self) -> u64 {
+        self.committed
+    }
+
+    /// Returns a [`Checkpoint`] to restart reading right after the
+    /// last committed line, with [`Checkpoint::seek`] and
+    /// [`with_checkpoint`](Self::with_checkpoint).
+    ///
+    /// Lines delivered but not committed are read again, so that none
+    /// is lost if the consumer crashes before processing them.
+    pub fn committed_checkpoint(&self) -> Checkpoint {
+        Checkpoint::at_offset(self.committed)
+    }
+
+    /// Returns a snapshot of the internal state of the reader, for
+    /// troubleshooting.
+    ///
+    /// The snapshot includes a copy of the partial line, so it may
+    /// contain sensitive data.
+    pub fn dump_state(&self) -> ReaderState {
+        let partial = self.partial_line();
+        ReaderState {
+            used: partial.len(),
+            capacity: self.buffer_capacity(),
+            partial: partial.to_vec(),
+            queued_lines: self.queued_lines(),
+            eof: self.at_eof,
+            broken: self.broken,
+            paused: self.paused,
+        }
+    }
+
+    /// Returns `true` if a previous read failed with an error.
+    ///
+    /// Errors are sticky: once [`LineRead::read_once`] returns an
+    /// error, every further read returns an error as well, as the
+    /// internal buffer can't be trusted anymore. The reader should be
+    /// torn down.
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
+
+    /// Takes the error that broke the reader, if any.
+    ///
+    /// The reader stays broken after the error is taken.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Appends a complete line to the line buffer.
+    fn push_line(&mut self, line: String, delimiter: Option<u8>) {
+        self.line_offset += line.len() as u64;
+        if self.skip > 0 {
+            self.skip -= 1;
+            return;
+        }
+        match &mut self.take {
+            Some(0) => return,
+            Some(take) => {
+                *take -= 1;
+                if *take == 0 {
+                    // Synthetic EOF:
+                    self.at_eof = true;
+                }
+            }
+            None => {}
+        }
+        let mut meta = LineMeta {
+            delimiter,
+            offset: self.line_offset,
+            generation: self.generation,
+            checksum: self.checksums.then(|| meta::crc32(line.as_bytes())),
+            ..Default::default()
+        };
+        if let Some(stats) = &mut self.stats {
+            stats.line_len.record(line.len() as u64);
+            meta.completed = Some(Instant::now());
+        }
+        if let Some(format) = &self.timestamps {
+            let timestamp = format.parse_prefix(&line);
+            meta.timestamp_from_line = timestamp.is_some();
+            meta.timestamp = timestamp.or_else(|| Some(SystemTime::now()));
+        }
+        if self.history_len > 0 {
+            if self.history.len() == self.history_len {
+                self.history.pop_front();
+            }
+            self.history.push_back(line.clone());
+        }
+        #[cfg(feature = "metrics")]
+        metrics::counter!("lineriver_lines_read").increment(1);
+        if let Some(on_line) = &mut self.hooks.on_line {
+            on_line(line);
+            self.hooked = true;
+            return;
+        }
+        self.lines.push_back(line);
+        self.metas.push_back(meta);
+    }
+
+    /// Takes the lines and their metadata out of the buffers,
+    /// updating the latency statistics.
+    fn take_lines(&mut self) -> (Vec<String>, Vec<LineMeta>) {
+        self.record_delivery();
+        (
+            mem::take(&mut self.lines).into(),
+            mem::take(&mut self.metas).into(),
+        )
+    }
+
+    /// Updates the latency statistics and the delivered line count
+    /// with the lines that are about to leave the buffer.
+    fn record_delivery(&mut self) {
+        if let Some(stats) = &mut self.stats {
+            let now = Instant::now();
+            for completed in self.metas.iter().filter_map(|m| m.completed) {
+                record_latency(stats, now, completed);
+            }
+        }
+        self.lines_delivered += self.lines.len() as u64;
+    }
+
+    #[tracing::instrument(skip(self),fields(self.label = ?self.label, self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
     fn eval_buf(&mut self, mut pos: usize) -> Result<(), io::Error> {
         loop {
-            if let Some(inewline) = memchr::memchr(b'\n', &self.buf[pos..self.used]) {
-                // Found a newline.
-                let mut line = self.buf.split_off(pos + inewline + 1);
-                self.used -= pos + inewline + 1;
+            if let Some(len) = self.delimiters.find(&self.buf[pos..self.used]) {
+                // Found a delimiter.
+                let end = pos + len;
+                let delimiter = self.buf[end - 1];
+                let mut line = self.buf.split_off(end);
+                self.used -= end;
                 // They are swapped at the moment, unswap:
                 mem::swap(&mut self.buf, &mut line);
-                // Convert line to string and append to self.lines:
-                self.lines.push(u8array_to_string(&line)?);
                 pos = 0;
+                if mem::take(&mut self.discarding) {
+                    // End of a truncated line:
+                    self.line_offset += line.len() as u64;
+                    continue;
+                }
+                match self.max_line {
+                    Some((max, policy)) if line.len() > max => {
+                        if policy == LongLinePolicy::Error {
+                            return Err(LineTooLong { max }.into());
+                        }
+                        let len = utf8_prefix_len(&line, max)?;
+                        self.push_truncated(u8array_to_string(&line[..len])?, line.len() - len);
+                    }
+                    // Convert line to string and append to self.lines:
+                    _ => self.push_line(u8array_to_string(&line)?, Some(delimiter)),
+                }
             } else {
                 // No newline read.
                 return Ok(());
             }
         }
     }
-}
 
-impl<R: Read + Debug> LineRead for crate::LineReader<R> {
-    #[tracing::instrument(skip(self),fields(self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
-    fn eof(&self) -> bool {
-        self.at_eof
+    /// Moves the partial line to the line buffer, if it has been
+    /// sitting there for longer than the idle flush duration.
+    fn flush_idle(&mut self) -> Result<(), io::Error> {
+        let (Some(idle_flush), Some(since)) = (self.idle_flush, self.partial_since) else {
+            return Ok(());
+        };
+        if since.elapsed() < idle_flush {
+            return Ok(());
+        }
+        let valid = match str::from_utf8(&self.buf[..self.used]) {
+            Ok(_) => self.used,
+            // Incomplete UTF-8 sequence at the end, keep it:
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        if valid > 0 {
+            let rest = self.buf.split_off(valid);
+            let line = mem::replace(&mut self.buf, rest);
+            self.push_line(u8array_to_string(&line)?, None);
+            self.used -= valid;
+        }
+        self.partial_since = None;
+        Ok(())
     }
 
-    #[tracing::instrument(skip(self),fields(self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
-    fn read_once(&mut self) -> Result<bool, io::Error> {
-        if self.at_eof {
-            return Ok(false);
+    /// Applies the [`LongLinePolicy`] to the partial line, if it's over
+    /// the maximum length.
+    fn limit_partial(&mut self) -> Result<(), io::Error> {
+        let Some((max, policy)) = self.max_line else {
+            return Ok(());
+        };
+        if !self.discarding {
+            if self.used <= max {
+                return Ok(());
+            }
+            if policy == LongLinePolicy::Error {
+                return Err(LineTooLong { max }.into());
+            }
+            let len = utf8_prefix_len(&self.buf[..self.used], max)?;
+            let rest = self.buf.split_off(len);
+            let line = mem::replace(&mut self.buf, rest);
+            self.used -= len;
+            let discard = self.discard_partial();
+            self.push_truncated(u8array_to_string(&line)?, discard);
+            self.discarding = true;
+            return Ok(());
+        }
+        let discard = self.discard_partial();
+        self.line_offset += discard as u64;
+        Ok(())
+    }
+
+    /// Drops the partial line of a truncated line, returning the
+    /// number of bytes dropped.
+    fn discard_partial(&mut self) -> usize {
+        // Keep what can be the start of a terminator:
+        let discard = self.used - self.delimiters.lookback().min(self.used);
+        self.buf.drain(..discard);
+        self.used -= discard;
+        discard
+    }
+
+    /// Appends the first bytes of a line that is over the maximum
+    /// length, counting the `discarded` bytes in its offset.
+    fn push_truncated(&mut self, line: String, discarded: usize) {
+        self.line_offset += discarded as u64;
+        self.push_line(line, None);
+    }
+
+    /// Checks the partial line of a reader that keeps the raw bytes
+    /// against the maximum length.
+    fn limit_borrowed(&self) -> Result<(), io::Error> {
+        match self.max_line {
+            Some((max, _)) if self.used - self.consumed - self.complete_len() > max => {
+                Err(LineTooLong { max }.into())
+            }
+            _ => Ok(()),
         }
+    }
+
+    /// Reads once from the underlying reader into the buffer and
+    /// splits out the complete lines.
+    fn read_buf(&mut self) -> Result<(), io::Error> {
+        self.compact();
         if self.buf.len() < self.used + BUFFER_SIZE {
             self.buf.resize(self.used + BUFFER_SIZE, 0);
         }
         let oldused = self.used;
         let buf = self.buf.as_mut_slice();
-        let r = self.reader.read(&mut buf[self.used..]);
+        let mut retries = self.eintr_retries;
+        let r = loop {
+            match self.reader.read(&mut buf[self.used..]) {
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted && retries > 0 => {
+                    retries -= 1;
+                }
+                r => break r,
+            }
+        };
         match r {
             Ok(0) => {
-                if self.used > 0 {
+                if mem::take(&mut self.discarding) {
+                    self.line_offset += self.used as u64;
+                    self.used = 0;
+                }
+                if self.used > 0 && !self.raw && !self.borrowed {
                     let mut lastline = mem::take(&mut self.buf);
                     lastline.truncate(self.used);
-                    self.lines.push(u8array_to_string(&lastline)?);
+                    self.push_line(u8array_to_string(&lastline)?, None);
                     self.used = 0;
                 }
+                self.partial_since = None;
                 self.at_eof = true;
             }
             Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
                 // No data availble, just let the function return
+                #[cfg(feature = "metrics")]
+                metrics::counter!("lineriver_wouldblock").increment(1);
             }
             Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {
-                // Interrupted, just let the function return
+                // Interrupted (and out of retries), just let the function return
             }
             Ok(len) => {
+                #[cfg(feature = "metrics")]
+                metrics::counter!("lineriver_bytes_read").increment(len as u64);
+                self.last_data = Instant::now();
+                self.stall_reported = false;
                 self.used += len;
+                self.bytes_read += len as u64;
+                self.eol.feed(&self.buf[oldused..self.used]);
+                if let Some(on_data) = &mut self.hooks.on_data {
+                    on_data(&self.buf[oldused..self.used]);
+                }
+                if self.borrowed {
+                    return self.limit_borrowed();
+                }
+                if self.raw {
+                    return Ok(());
+                }
+                let numlines = self.lines.len();
                 // Look for newlines from "oldused" forward:
-                self.eval_buf(oldused)?;
+                self.eval_buf(oldused.saturating_sub(self.delimiters.lookback()))?;
+                self.limit_partial()?;
+                if self.used == 0 {
+                    self.partial_since = None;
+                } else if self.partial_since.is_none() || self.lines.len() > numlines {
+                    self.partial_since = Some(Instant::now());
+                }
+                #[cfg(feature = "metrics")]
+                metrics::gauge!("lineriver_buffer_bytes").set(self.used as f64);
             }
             Err(err) => {
                 return Err(err);
             }
         }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek + Debug> LineReader<R> {
+    /// Moves the underlying reader to `offset`, for instance to start
+    /// tailing a file from a known position or to resume after a
+    /// restart.
+    ///
+    /// The partial line and the queued lines are discarded, EOF is
+    /// cleared and the offsets in [`LineMeta`] and
+    /// [`Self::committed`] continue from `offset`. The count of lines
+    /// delivered is reset, as the number of lines before `offset` is
+    /// not known.
+    pub fn seek_to(&mut self, offset: u64) -> Result<(), io::Error> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.buf.clear();
+        self.used = 0;
+        self.consumed = 0;
+        self.resplit = false;
+        self.partial_since = None;
+        self.lines.clear();
+        self.metas.clear();
+        self.discarding = false;
+        self.eol = Default::default();
+        self.at_eof = false;
+        self.bytes_read = offset;
+        self.line_offset = offset;
+        self.committed = offset;
+        self.lines_delivered = 0;
+        Ok(())
+    }
+
+    /// Returns the offset in the underlying reader where the next
+    /// line begins, which is where [`Self::seek_to`] should resume to
+    /// not lose or repeat lines.
+    ///
+    /// Lines that were split but not retrieved yet are before this
+    /// offset.
+    pub fn current_offset(&self) -> u64 {
+        self.line_offset
+    }
+}
+
+impl<R: Read + Debug> LineRead for crate::LineReader<R> {
+    #[tracing::instrument(skip(self),fields(self.label = ?self.label, self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
+    fn eof(&self) -> bool {
+        self.at_eof
+    }
+
+    #[tracing::instrument(skip(self),fields(self.label = ?self.label, self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        if self.broken {
+            return Err(
+                self.add_context(io::Error::other("LineReader is broken by a previous error"))
+            );
+        }
+        if self.at_eof {
+            return Ok(false);
+        }
+        if self.paused || self.is_full() {
+            return Ok(true);
+        }
+        // Split the pending bytes restored from a checkpoint:
+        let resplit = if mem::take(&mut self.resplit) && !self.borrowed {
+            self.eval_buf(0)
+        } else {
+            Ok(())
+        };
+        if let Err(err) = resplit
+            .and_then(|_| self.read_buf())
+            .and_then(|_| self.flush_idle())
+        {
+            let err = self.add_context(err);
+            self.broken = true;
+            self.error = Some(io::Error::new(err.kind(), err.to_string()));
+            if let Some(on_error) = &mut self.hooks.on_error {
+                on_error(&err);
+            }
+            return Err(err);
+        }
+        if self.at_eof {
+            if let Some(on_eof) = &mut self.hooks.on_eof {
+                on_eof();
+            }
+        }
+        Ok(true)
+    }
+
+    /// Reads all available data into the internal line buffer, or at
+    /// least until a complete line is available.
+    ///
+    /// Returns early, without an error, if the reader's
+    /// [`CancelToken`] gets cancelled or if the reader is paused.
+    #[tracing::instrument(skip(self),fields(self.label = ?self.label, self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
+    fn read_available(&mut self) -> Result<(), io::Error> {
+        self.hooked = false;
+        while !self.is_cancelled()
+            && !self.paused
+            && self.read_once()?
+            && !self.has_lines()
+            && !self.hooked
+        {}
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self),fields(self.label = ?self.label, self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
+    fn lines_get(&mut self) -> Vec<String> {
+        if self.borrowed {
+            let mut lines = Vec::new();
+            self.consume_borrowed(&mut |line| lines.push(line.to_owned()));
+            return lines;
+        }
+        self.take_lines().0
+    }
+
+    #[tracing::instrument(skip(self, f),fields(self.label = ?self.label, self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
+    fn consume_lines(&mut self, f: &mut dyn FnMut(&str)) {
+        if self.borrowed {
+            self.consume_borrowed(f);
+            return;
+        }
+        self.record_delivery();
+        self.metas.clear();
+        for line in self.lines.drain(..) {
+            f(&line);
+        }
+    }
+
+    #[tracing::instrument(skip(self),fields(self.label = ?self.label, self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
+    fn pop_line(&mut self) -> Option<String> {
+        if self.borrowed {
+            let rest = &self.buf[self.consumed..self.used];
+            let len = match self.delimiters.find(rest) {
+                Some(len) => len,
+                None if self.at_eof && !rest.is_empty() => rest.len(),
+                None => return None,
+            };
+            let line = String::from_utf8_lossy(&rest[..len]).into_owned();
+            self.consumed += len;
+            return Some(line);
+        }
+        let line = self.lines.pop_front()?;
+        let meta = self.metas.pop_front();
+        if let (Some(stats), Some(completed)) = (&mut self.stats, meta.and_then(|m| m.completed)) {
+            record_latency(stats, Instant::now(), completed);
+        }
+        self.lines_delivered += 1;
+        Some(line)
+    }
+
+    #[tracing::instrument(skip(self),fields(self.label = ?self.label, self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
+    fn has_lines(&mut self) -> bool {
+        if self.borrowed {
+            let rest = &self.buf[self.consumed..self.used];
+            return self.delimiters.find(rest).is_some() || (self.at_eof && !rest.is_empty());
+        }
+        !self.lines.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.max_queued
+            .is_some_and(|max| self.queued_lines() >= max)
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.delimiters.terminates(line.as_bytes())
+    }
+}
+
+/// Lines returned by [`LineReader::read_until_sentinel`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SentinelScope {
+    /// The lines of the scope that were available.
+    pub lines: Vec<String>,
+    /// `true` if the sentinel line was found, ending the scope.
+    pub complete: bool,
+}
+
+/// [`LineRead`] view of a [`LineReader`] returned by
+/// [`LineReader::take_until`].
+#[derive(Debug)]
+pub struct TakeUntil<'a, R, P> {
+    reader: &'a mut LineReader<R>,
+    pattern: P,
+    done: bool,
+    lines: VecDeque<String>,
+    full: FullLatch,
+}
+
+impl<R: Read + Debug, P: Pattern> TakeUntil<'_, R, P> {
+    /// Moves the lines of the scope from the reader to our buffer.
+    fn pull(&mut self) {
+        self.full
+            .update(self.reader.is_full(), !self.lines.is_empty());
+        if self.done {
+            return;
+        }
+        let pattern = &self.pattern;
+        let scope = self.reader.take_scope(|line| pattern.matches(line), true);
+        self.lines.extend(scope.lines);
+        self.done = scope.complete;
+    }
+
+    /// Returns `true` if the matching line was found.
+    pub fn is_complete(&self) -> bool {
+        self.done
+    }
+}
+
+impl<R: Read + Debug, P: Pattern> LineRead for TakeUntil<'_, R, P> {
+    fn eof(&self) -> bool {
+        self.done || self.reader.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        if self.eof() {
+            return Ok(false);
+        }
+        self.pull();
+        if !self.done {
+            self.reader.read_once()?;
+            self.pull();
+        }
         Ok(true)
     }
 
-    #[tracing::instrument(skip(self),fields(self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
     fn lines_get(&mut self) -> Vec<String> {
-        mem::take(&mut self.lines)
+        self.pull();
+        mem::take(&mut self.lines).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        self.lines.pop_front()
     }
 
-    #[tracing::instrument(skip(self),fields(self.at_eof = %self.at_eof, self.num_lines=self.lines.len()))]
     fn has_lines(&mut self) -> bool {
+        self.pull();
         !self.lines.is_empty()
     }
+
+    fn is_full(&self) -> bool {
+        self.full
+            .is_full(self.reader.is_full(), !self.lines.is_empty())
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.reader.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.reader.is_terminated(line)
+    }
+}
+
+/// Iterator over the lines borrowed from the buffer of a
+/// [`LineReader`], returned by [`LineReader::lines_iter`].
+#[derive(Debug)]
+pub struct LinesIter<'a> {
+    buf: &'a [u8],
+    pos: &'a mut usize,
+    eof: bool,
+    delimiters: &'a Delimiters,
+}
+
+impl<'a> Iterator for LinesIter<'a> {
+    type Item = Result<&'a str, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.buf[*self.pos..];
+        let len = match self.delimiters.find(rest) {
+            Some(len) => len,
+            None if self.eof && !rest.is_empty() => rest.len(),
+            None => return None,
+        };
+        *self.pos += len;
+        Some(
+            str::from_utf8(&rest[..len]).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        )
+    }
 }
 
 impl<R: AsRawFd> AsRawFd for LineReader<R> {
@@ -158,3 +2014,112 @@ impl<R: AsFd> AsFd for LineReader<R> {
 impl<R: AsFd + Read + Debug> LineReadFd for LineReader<R> {}
 
 impl<R: AsFd + AsRawFd + Read + Debug> LineReadRawAndFd for LineReader<R> {}
+
+/// Non-blocking line reader that returns the lines as bytes, for
+/// streams with binary or non-UTF-8 content.
+///
+/// It's a [`LineReader`] with [deferred UTF-8
+/// validation](LineReader::with_deferred_utf8) that never validates:
+/// lines are split at the delimiters and returned as they are, so
+/// invalid UTF-8 doesn't break the reader with an
+/// [`io::ErrorKind::InvalidData`] error.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), std::io::Error> {
+/// use lineriver::LineReaderBytes;
+///
+/// let mut reader = LineReaderBytes::from_nonblocking(&b"\xff\xfe\nlast"[..])?;
+/// while reader.read_once()? {}
+/// assert_eq!(reader.lines_get(), vec![b"\xff\xfe\n".to_vec(), b"last".to_vec()]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LineReaderBytes<R> {
+    inner: LineReader<R>,
+}
+
+impl<R: Read + AsRawFd + Debug> LineReaderBytes<R> {
+    /// Creates a new `LineReaderBytes`, setting the underlying reader
+    /// as non-blocking.
+    pub fn new(reader: R) -> Result<Self, io::Error> {
+        Ok(Self::from_line_reader(LineReader::new(reader)?))
+    }
+}
+
+impl<R: Read + Debug> LineReaderBytes<R> {
+    /// Creates a new `LineReaderBytes`.
+    ///
+    /// Assumes the reader is already non-blocking, not configuring
+    /// anything in the underlying descriptor.
+    pub fn from_nonblocking(reader: R) -> Result<Self, io::Error> {
+        Ok(Self::from_line_reader(LineReader::from_nonblocking(
+            reader,
+        )?))
+    }
+
+    /// Creates a new `LineReaderBytes` from a [`LineReader`], to use
+    /// its configuration methods, like
+    /// [`with_delimiters`](LineReader::with_delimiters).
+    pub fn from_line_reader(inner: LineReader<R>) -> Self {
+        Self {
+            inner: inner.with_deferred_utf8(true),
+        }
+    }
+
+    /// Returns `true` if the reader has reached EOF.
+    pub fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    /// Reads once from the underlying reader; see
+    /// [`LineRead::read_once`].
+    pub fn read_once(&mut self) -> Result<bool, io::Error> {
+        self.inner.read_once()
+    }
+
+    /// Reads until a complete line is available or the reader would
+    /// block; see [`LineRead::read_available`].
+    pub fn read_available(&mut self) -> Result<(), io::Error> {
+        self.inner.read_available()
+    }
+
+    /// Returns `true` if there are lines available.
+    pub fn has_lines(&mut self) -> bool {
+        self.inner.has_lines()
+    }
+
+    /// Takes the available lines, with their delimiters.
+    pub fn lines_get(&mut self) -> Vec<Vec<u8>> {
+        self.inner.lines_get_bytes()
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+
+    /// Returns the [`LineReader`] that does the splitting.
+    pub fn into_line_reader(self) -> LineReader<R> {
+        self.inner
+    }
+}
+
+impl<R: AsRawFd> AsRawFd for LineReaderBytes<R> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<R: AsFd> AsFd for LineReaderBytes<R> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
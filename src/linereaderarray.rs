@@ -0,0 +1,171 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineReaderArray`], a variant of
+//! [`LineReader`](crate::LineReader) with an inline read buffer.
+
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::io::{self, Read};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::{mem, str};
+
+use crate::blocking;
+use crate::lineread::{LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+
+/// Variant of [`LineReader`](crate::LineReader) whose read buffer is
+/// an inline `[u8; N]`, avoiding heap usage for the buffer entirely.
+///
+/// `N` is also the maximum length of a line, including its newline; a
+/// longer line makes [`LineRead::read_once`] return an
+/// [`io::ErrorKind::InvalidData`] error.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), std::io::Error> {
+/// use lineriver::{LineRead, LineReaderArray};
+///
+/// let mut reader = LineReaderArray::<_, 64>::from_nonblocking(&b"1\n2\n"[..]);
+/// reader.read_available()?;
+/// assert_eq!(reader.lines_get(), vec!["1\n", "2\n"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LineReaderArray<R, const N: usize> {
+    reader: R,
+    buf: [u8; N],
+    used: usize,
+    at_eof: bool,
+    lines: VecDeque<String>,
+}
+
+impl<R: Read + AsRawFd + Debug, const N: usize> LineReaderArray<R, N> {
+    /// Creates a new LineReaderArray, setting the underlying descriptor
+    /// as non-blocking.
+    pub fn new(reader: R) -> Result<Self, io::Error> {
+        blocking::disable(reader.as_raw_fd())?;
+        Ok(Self::from_nonblocking(reader))
+    }
+}
+
+impl<R: Read + Debug, const N: usize> LineReaderArray<R, N> {
+    /// Creates a new LineReaderArray.
+    ///
+    /// Assumes the reader is already non-blocking, not configuring
+    /// anything in the underlying descriptor.
+    pub fn from_nonblocking(reader: R) -> Self {
+        Self {
+            reader,
+            buf: [0; N],
+            used: 0,
+            at_eof: false,
+            lines: VecDeque::new(),
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the partial line in the buffer.
+    pub fn partial_line(&self) -> &[u8] {
+        &self.buf[..self.used]
+    }
+
+    fn push_line(&mut self, len: usize) -> Result<(), io::Error> {
+        let line = str::from_utf8(&self.buf[..len])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.lines.push_back(line.to_owned());
+        self.buf.copy_within(len..self.used, 0);
+        self.used -= len;
+        Ok(())
+    }
+
+    fn eval_buf(&mut self, mut pos: usize) -> Result<(), io::Error> {
+        while let Some(inewline) = memchr::memchr(b'\n', &self.buf[pos..self.used]) {
+            self.push_line(pos + inewline + 1)?;
+            pos = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Debug, const N: usize> LineRead for LineReaderArray<R, N> {
+    fn eof(&self) -> bool {
+        self.at_eof
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        if self.at_eof {
+            return Ok(false);
+        }
+        if self.used == N {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line longer than the {} bytes buffer", N),
+            ));
+        }
+        let oldused = self.used;
+        match self.reader.read(&mut self.buf[self.used..]) {
+            Ok(0) => {
+                if self.used > 0 {
+                    self.push_line(self.used)?;
+                }
+                self.at_eof = true;
+            }
+            Ok(len) => {
+                self.used += len;
+                self.eval_buf(oldused)?;
+            }
+            Err(ref err)
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+        Ok(true)
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        mem::take(&mut self.lines).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.lines.pop_front()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        !self.lines.is_empty()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        // Lines are only ever split at the newline, see eval_buf:
+        line.as_bytes().last() == Some(&b'\n')
+    }
+}
+
+impl<R: AsRawFd, const N: usize> AsRawFd for LineReaderArray<R, N> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.reader.as_raw_fd()
+    }
+}
+
+impl<R: AsRawFd + Read + Debug, const N: usize> LineReadRawFd for LineReaderArray<R, N> {}
+
+impl<R: AsFd, const N: usize> AsFd for LineReaderArray<R, N> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.reader.as_fd()
+    }
+}
+
+impl<R: AsFd + Read + Debug, const N: usize> LineReadFd for LineReaderArray<R, N> {}
+
+impl<R: AsFd + AsRawFd + Read + Debug, const N: usize> LineReadRawAndFd for LineReaderArray<R, N> {}
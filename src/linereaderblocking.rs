@@ -0,0 +1,131 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineReaderBlocking`], a [`LineRead`] that uses
+//! ordinary blocking reads.
+
+use std::fmt::Debug;
+use std::io::{self, Read};
+
+use crate::lineread::LineRead;
+use crate::linereader::LineReader;
+
+/// [`LineRead`] over a blocking reader, without any file descriptor
+/// manipulation.
+///
+/// Each [`LineRead::read_once`] call blocks in a single `read`, so
+/// this is meant for readers that are driven by their own worker
+/// thread, or that never block for long, like regular files. It lets
+/// them be kept in the same `Vec<Box<dyn LineRead>>` as non-blocking
+/// [`LineReader`]s and handled by the same code.
+///
+/// The reader doesn't need a file descriptor at all, so it also works
+/// with in-memory readers and decompressors.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), std::io::Error> {
+/// use std::os::unix::net::UnixStream;
+/// use lineriver::{LineRead, LineReader, LineReaderBlocking};
+///
+/// let (_tx, socket) = UnixStream::pair()?;
+/// let file = std::fs::File::open("/dev/null")?;
+/// let mut readers: Vec<Box<dyn LineRead + Send>> = vec![
+///     LineReader::boxed(socket)?,
+///     LineReaderBlocking::boxed(file)?,
+///     LineReaderBlocking::boxed(&b"a\nb\n"[..])?,
+/// ];
+/// readers[2].read_available()?;
+/// assert_eq!(readers[2].lines_get(), vec!["a\n", "b\n"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LineReaderBlocking<R> {
+    inner: LineReader<R>,
+}
+
+impl<R: Read + Debug> LineReaderBlocking<R> {
+    /// Creates a new `LineReaderBlocking`, leaving `reader` as it is.
+    pub fn new(reader: R) -> Result<Self, io::Error> {
+        Ok(Self::from_line_reader(LineReader::from_nonblocking(
+            reader,
+        )?))
+    }
+
+    /// Creates a new `LineReaderBlocking` from a [`LineReader`]
+    /// created with [`LineReader::from_nonblocking`], to use its
+    /// configuration methods.
+    pub fn from_line_reader(inner: LineReader<R>) -> Self {
+        Self { inner }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// Reading from it directly corrupts the stream of lines.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+
+    /// Returns the [`LineReader`] that does the splitting.
+    pub fn into_line_reader(self) -> LineReader<R> {
+        self.inner
+    }
+}
+
+impl<R: Read + Debug + Send + 'static> LineReaderBlocking<R> {
+    /// Creates a new `LineReaderBlocking` with [`Self::new`] and boxes
+    /// it as a [`LineRead`] trait object.
+    pub fn boxed(reader: R) -> Result<Box<dyn LineRead + Send>, io::Error> {
+        Ok(Box::new(Self::new(reader)?))
+    }
+}
+
+impl<R: Read + Debug> LineRead for LineReaderBlocking<R> {
+    fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        self.inner.read_once()
+    }
+
+    fn read_available(&mut self) -> Result<(), io::Error> {
+        self.inner.read_available()
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.inner.lines_get()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.inner.pop_line()
+    }
+
+    fn consume_lines(&mut self, f: &mut dyn FnMut(&str)) {
+        self.inner.consume_lines(f)
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.inner.has_lines()
+    }
+
+    fn is_full(&self) -> bool {
+        self.inner.is_full()
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
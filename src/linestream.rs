@@ -0,0 +1,246 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineStream`], a bidirectional line stream made of
+//! a [`LineReader`] and a [`LineWriter`].
+
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+
+use crate::lineread::{LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+use crate::linereader::LineReader;
+use crate::linewriter::LineWriter;
+
+/// Bidirectional non-blocking line stream.
+///
+/// Reading is done through the [`LineRead`] implementation, and
+/// writing with [`write_line`](Self::write_line) and
+/// [`flush`](Self::flush). The descriptors returned by [`AsFd`] and
+/// [`AsRawFd`] are the ones of the read side.
+#[derive(Debug)]
+pub struct LineStream<R, W> {
+    reader: LineReader<R>,
+    writer: LineWriter<W>,
+    finishing: bool,
+    write_closed: bool,
+}
+
+impl LineStream<File, File> {
+    /// Creates a stream that reads from stdin and writes to stdout, as
+    /// used by inetd and by systemd services with `Accept=yes`.
+    ///
+    /// Duplicates of descriptors 0 and 1 are used and set as
+    /// non-blocking. In those setups both are usually the same socket,
+    /// and the flag is shared. Note that, as the flag belongs to the
+    /// open file, other processes that share the terminal or pipe
+    /// also see it.
+    pub fn stdio() -> Result<Self, io::Error> {
+        let stdin = File::from(io::stdin().as_fd().try_clone_to_owned()?);
+        let stdout = File::from(io::stdout().as_fd().try_clone_to_owned()?);
+        Ok(Self {
+            reader: LineReader::new(stdin)?,
+            writer: LineWriter::new(stdout)?,
+            finishing: false,
+            write_closed: false,
+        })
+    }
+}
+
+impl<R: Read + AsRawFd + Debug, W: Write + AsRawFd + Debug> LineStream<R, W> {
+    /// Creates a new LineStream, setting both descriptors as
+    /// non-blocking.
+    pub fn new(reader: R, writer: W) -> Result<Self, io::Error> {
+        Ok(Self {
+            reader: LineReader::new(reader)?,
+            writer: LineWriter::new(writer)?,
+            finishing: false,
+            write_closed: false,
+        })
+    }
+}
+
+impl<R: Read + Debug, W: Write> LineStream<R, W> {
+    /// Creates a new LineStream from a reader and a writer.
+    pub fn from_parts(reader: LineReader<R>, writer: LineWriter<W>) -> Self {
+        Self {
+            reader,
+            writer,
+            finishing: false,
+            write_closed: false,
+        }
+    }
+
+    /// Queues a line, appending the configured terminator of the
+    /// writer side (see [`LineWriter::with_terminator`]), and tries to
+    /// write everything that is pending.
+    ///
+    /// Fails with [`io::ErrorKind::BrokenPipe`] after
+    /// [`finish_writing`](Self::finish_writing).
+    pub fn write_line(&mut self, line: &str) -> Result<(), io::Error> {
+        if self.finishing {
+            return Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "writing already finished",
+            ));
+        }
+        self.writer.write_line(line)
+    }
+
+    /// Writes as much of the pending data as possible without
+    /// blocking.
+    ///
+    /// Returns `true` if there is no more pending data.
+    pub fn flush(&mut self) -> Result<bool, io::Error> {
+        self.writer.flush()
+    }
+
+    /// Gets a reference to the read side.
+    pub fn reader(&self) -> &LineReader<R> {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the read side.
+    pub fn reader_mut(&mut self) -> &mut LineReader<R> {
+        &mut self.reader
+    }
+
+    /// Gets a reference to the write side.
+    pub fn writer(&self) -> &LineWriter<W> {
+        &self.writer
+    }
+
+    /// Gets a mutable reference to the write side.
+    pub fn writer_mut(&mut self) -> &mut LineWriter<W> {
+        &mut self.writer
+    }
+
+    /// Returns the read and write sides.
+    pub fn into_parts(self) -> (LineReader<R>, LineWriter<W>) {
+        (self.reader, self.writer)
+    }
+}
+
+impl<R: Read + Debug, W: Write + AsRawFd> LineStream<R, W> {
+    /// Finishes writing: flushes the pending output and then closes
+    /// the write side, while the read side keeps going until EOF.
+    ///
+    /// Many line protocols need this half-close for the peer to
+    /// respond; `sort`, for instance, only outputs anything after its
+    /// input is closed. Sockets are shut down with `shutdown(2)`; other
+    /// descriptors, like pipes, are closed by replacing them with
+    /// `/dev/null`.
+    ///
+    /// Returns `false` if the pending output couldn't be written
+    /// without blocking; in that case, this method should be called
+    /// again when the descriptor becomes writable. Returns `true` once
+    /// the write side is closed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::process::{Command, Stdio};
+    /// use lineriver::{LineReadRawFd, LineStream};
+    ///
+    /// let child = Command::new("sort")
+    ///     .stdin(Stdio::piped())
+    ///     .stdout(Stdio::piped())
+    ///     .spawn()?;
+    /// let mut stream = LineStream::new(child.stdout.unwrap(), child.stdin.unwrap())?;
+    /// stream.write_line("b")?;
+    /// stream.write_line("a")?;
+    /// while !stream.finish_writing()? {}
+    /// assert_eq!(stream.collect_all(None)?, vec!["a\n", "b\n"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn finish_writing(&mut self) -> Result<bool, io::Error> {
+        self.finishing = true;
+        if self.write_closed {
+            return Ok(true);
+        }
+        if !self.writer.flush()? {
+            return Ok(false);
+        }
+        let fd = self.writer.as_raw_fd();
+        if unsafe { libc::shutdown(fd, libc::SHUT_WR) } != 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::ENOTSOCK) {
+                return Err(err);
+            }
+            // Not a socket: close the descriptor while keeping it valid
+            // for the writer, which closes it again when dropped.
+            let devnull = File::options().write(true).open("/dev/null")?;
+            if unsafe { libc::dup2(devnull.as_raw_fd(), fd) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        self.write_closed = true;
+        Ok(true)
+    }
+
+    /// Returns `true` once the write side was closed by
+    /// [`finish_writing`](Self::finish_writing).
+    pub fn is_write_closed(&self) -> bool {
+        self.write_closed
+    }
+}
+
+impl<R: Read + Debug, W: Write> LineRead for LineStream<R, W> {
+    fn eof(&self) -> bool {
+        self.reader.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        self.reader.read_once()
+    }
+
+    fn read_available(&mut self) -> Result<(), io::Error> {
+        self.reader.read_available()
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.reader.lines_get()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.reader.pop_line()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.reader.has_lines()
+    }
+
+    fn is_full(&self) -> bool {
+        self.reader.is_full()
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.reader.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.reader.is_terminated(line)
+    }
+}
+
+impl<R: AsRawFd, W> AsRawFd for LineStream<R, W> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.reader.as_raw_fd()
+    }
+}
+
+impl<R: AsFd, W> AsFd for LineStream<R, W> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.reader.as_fd()
+    }
+}
+
+impl<R: AsRawFd + Read + Debug, W: Write> LineReadRawFd for LineStream<R, W> {}
+
+impl<R: AsFd + Read + Debug, W: Write> LineReadFd for LineStream<R, W> {}
+
+impl<R: AsFd + AsRawFd + Read + Debug, W: Write> LineReadRawAndFd for LineStream<R, W> {}
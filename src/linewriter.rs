@@ -0,0 +1,186 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineWriter`], the non-blocking counterpart of
+//! [`LineReader`](crate::LineReader).
+
+use std::fmt::Debug;
+use std::io::{self, Write};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+
+use crate::blocking;
+
+/// Buffered non-blocking writer of lines.
+///
+/// Lines are queued and written as far as the underlying writer
+/// accepts without blocking; the rest stays queued until the next
+/// [`flush`](Self::flush), which should be called when the descriptor
+/// becomes writable.
+#[derive(Debug)]
+pub struct LineWriter<W> {
+    writer: W,
+    pending: Vec<u8>,
+    written: usize,
+    terminator: Vec<u8>,
+    dot_stuffing: bool,
+}
+
+impl<W: Write + AsRawFd + Debug> LineWriter<W> {
+    /// Creates a new LineWriter, setting the underlying descriptor as
+    /// non-blocking.
+    pub fn new(writer: W) -> Result<Self, io::Error> {
+        blocking::disable(writer.as_raw_fd())?;
+        Ok(Self::from_nonblocking(writer))
+    }
+}
+
+impl<W: Write> LineWriter<W> {
+    /// Creates a new LineWriter.
+    ///
+    /// Assumes the writer is already non-blocking, not configuring
+    /// anything in the underlying descriptor.
+    pub fn from_nonblocking(writer: W) -> Self {
+        Self {
+            writer,
+            pending: Vec::new(),
+            written: 0,
+            terminator: b"\n".to_vec(),
+            dot_stuffing: false,
+        }
+    }
+
+    /// Sets the terminator appended to the lines, `\n` by default.
+    ///
+    /// Lines that end with `\n` or `\r\n` get that ending replaced by
+    /// the terminator; an empty terminator makes the writer write the
+    /// lines as given.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::LineWriter;
+    ///
+    /// let mut writer = LineWriter::from_nonblocking(Vec::new()).with_terminator("\r\n");
+    /// writer.write_line("1\n")?;
+    /// writer.write_line("2")?;
+    /// assert_eq!(writer.get_ref(), b"1\r\n2\r\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_terminator(mut self, terminator: impl Into<Vec<u8>>) -> Self {
+        self.terminator = terminator.into();
+        self
+    }
+
+    /// Enables the dot-stuffing used by SMTP and NNTP to send
+    /// multi-line data: lines that start with `.` get an extra `.`
+    /// prepended, and [`Self::write_terminating_dot`] ends the data.
+    ///
+    /// Also sets the terminator to `\r\n`, as these protocols require.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use lineriver::LineWriter;
+    ///
+    /// let mut writer = LineWriter::from_nonblocking(Vec::new()).with_dot_stuffing();
+    /// writer.write_line("Hello\n")?;
+    /// writer.write_line(".hidden\n")?;
+    /// writer.write_terminating_dot()?;
+    /// assert_eq!(writer.get_ref(), b"Hello\r\n..hidden\r\n.\r\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_dot_stuffing(mut self) -> Self {
+        self.dot_stuffing = true;
+        self.terminator = b"\r\n".to_vec();
+        self
+    }
+
+    /// Queues the `.` line that ends dot-stuffed data, and tries to
+    /// write everything that is pending.
+    pub fn write_terminating_dot(&mut self) -> Result<(), io::Error> {
+        self.pending.push(b'.');
+        self.pending.extend_from_slice(&self.terminator);
+        self.flush()?;
+        Ok(())
+    }
+
+    /// Queues a line, appending the terminator if it doesn't end with
+    /// it, and tries to write everything that is pending.
+    pub fn write_line(&mut self, line: &str) -> Result<(), io::Error> {
+        let line = line.as_bytes();
+        if self.dot_stuffing && line.starts_with(b".") {
+            self.pending.push(b'.');
+        }
+        if self.terminator.is_empty() || line.ends_with(&self.terminator) {
+            self.pending.extend_from_slice(line);
+        } else {
+            let line = line
+                .strip_suffix(b"\n")
+                .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+                .unwrap_or(line);
+            self.pending.extend_from_slice(line);
+            self.pending.extend_from_slice(&self.terminator);
+        }
+        self.flush()?;
+        Ok(())
+    }
+
+    /// Writes as much of the pending data as possible without
+    /// blocking.
+    ///
+    /// Returns `true` if there is no more pending data.
+    pub fn flush(&mut self) -> Result<bool, io::Error> {
+        while self.written < self.pending.len() {
+            match self.writer.write(&self.pending[self.written..]) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(len) => self.written += len,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        self.pending.clear();
+        self.written = 0;
+        match self.writer.flush() {
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => Ok(false),
+            r => r.map(|_| true),
+        }
+    }
+
+    /// Returns the number of bytes waiting to be written.
+    pub fn pending_bytes(&self) -> usize {
+        self.pending.len() - self.written
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns the underlying writer, dropping any pending data.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: AsRawFd> AsRawFd for LineWriter<W> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.writer.as_raw_fd()
+    }
+}
+
+impl<W: AsFd> AsFd for LineWriter<W> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.writer.as_fd()
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineLogger`], which forwards lines to the [`log`]
+//! crate, so that the output of subprocesses shows up in the logging
+//! pipeline of the host application.
+//!
+//! Requires the `log` feature.
+
+use log::Level;
+
+use crate::lineread::LineRead;
+
+/// Forwards lines to the [`log`] crate as [`log::Record`]s.
+///
+/// The target of the records is the label given to
+/// [`LineLogger::new`], typically identifying the source of the lines
+/// (e.g. `"worker-3 stderr"`). The level is detected from the content
+/// of the line, or fixed with [`Self::with_level`].
+///
+/// Example:
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let child = std::process::Command::new("true")
+/// #     .stderr(std::process::Stdio::piped())
+/// #     .spawn()?;
+/// use lineriver::{LineLogger, LineRead, LineReader};
+///
+/// let mut stderr = LineReader::new(child.stderr.unwrap())?;
+/// let logger = LineLogger::new("child stderr");
+/// while !stderr.eof() {
+///     stderr.read_available()?;
+///     logger.forward(&mut stderr);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineLogger {
+    target: String,
+    level: Level,
+    detect_level: bool,
+}
+
+impl LineLogger {
+    /// Creates a new `LineLogger` that uses `target` as the target of
+    /// the records.
+    ///
+    /// By default, the level is detected from the content of each
+    /// line, falling back to [`Level::Info`].
+    pub fn new(target: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            level: Level::Info,
+            detect_level: true,
+        }
+    }
+
+    /// Sets the level used for lines that don't have a level in their
+    /// content, or for all lines if detection is disabled.
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Enables or disables the detection of the level from the
+    /// content of the lines.
+    pub fn with_level_detection(mut self, detect_level: bool) -> Self {
+        self.detect_level = detect_level;
+        self
+    }
+
+    /// Returns the level that would be used for `line`.
+    ///
+    /// Detection looks for the first word in the beginning of the line
+    /// that names a level, like `ERROR`, `warn` or `[DEBUG]`.
+    pub fn level_for(&self, line: &str) -> Level {
+        if !self.detect_level {
+            return self.level;
+        }
+        let head = line.get(..64).unwrap_or(line);
+        head.split(|c: char| !c.is_ascii_alphabetic())
+            .find_map(|word| match word.to_ascii_uppercase().as_str() {
+                "FATAL" | "CRIT" | "CRITICAL" | "ERR" | "ERROR" => Some(Level::Error),
+                "WARN" | "WARNING" => Some(Level::Warn),
+                "INFO" | "NOTICE" => Some(Level::Info),
+                "DEBUG" => Some(Level::Debug),
+                "TRACE" => Some(Level::Trace),
+                _ => None,
+            })
+            .unwrap_or(self.level)
+    }
+
+    /// Logs a single line, without its trailing line terminator.
+    pub fn log_line(&self, line: &str) {
+        let level = self.level_for(line);
+        if level > log::max_level() {
+            return;
+        }
+        let line = line.trim_end_matches(['\n', '\r']);
+        log::logger().log(
+            &log::Record::builder()
+                .args(format_args!("{}", line))
+                .level(level)
+                .target(&self.target)
+                .build(),
+        );
+    }
+
+    /// Takes the lines available in `linereader` and logs them,
+    /// returning how many lines were logged.
+    pub fn forward<L: LineRead + ?Sized>(&self, linereader: &mut L) -> usize {
+        let lines = linereader.lines_get();
+        for line in &lines {
+            self.log_line(line);
+        }
+        lines.len()
+    }
+}
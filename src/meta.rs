@@ -0,0 +1,75 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineMeta`], the metadata that
+//! [`LineReader`](crate::LineReader) keeps about each line.
+
+use std::time::{Instant, SystemTime};
+
+/// Metadata about a line, returned along with it by
+/// [`LineReader::lines_get_with_meta`](crate::LineReader::lines_get_with_meta).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LineMeta {
+    /// CRC-32 (IEEE) of the line, including its terminator; only
+    /// computed if enabled with
+    /// [`LineReader::with_checksums`](crate::LineReader::with_checksums).
+    pub checksum: Option<u32>,
+    /// Time of the event logged in the line, if enabled with
+    /// [`LineReader::with_timestamps`](crate::LineReader::with_timestamps).
+    ///
+    /// This is the timestamp extracted from the line or, if the line
+    /// doesn't have one, the time the line arrived.
+    pub timestamp: Option<SystemTime>,
+    /// `true` if [`Self::timestamp`] was extracted from the line,
+    /// `false` if it's the arrival time.
+    pub timestamp_from_line: bool,
+    /// Delimiter that terminated the line, kept at its end; `None` for
+    /// a line emitted without one, at EOF for instance. See
+    /// [`LineReader::with_delimiters`](crate::LineReader::with_delimiters).
+    /// For a multi-byte
+    /// [terminator](crate::LineReader::with_terminator), this is its
+    /// last byte.
+    pub delimiter: Option<u8>,
+    /// Offset in the source right after the end of the line, counting
+    /// from where the reader started; this is the offset to
+    /// [`commit`](crate::LineReader::commit) once the line was
+    /// processed.
+    pub offset: u64,
+    /// Generation of the underlying reader the line came from, which
+    /// [`LineReader::replace_reader`](crate::LineReader::replace_reader)
+    /// increments.
+    pub generation: u64,
+    /// When the line was completed, only kept if statistics are
+    /// enabled.
+    pub(crate) completed: Option<Instant>,
+}
+
+/// Lookup table for the reflected CRC-32 (IEEE) polynomial.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0_u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes the CRC-32 (IEEE) of `data`.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0_u32, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
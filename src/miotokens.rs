@@ -0,0 +1,167 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`MioLineReaders`], which maps [mio] tokens to
+//! line readers, enabled by the `mio` feature.
+//!
+//! [mio]: https://docs.rs/mio
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::io::{self, Read};
+use std::os::fd::AsRawFd;
+
+use mio::event::Event;
+use mio::unix::SourceFd;
+use mio::{Interest, Registry, Token};
+
+use crate::lineread::LineRead;
+use crate::LineReader;
+
+/// Registry of [`LineReader`]s indexed by [`mio::Token`].
+///
+/// It registers the readers in a mio [`Registry`] when they are added,
+/// deregisters them when they are removed, and reads from them when
+/// [`Self::handle_event`] gets their events - removing them once they
+/// reach EOF. This is the bookkeeping every mio integration ends up
+/// writing by hand.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::Write;
+/// use std::os::unix::net::UnixStream;
+/// use mio::{Events, Poll, Token};
+/// use lineriver::{LineReader, MioLineReaders};
+///
+/// let mut poll = Poll::new()?;
+/// let mut readers = MioLineReaders::new();
+/// let (mut writer, stream) = UnixStream::pair()?;
+/// readers.insert(poll.registry(), Token(7), LineReader::new(stream)?)?;
+/// writer.write_all(b"hello\n")?;
+/// drop(writer);
+/// let mut events = Events::with_capacity(16);
+/// let mut lines = vec![];
+/// while !readers.is_empty() {
+///     poll.poll(&mut events, None)?;
+///     for event in events.iter() {
+///         lines.extend(readers.handle_event(poll.registry(), event)?);
+///     }
+/// }
+/// assert_eq!(lines, vec!["hello\n"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MioLineReaders<R> {
+    readers: BTreeMap<Token, LineReader<R>>,
+}
+
+impl<R> Default for MioLineReaders<R> {
+    fn default() -> Self {
+        Self {
+            readers: BTreeMap::new(),
+        }
+    }
+}
+
+impl<R: Read + AsRawFd + Debug> MioLineReaders<R> {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `reader` for readability with `token`, and stores it.
+    ///
+    /// Fails with [`io::ErrorKind::AlreadyExists`] if the token is
+    /// already in use.
+    pub fn insert(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        reader: LineReader<R>,
+    ) -> Result<(), io::Error> {
+        if self.readers.contains_key(&token) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "token already in use",
+            ));
+        }
+        registry.register(
+            &mut SourceFd(&reader.as_raw_fd()),
+            token,
+            Interest::READABLE,
+        )?;
+        self.readers.insert(token, reader);
+        Ok(())
+    }
+
+    /// Deregisters and returns the reader with `token`, if any.
+    pub fn remove(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+    ) -> Result<Option<LineReader<R>>, io::Error> {
+        let Some(reader) = self.readers.remove(&token) else {
+            return Ok(None);
+        };
+        registry.deregister(&mut SourceFd(&reader.as_raw_fd()))?;
+        Ok(Some(reader))
+    }
+
+    /// Reads from the reader of the event's token and returns the
+    /// lines available.
+    ///
+    /// As mio events are edge-triggered, the reader is read until no
+    /// more data is available. A reader that reaches EOF is removed,
+    /// after its last lines are returned; a reader that fails is
+    /// removed and the error returned. Events of unknown tokens are
+    /// ignored.
+    pub fn handle_event(
+        &mut self,
+        registry: &Registry,
+        event: &Event,
+    ) -> Result<Vec<String>, io::Error> {
+        let token = event.token();
+        let Some(reader) = self.readers.get_mut(&token) else {
+            return Ok(vec![]);
+        };
+        loop {
+            let before = reader.bytes_read();
+            if let Err(err) = reader.read_once() {
+                self.remove(registry, token)?;
+                return Err(err);
+            }
+            if reader.eof() || reader.bytes_read() == before {
+                break;
+            }
+        }
+        let lines = reader.lines_get();
+        if reader.eof() {
+            self.remove(registry, token)?;
+        }
+        Ok(lines)
+    }
+
+    /// Gets a reference to the reader with `token`.
+    pub fn get(&self, token: Token) -> Option<&LineReader<R>> {
+        self.readers.get(&token)
+    }
+
+    /// Gets a mutable reference to the reader with `token`.
+    pub fn get_mut(&mut self, token: Token) -> Option<&mut LineReader<R>> {
+        self.readers.get_mut(&token)
+    }
+
+    /// Returns the number of readers.
+    pub fn len(&self) -> usize {
+        self.readers.len()
+    }
+
+    /// Returns `true` if there are no readers.
+    pub fn is_empty(&self) -> bool {
+        self.readers.is_empty()
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineMirror`], a [`LineRead`] wrapper that writes
+//! every line to a file before handing it over to the consumer.
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::path::Path;
+
+use crate::lineread::{FullLatch, LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+
+/// When [`LineMirror`] syncs the mirror file to the disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Never syncs explicitly, leaving it to the operating system.
+    Never,
+    /// Syncs after each group of lines pulled from the inner reader.
+    EveryBatch,
+    /// Syncs after each line.
+    EveryLine,
+}
+
+/// [`LineRead`] wrapper that appends every line to a mirror file
+/// before handing it over to the consumer.
+///
+/// Combined with [`SyncPolicy::EveryBatch`] or
+/// [`SyncPolicy::EveryLine`], this guarantees that a crash in the
+/// consumer never loses received data.
+///
+/// Lines are only returned after they are written and synced,
+/// according to the policy. If writing or syncing fails, the lines are
+/// kept and the error is returned by the next [`LineRead::read_once`];
+/// writing is resumed on the next use, from where it stopped.
+#[derive(Debug)]
+pub struct LineMirror<L> {
+    inner: L,
+    file: File,
+    policy: SyncPolicy,
+    unwritten: VecDeque<String>,
+    written: usize,
+    offset: usize,
+    lines: VecDeque<String>,
+    error: Option<io::Error>,
+    full: FullLatch,
+}
+
+impl<L: LineRead> LineMirror<L> {
+    /// Creates a new `LineMirror` over `inner` that writes to `file`.
+    pub fn new(inner: L, file: File, policy: SyncPolicy) -> Self {
+        Self {
+            inner,
+            file,
+            policy,
+            unwritten: Default::default(),
+            written: 0,
+            offset: 0,
+            lines: Default::default(),
+            error: None,
+            full: FullLatch::default(),
+        }
+    }
+
+    /// Creates a new `LineMirror` over `inner` that appends to the
+    /// file at `path`, creating it if necessary.
+    pub fn open<P: AsRef<Path>>(inner: L, path: P, policy: SyncPolicy) -> Result<Self, io::Error> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(inner, file, policy))
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &L {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut L {
+        &mut self.inner
+    }
+
+    /// Returns the underlying reader and the mirror file, dropping the
+    /// lines that were not retrieved yet.
+    pub fn into_inner(self) -> (L, File) {
+        (self.inner, self.file)
+    }
+
+    fn write(&mut self) -> Result<(), io::Error> {
+        while let Some(line) = self.unwritten.get(self.written) {
+            // Partially written lines are resumed at offset:
+            while self.offset < line.len() {
+                match self.file.write(&line.as_bytes()[self.offset..]) {
+                    Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                    Ok(len) => self.offset += len,
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            self.offset = 0;
+            self.written += 1;
+            if self.policy == SyncPolicy::EveryLine {
+                self.file.sync_data()?;
+                self.release();
+            }
+        }
+        if self.policy == SyncPolicy::EveryBatch {
+            self.file.sync_data()?;
+        }
+        self.release();
+        Ok(())
+    }
+
+    /// Hands the lines written (and synced, according to the policy)
+    /// over to the consumer.
+    fn release(&mut self) {
+        self.lines.extend(self.unwritten.drain(..self.written));
+        self.written = 0;
+    }
+
+    /// Moves lines from the inner reader to our buffer, writing them
+    /// to the mirror file on the way.
+    fn pull(&mut self) {
+        self.full
+            .update(self.inner.is_full(), !self.lines.is_empty());
+        self.unwritten.extend(self.inner.lines_get());
+        if self.unwritten.is_empty() {
+            return;
+        }
+        if let Err(err) = self.write() {
+            self.error = Some(err);
+        }
+    }
+}
+
+impl<L: LineRead> LineRead for LineMirror<L> {
+    fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        let result = self.inner.read_once();
+        self.pull();
+        result
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.pull();
+        mem::take(&mut self.lines).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        self.lines.pop_front()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.pull();
+        !self.lines.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.full
+            .is_full(self.inner.is_full(), !self.lines.is_empty())
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: AsRawFd> AsRawFd for LineMirror<L> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd> AsFd for LineMirror<L> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd> LineReadRawFd for LineMirror<L> {}
+
+impl<L: LineRead + AsFd> LineReadFd for LineMirror<L> {}
+
+impl<L: LineRead + AsFd + AsRawFd> LineReadRawAndFd for LineMirror<L> {}
@@ -0,0 +1,183 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`OsRecordReader`], which yields records as
+//! [`OsString`] instead of [`String`].
+
+use std::ffi::{OsStr, OsString};
+use std::fmt::Debug;
+use std::io::{self, Read};
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+use crate::blocking;
+
+const BUFFER_SIZE: usize = 8192;
+
+/// Non-blocking reader that splits the data into [`OsString`] records,
+/// without any UTF-8 validation.
+///
+/// Records are terminated by a newline by default, or by a NUL byte
+/// with [`Self::with_nul_terminator`], and the terminator is not
+/// included in them. This allows the lossless consumption of the
+/// output of `find -print0` or `git ls-files -z`, where file names
+/// don't have to be valid UTF-8.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), std::io::Error> {
+/// use std::path::PathBuf;
+/// use lineriver::OsRecordReader;
+///
+/// let mut reader = OsRecordReader::from_nonblocking(&b"a\0b\xff\0"[..]).with_nul_terminator();
+/// while reader.read_once()? {}
+/// let paths = reader.paths_get();
+/// assert_eq!(paths[0], PathBuf::from("a"));
+/// assert_eq!(paths[1].as_os_str().as_encoded_bytes(), b"b\xff");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct OsRecordReader<R> {
+    reader: R,
+    terminator: u8,
+    at_eof: bool,
+    buf: Vec<u8>,
+    used: usize,
+    records: Vec<OsString>,
+}
+
+impl<R: Read + AsRawFd + Debug> OsRecordReader<R> {
+    /// Creates a new OsRecordReader, setting the underlying descriptor
+    /// as non-blocking.
+    pub fn new(reader: R) -> Result<Self, io::Error> {
+        blocking::disable(reader.as_raw_fd())?;
+        Ok(Self::from_nonblocking(reader))
+    }
+}
+
+impl<R: Read> OsRecordReader<R> {
+    /// Creates a new OsRecordReader.
+    ///
+    /// Assumes the reader is already non-blocking, not configuring
+    /// anything in the underlying descriptor.
+    pub fn from_nonblocking(reader: R) -> Self {
+        Self {
+            reader,
+            terminator: b'\n',
+            at_eof: false,
+            buf: Vec::new(),
+            used: 0,
+            records: Vec::new(),
+        }
+    }
+
+    /// Terminates the records with NUL bytes instead of newlines.
+    pub fn with_nul_terminator(mut self) -> Self {
+        self.terminator = b'\0';
+        self
+    }
+
+    /// Returns `true` if the reader reached EOF.
+    ///
+    /// Records may still be available after EOF.
+    pub fn eof(&self) -> bool {
+        self.at_eof
+    }
+
+    /// Performs a single read and splits all complete records.
+    ///
+    /// Returns `false` if the reader is at EOF. At EOF, the remaining
+    /// bytes become the last record, even without a terminator.
+    pub fn read_once(&mut self) -> Result<bool, io::Error> {
+        if self.at_eof {
+            return Ok(false);
+        }
+        if self.buf.len() < self.used + BUFFER_SIZE {
+            self.buf.resize(self.used + BUFFER_SIZE, 0);
+        }
+        match self.reader.read(&mut self.buf[self.used..]) {
+            Ok(0) => {
+                self.at_eof = true;
+                if self.used > 0 {
+                    self.push_record(self.used, self.used);
+                }
+            }
+            Ok(len) => {
+                let mut pos = self.used;
+                self.used += len;
+                while let Some(i) = memchr::memchr(self.terminator, &self.buf[pos..self.used]) {
+                    self.push_record(pos + i, pos + i + 1);
+                    pos = 0;
+                }
+            }
+            Err(ref err)
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+        Ok(true)
+    }
+
+    /// Moves the first `len` bytes of the buffer to a new record,
+    /// dropping the bytes up to `end`.
+    fn push_record(&mut self, len: usize, end: usize) {
+        let rest = self.buf.split_off(end);
+        let mut record = mem::replace(&mut self.buf, rest);
+        record.truncate(len);
+        self.used -= end;
+        self.records.push(OsStr::from_bytes(&record).to_owned());
+    }
+
+    /// Reads all available data, or until a record is available.
+    pub fn read_available(&mut self) -> Result<(), io::Error> {
+        while self.read_once()? && !self.has_records() {}
+        Ok(())
+    }
+
+    /// Returns `true` if there are complete records available.
+    pub fn has_records(&self) -> bool {
+        !self.records.is_empty()
+    }
+
+    /// Takes the complete records.
+    pub fn records_get(&mut self) -> Vec<OsString> {
+        mem::take(&mut self.records)
+    }
+
+    /// Takes the complete records as paths.
+    pub fn paths_get(&mut self) -> Vec<PathBuf> {
+        self.records_get().into_iter().map(PathBuf::from).collect()
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the underlying reader, dropping any buffered data.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsRawFd> AsRawFd for OsRecordReader<R> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.reader.as_raw_fd()
+    }
+}
+
+impl<R: AsFd> AsFd for OsRecordReader<R> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.reader.as_fd()
+    }
+}
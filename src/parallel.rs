@@ -0,0 +1,77 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`par_for_each_line`], which processes lines in
+//! parallel with [rayon], enabled by the `rayon` feature.
+//!
+//! [rayon]: https://docs.rs/rayon
+
+use std::io;
+use std::mem;
+
+use rayon::prelude::*;
+
+use crate::lineread::LineRead;
+
+/// Reads `reader` until EOF, processing its lines with `f` in parallel
+/// batches of up to `batch_size` lines.
+///
+/// Each batch is processed by the rayon thread pool with `par_iter`,
+/// while the current thread keeps reading the next batch. Lines are
+/// not processed in order. This is meant for offline, high-volume
+/// processing of huge line streams, from files or pipes: while the
+/// reader has no data available, the reading side keeps polling it.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), std::io::Error> {
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use lineriver::{par_for_each_line, LineReader};
+///
+/// let input = "1\n2\n3\n4\n5\n";
+/// let mut reader = LineReader::from_nonblocking(input.as_bytes())?;
+/// let sum = AtomicUsize::new(0);
+/// par_for_each_line(&mut reader, 2, |line| {
+///     sum.fetch_add(line.trim_end().parse().unwrap(), Ordering::Relaxed);
+/// })?;
+/// assert_eq!(sum.into_inner(), 15);
+/// # Ok(())
+/// # }
+/// ```
+pub fn par_for_each_line<L, F>(reader: &mut L, batch_size: usize, f: F) -> Result<(), io::Error>
+where
+    L: LineRead + Send,
+    F: Fn(String) + Sync + Send,
+{
+    let batch_size = batch_size.max(1);
+    let mut pending = Vec::new();
+    let mut batch = read_batch(reader, &mut pending, batch_size)?;
+    while !batch.is_empty() {
+        let (_, next) = rayon::join(
+            || batch.into_par_iter().for_each(&f),
+            || read_batch(reader, &mut pending, batch_size),
+        );
+        batch = next?;
+    }
+    Ok(())
+}
+
+/// Reads until `batch_size` lines are available, or EOF, returning up
+/// to `batch_size` lines and keeping the rest in `pending`.
+fn read_batch<L: LineRead>(
+    reader: &mut L,
+    pending: &mut Vec<String>,
+    batch_size: usize,
+) -> Result<Vec<String>, io::Error> {
+    while pending.len() < batch_size {
+        if !reader.read_once()? {
+            break;
+        }
+        pending.extend(reader.lines_get());
+    }
+    pending.extend(reader.lines_get());
+    let rest = pending.split_off(batch_size.min(pending.len()));
+    Ok(mem::replace(pending, rest))
+}
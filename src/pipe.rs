@@ -0,0 +1,41 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`pipe`], which creates a pipe with a
+//! [`LineReader`] on the read end.
+
+use std::fs::File;
+use std::io;
+use std::os::fd::OwnedFd;
+
+use crate::linereader::LineReader;
+
+/// Creates a pipe, returning the writing end as a [`File`] and a
+/// [`LineReader`] over the reading end.
+///
+/// Only the reading end is non-blocking, so the writer can be used
+/// as a regular blocking file. Both ends are close-on-exec. This makes
+/// in-process producer/consumer setups and tests a single call.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), std::io::Error> {
+/// use std::io::Write;
+/// use lineriver::LineRead;
+///
+/// let (mut writer, mut reader) = lineriver::pipe()?;
+/// writer.write_all(b"1\n2\n")?;
+/// drop(writer);
+/// reader.read_available()?;
+/// assert_eq!(reader.lines_get(), vec!["1\n", "2\n"]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn pipe() -> Result<(File, LineReader<File>), io::Error> {
+    let (reader, writer) = io::pipe()?;
+    let reader = File::from(OwnedFd::from(reader));
+    let writer = File::from(OwnedFd::from(writer));
+    Ok((writer, LineReader::new(reader)?))
+}
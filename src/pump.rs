@@ -0,0 +1,369 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LinePump`], which forwards data from a reader to
+//! a writer without blocking, counting the lines.
+
+use std::fmt::Debug;
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+
+use crate::blocking;
+
+const BUFFER_SIZE: usize = 65536;
+
+/// Forwards all data from a reader to a writer without blocking,
+/// counting the lines that go through.
+///
+/// The data is forwarded as is, without any per-line transformation;
+/// use a [`LineReader`](crate::LineReader) and a
+/// [`LineWriter`](crate::LineWriter) for that.
+///
+/// On Linux, the data is moved with `splice(2)` through an
+/// intermediate pipe, without copying it through userspace on its way
+/// to the writer. The lines are counted on a `tee(2)` of that pipe,
+/// which can be disabled with [`Self::with_line_count`]. If the
+/// descriptors don't support `splice`, or on other systems, the data
+/// is copied through a buffer.
+///
+/// With the `metrics` feature, the pump reports the
+/// `lineriver_pump_bytes` and `lineriver_pump_lines` counters.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::{Read, Write};
+/// use std::os::unix::net::UnixStream;
+/// use lineriver::LinePump;
+///
+/// let (mut input, src) = UnixStream::pair()?;
+/// let (dst, mut output) = UnixStream::pair()?;
+/// let mut pump = LinePump::new(src, dst)?;
+/// input.write_all(b"1\n2\n3")?;
+/// drop(input);
+/// while pump.pump_once()? {}
+/// assert_eq!(pump.lines(), 3);
+/// drop(pump);
+/// let mut received = String::new();
+/// output.read_to_string(&mut received)?;
+/// assert_eq!(received, "1\n2\n3");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LinePump<R, W> {
+    src: R,
+    dst: W,
+    #[cfg(target_os = "linux")]
+    splice: Option<splice::SplicePipe>,
+    count_lines: bool,
+    buf: Vec<u8>,
+    written: usize,
+    bytes: u64,
+    lines: u64,
+    last_byte: u8,
+    at_eof: bool,
+}
+
+impl<R: Read + AsRawFd + Debug, W: Write + AsRawFd + Debug> LinePump<R, W> {
+    /// Creates a new LinePump, setting both descriptors as
+    /// non-blocking.
+    pub fn new(src: R, dst: W) -> Result<Self, io::Error> {
+        blocking::disable(src.as_raw_fd())?;
+        blocking::disable(dst.as_raw_fd())?;
+        Ok(Self {
+            #[cfg(target_os = "linux")]
+            splice: splice::SplicePipe::new().ok(),
+            src,
+            dst,
+            count_lines: true,
+            buf: Vec::new(),
+            written: 0,
+            bytes: 0,
+            lines: 0,
+            last_byte: b'\n',
+            at_eof: false,
+        })
+    }
+
+    /// Enables or disables counting the lines, which is enabled by
+    /// default.
+    ///
+    /// Without line counting, the data never goes through userspace
+    /// when `splice(2)` is used.
+    pub fn with_line_count(mut self, count_lines: bool) -> Self {
+        self.count_lines = count_lines;
+        self
+    }
+
+    /// Returns `true` if the data is being moved with `splice(2)`.
+    pub fn is_zero_copy(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        return self.splice.is_some();
+        #[cfg(not(target_os = "linux"))]
+        return false;
+    }
+
+    /// Returns the number of bytes forwarded so far.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Returns the number of lines forwarded so far, counting the last
+    /// line at EOF even if it has no newline; always 0 if line counting
+    /// is disabled.
+    pub fn lines(&self) -> u64 {
+        self.lines
+    }
+
+    /// Returns `true` if the reader reached EOF.
+    ///
+    /// Data may still be pending to be written.
+    pub fn eof(&self) -> bool {
+        self.at_eof
+    }
+
+    /// Moves the data available from the reader to the writer, as far
+    /// as neither of them would block.
+    ///
+    /// Returns `Ok(false)` once the reader reached EOF and all data was
+    /// written, `Ok(true)` before that.
+    pub fn pump_once(&mut self) -> Result<bool, io::Error> {
+        #[cfg(target_os = "linux")]
+        if self.splice.is_some() {
+            match self.pump_splice() {
+                Err(err) if err.raw_os_error() == Some(libc::EINVAL) && self.bytes == 0 => {
+                    // The descriptors don't support splice:
+                    self.splice = None;
+                }
+                result => return result,
+            }
+        }
+        self.pump_copy()
+    }
+
+    /// Counts the lines in `data`.
+    fn count(&mut self, data: &[u8]) {
+        let lines = memchr::memchr_iter(b'\n', data).count() as u64;
+        if let Some(last) = data.last() {
+            self.last_byte = *last;
+        }
+        self.lines += lines;
+        self.bytes += data.len() as u64;
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!("lineriver_pump_bytes").increment(data.len() as u64);
+            metrics::counter!("lineriver_pump_lines").increment(lines);
+        }
+    }
+
+    /// Reaches EOF, counting the last line if it has no newline.
+    fn set_eof(&mut self) {
+        self.at_eof = true;
+        if self.count_lines && self.last_byte != b'\n' {
+            self.lines += 1;
+            #[cfg(feature = "metrics")]
+            metrics::counter!("lineriver_pump_lines").increment(1);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn pump_splice(&mut self) -> Result<bool, io::Error> {
+        let (src, dst) = (self.src.as_raw_fd(), self.dst.as_raw_fd());
+        loop {
+            let Some(pipe) = &mut self.splice else {
+                return Ok(true);
+            };
+            if pipe.len > 0 {
+                match pipe.flush_to(dst) {
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(true),
+                    Err(err) => return Err(err),
+                    Ok(()) if pipe.len > 0 => continue,
+                    Ok(()) => {}
+                }
+            }
+            if self.at_eof {
+                return Ok(false);
+            }
+            let len = match pipe.fill_from(src) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(true),
+                Err(err) => return Err(err),
+                Ok(len) => len,
+            };
+            if len == 0 {
+                self.set_eof();
+                continue;
+            }
+            if self.count_lines {
+                let data = pipe.peek(len)?;
+                self.count(&data);
+            } else {
+                self.bytes += len as u64;
+                #[cfg(feature = "metrics")]
+                metrics::counter!("lineriver_pump_bytes").increment(len as u64);
+            }
+        }
+    }
+
+    fn pump_copy(&mut self) -> Result<bool, io::Error> {
+        loop {
+            while self.written < self.buf.len() {
+                match self.dst.write(&self.buf[self.written..]) {
+                    Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                    Ok(len) => self.written += len,
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(true),
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            if self.at_eof {
+                return Ok(false);
+            }
+            self.buf.resize(BUFFER_SIZE, 0);
+            self.written = 0;
+            match self.src.read(&mut self.buf) {
+                Ok(0) => {
+                    self.buf.clear();
+                    self.set_eof();
+                }
+                Ok(len) => {
+                    self.buf.truncate(len);
+                    let data = std::mem::take(&mut self.buf);
+                    if self.count_lines {
+                        self.count(&data);
+                    } else {
+                        self.bytes += len as u64;
+                    }
+                    self.buf = data;
+                }
+                Err(err) => {
+                    self.buf.clear();
+                    match err.kind() {
+                        io::ErrorKind::WouldBlock => return Ok(true),
+                        io::ErrorKind::Interrupted => {}
+                        _ => return Err(err),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gets references to the reader and the writer.
+    pub fn get_ref(&self) -> (&R, &W) {
+        (&self.src, &self.dst)
+    }
+
+    /// Returns the reader and the writer, dropping any data that was
+    /// not written yet.
+    pub fn into_inner(self) -> (R, W) {
+        (self.src, self.dst)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod splice {
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+    use std::ptr;
+
+    use super::BUFFER_SIZE;
+
+    /// The intermediate pipe used for `splice(2)`, and the one that
+    /// gets the `tee(2)` used to count the lines.
+    #[derive(Debug)]
+    pub(super) struct SplicePipe {
+        reader: OwnedFd,
+        writer: OwnedFd,
+        tee_reader: OwnedFd,
+        tee_writer: OwnedFd,
+        /// Bytes in the pipe, not written yet.
+        pub(super) len: usize,
+    }
+
+    fn pipe() -> Result<(OwnedFd, OwnedFd), io::Error> {
+        let mut fds = [0 as RawFd; 2];
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // Safety: pipe2 just created these descriptors.
+        unsafe { Ok((OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1]))) }
+    }
+
+    fn check(len: libc::ssize_t) -> Result<usize, io::Error> {
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(len as usize)
+    }
+
+    fn splice(from: RawFd, to: RawFd, len: usize) -> Result<usize, io::Error> {
+        check(unsafe {
+            libc::splice(
+                from,
+                ptr::null_mut(),
+                to,
+                ptr::null_mut(),
+                len,
+                libc::SPLICE_F_MOVE | libc::SPLICE_F_NONBLOCK,
+            )
+        })
+    }
+
+    impl SplicePipe {
+        pub(super) fn new() -> Result<Self, io::Error> {
+            let (reader, writer) = pipe()?;
+            let (tee_reader, tee_writer) = pipe()?;
+            Ok(Self {
+                reader,
+                writer,
+                tee_reader,
+                tee_writer,
+                len: 0,
+            })
+        }
+
+        /// Moves data from `src` to the pipe, which must be empty.
+        pub(super) fn fill_from(&mut self, src: RawFd) -> Result<usize, io::Error> {
+            let len = splice(src, self.writer.as_raw_fd(), BUFFER_SIZE)?;
+            self.len = len;
+            Ok(len)
+        }
+
+        /// Moves data from the pipe to `dst`.
+        pub(super) fn flush_to(&mut self, dst: RawFd) -> Result<(), io::Error> {
+            let len = splice(self.reader.as_raw_fd(), dst, self.len)?;
+            self.len -= len;
+            Ok(())
+        }
+
+        /// Returns a copy of the first `len` bytes in the pipe, without
+        /// consuming them.
+        ///
+        /// Both pipes have the same capacity, so the `tee(2)` copies
+        /// all the data that is in the pipe.
+        pub(super) fn peek(&mut self, len: usize) -> Result<Vec<u8>, io::Error> {
+            let teed = check(unsafe {
+                libc::tee(
+                    self.reader.as_raw_fd(),
+                    self.tee_writer.as_raw_fd(),
+                    len,
+                    libc::SPLICE_F_NONBLOCK,
+                )
+            })?;
+            let mut data = vec![0; teed];
+            let mut got = 0;
+            while got < teed {
+                got += check(unsafe {
+                    libc::read(
+                        self.tee_reader.as_raw_fd(),
+                        data[got..].as_mut_ptr() as *mut libc::c_void,
+                        teed - got,
+                    )
+                })?;
+            }
+            Ok(data)
+        }
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has the Python bindings of [`LineReader`], enabled by
+//! the `python` feature.
+//!
+//! The [`lineriver`] function is the initializer of the `lineriver`
+//! Python module, which has a `LineReader` class:
+//!
+//! ```python
+//! import subprocess
+//! import lineriver
+//!
+//! proc = subprocess.Popen(["ls"], stdout=subprocess.PIPE)
+//! reader = lineriver.LineReader(proc.stdout.fileno())
+//! while not reader.eof():
+//!     reader.read_available()
+//!     for line in reader.lines():
+//!         print(line, end="")
+//! ```
+
+use std::fs::File;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+use pyo3::prelude::*;
+
+use crate::lineread::LineRead;
+use crate::linereader::LineReader;
+
+/// Python `LineReader` class.
+#[pyclass(name = "LineReader", module = "lineriver")]
+#[derive(Debug)]
+pub struct PyLineReader {
+    reader: LineReader<File>,
+}
+
+#[pymethods]
+impl PyLineReader {
+    /// Creates a reader for a duplicate of the file descriptor `fd`,
+    /// which is set as non-blocking.
+    ///
+    /// The original descriptor stays owned by the caller, but shares
+    /// the non-blocking flag with the duplicate.
+    #[new]
+    fn py_new(fd: RawFd) -> PyResult<Self> {
+        let file = File::from(unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?);
+        Ok(Self {
+            reader: LineReader::new(file)?,
+        })
+    }
+
+    /// Reads all available data, or until a complete line is available.
+    fn read_available(&mut self) -> PyResult<()> {
+        Ok(self.reader.read_available()?)
+    }
+
+    /// Returns the complete lines read so far.
+    fn lines(&mut self) -> Vec<String> {
+        self.reader.lines_get()
+    }
+
+    /// Returns `True` if the file descriptor reached EOF.
+    fn eof(&self) -> bool {
+        self.reader.eof()
+    }
+
+    /// Returns the duplicated file descriptor, for use with `select`
+    /// and similar.
+    fn fileno(&self) -> RawFd {
+        self.reader.as_fd().as_raw_fd()
+    }
+}
+
+/// Initializer of the `lineriver` Python module.
+#[pymodule]
+pub fn lineriver(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyLineReader>()?;
+    Ok(())
+}
@@ -0,0 +1,256 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineRateLimiter`], a [`LineRead`] wrapper that
+//! limits the rate at which lines are delivered.
+
+use std::collections::VecDeque;
+use std::io;
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::time::Instant;
+
+use crate::lineread::{FullLatch, LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+
+/// What [`LineRateLimiter`] does with lines that exceed the rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Keeps the lines buffered until the rate allows them through.
+    Buffer,
+    /// Drops the lines, counting them in
+    /// [`LineRateLimiter::dropped`].
+    Drop,
+    /// Keeps the lines buffered, but makes the next
+    /// [`LineRead::read_once`] return an error.
+    Error,
+}
+
+/// Token bucket that refills at `rate` tokens per second, up to one
+/// second worth of tokens.
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64) -> Self {
+        assert!(
+            rate.is_finite() && rate > 0.0,
+            "the rate must be positive and finite"
+        );
+        Self {
+            rate,
+            tokens: rate,
+            last: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last = now;
+    }
+
+    /// Returns `true` if there are tokens for `cost`. A cost bigger
+    /// than the bucket is allowed when the bucket is full, leaving it
+    /// in debt.
+    fn allows(&self, cost: f64) -> bool {
+        self.tokens >= cost.min(self.rate)
+    }
+}
+
+/// [`LineRead`] wrapper that limits the rate of delivered lines, in
+/// lines per second and/or bytes per second, using token buckets.
+///
+/// This protects downstream systems from bursts. Lines that exceed
+/// the rate are handled according to the [`OverflowPolicy`]. Bursts
+/// of up to one second worth of lines/bytes are allowed.
+#[derive(Debug)]
+pub struct LineRateLimiter<L> {
+    inner: L,
+    policy: OverflowPolicy,
+    lines_bucket: Option<Bucket>,
+    bytes_bucket: Option<Bucket>,
+    queue: VecDeque<String>,
+    lines: VecDeque<String>,
+    dropped: u64,
+    overflowed: bool,
+    full: FullLatch,
+}
+
+impl<L: LineRead> LineRateLimiter<L> {
+    /// Creates a new `LineRateLimiter` over `inner`, with no limits
+    /// set.
+    pub fn new(inner: L, policy: OverflowPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            lines_bucket: None,
+            bytes_bucket: None,
+            queue: Default::default(),
+            lines: Default::default(),
+            dropped: 0,
+            overflowed: false,
+            full: FullLatch::default(),
+        }
+    }
+
+    /// Limits the delivery to `rate` lines per second.
+    ///
+    /// `rate` must be positive and finite; there is no limit by
+    /// default.
+    pub fn with_lines_per_sec(mut self, rate: f64) -> Self {
+        self.lines_bucket = Some(Bucket::new(rate));
+        self
+    }
+
+    /// Limits the delivery to `rate` bytes per second.
+    ///
+    /// `rate` must be positive and finite; there is no limit by
+    /// default.
+    pub fn with_bytes_per_sec(mut self, rate: f64) -> Self {
+        self.bytes_bucket = Some(Bucket::new(rate));
+        self
+    }
+
+    /// Returns the number of lines dropped by the
+    /// [`OverflowPolicy::Drop`] policy.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Returns the number of lines buffered waiting for the rate to
+    /// allow them through.
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &L {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut L {
+        &mut self.inner
+    }
+
+    /// Returns the underlying reader, dropping the lines that were
+    /// not retrieved yet.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+
+    fn allows(&self, line: &str) -> bool {
+        self.lines_bucket.as_ref().is_none_or(|b| b.allows(1.0))
+            && self
+                .bytes_bucket
+                .as_ref()
+                .is_none_or(|b| b.allows(line.len() as f64))
+    }
+
+    /// Moves the lines allowed by the rate from the inner reader to
+    /// our buffer.
+    fn pull(&mut self) {
+        self.full
+            .update(self.inner.is_full(), !self.lines.is_empty());
+        self.queue.extend(self.inner.lines_get());
+        let now = Instant::now();
+        for bucket in [&mut self.lines_bucket, &mut self.bytes_bucket]
+            .into_iter()
+            .flatten()
+        {
+            bucket.refill(now);
+        }
+        while let Some(line) = self.queue.front() {
+            if !self.allows(line) {
+                break;
+            }
+            if let Some(bucket) = &mut self.lines_bucket {
+                bucket.tokens -= 1.0;
+            }
+            if let Some(bucket) = &mut self.bytes_bucket {
+                bucket.tokens -= line.len() as f64;
+            }
+            self.lines.extend(self.queue.pop_front());
+        }
+        if !self.queue.is_empty() {
+            match self.policy {
+                OverflowPolicy::Buffer => {}
+                OverflowPolicy::Drop => {
+                    self.dropped += self.queue.len() as u64;
+                    self.queue.clear();
+                }
+                OverflowPolicy::Error => {
+                    self.overflowed = true;
+                }
+            }
+        }
+    }
+}
+
+impl<L: LineRead> LineRead for LineRateLimiter<L> {
+    /// Returns `true` if the inner reader is at EOF and there are no
+    /// lines waiting for the rate.
+    fn eof(&self) -> bool {
+        self.inner.eof() && self.queue.is_empty()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        if mem::take(&mut self.overflowed) {
+            return Err(io::Error::other("line rate limit exceeded"));
+        }
+        let result = self.inner.read_once();
+        self.pull();
+        Ok(result? || !self.queue.is_empty())
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.pull();
+        mem::take(&mut self.lines).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        self.lines.pop_front()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.pull();
+        !self.lines.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.full
+            .is_full(self.inner.is_full(), !self.lines.is_empty())
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: AsRawFd> AsRawFd for LineRateLimiter<L> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd> AsFd for LineRateLimiter<L> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd> LineReadRawFd for LineRateLimiter<L> {}
+
+impl<L: LineRead + AsFd> LineReadFd for LineRateLimiter<L> {}
+
+impl<L: LineRead + AsFd + AsRawFd> LineReadRawAndFd for LineRateLimiter<L> {}
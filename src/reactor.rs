@@ -0,0 +1,489 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`Reactor`], a safe wrapper around a
+//! [`polling::Poller`] that owns line readers, enabled by the
+//! `polling` feature.
+//!
+//! With the `signal` feature, the reactor can also deliver signals as
+//! events, using [signal-hook](https://docs.rs/signal-hook)'s
+//! self-pipe.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io;
+use std::os::fd::{AsFd, BorrowedFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+#[cfg(feature = "signal")]
+use std::{io::Read, os::raw::c_int, os::unix::net::UnixStream};
+
+use polling::{Event, Events, Poller};
+
+use crate::lineread::LineRead;
+
+/// Event returned by [`Reactor::wait`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReactorEvent {
+    /// The reader with the given key has lines available.
+    Lines(usize),
+    /// The reader with the given key reached EOF; it is no longer
+    /// polled, but stays in the reactor until removed.
+    Eof(usize),
+    /// Reading from the reader with the given key failed; it is no
+    /// longer polled, but stays in the reactor until removed.
+    Error(usize, io::Error),
+    /// The given signal, registered with [`Reactor::add_signal`], was
+    /// received at least once since the last event.
+    #[cfg(feature = "signal")]
+    Signal(c_int),
+    /// The reactor was woken up by a [`ReactorWaker`].
+    Wakeup,
+}
+
+/// Handle that interrupts a [`Reactor::wait`] from another thread.
+///
+/// Created by [`Reactor::waker`]. A wakeup makes the current or the
+/// next `wait` return a [`ReactorEvent::Wakeup`], so that the thread
+/// that runs the poll loop can add or remove readers, or shut down, at
+/// the request of other threads. Waking a reactor that was dropped
+/// does nothing.
+#[derive(Debug, Clone)]
+pub struct ReactorWaker {
+    poller: Weak<Poller>,
+    woken: Arc<AtomicBool>,
+}
+
+impl ReactorWaker {
+    /// Wakes the reactor up.
+    pub fn wake(&self) -> Result<(), io::Error> {
+        self.woken.store(true, Ordering::SeqCst);
+        match self.poller.upgrade() {
+            Some(poller) => poller.notify(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "signal")]
+#[derive(Debug)]
+struct SignalPipe {
+    signal: c_int,
+    reader: UnixStream,
+    id: signal_hook::SigId,
+}
+
+/// Poll loop helper that owns [`LineRead`] objects and waits for any
+/// of them to have lines available.
+///
+/// The reactor registers the readers in a [`Poller`] when they are
+/// added, and deletes them when they are removed, before giving them
+/// back. As it owns the readers, the descriptors can't be closed while
+/// registered, which makes the `unsafe` blocks that [`Poller::add`]
+/// requires unnecessary in user code. Interest is re-armed
+/// automatically after each event.
+///
+/// With the `metrics` feature, the reactor reports the following
+/// metrics through the [metrics] facade on each wait, in addition to
+/// the ones of the readers:
+/// - `lineriver_reactor_events` (counter): events returned;
+/// - `lineriver_reactor_readers` (gauge): readers owned;
+/// - `lineriver_reactor_throttled` (gauge): readers not polled because
+///   their line queue is full;
+/// - `lineriver_reactor_suspended` (gauge): readers suspended with
+///   [`Reactor::suspend`].
+///
+/// [metrics]: https://docs.rs/metrics
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::Write;
+/// use std::os::unix::net::UnixStream;
+/// use lineriver::{LineRead, LineReader, Reactor, ReactorEvent};
+///
+/// let (mut writer, stream) = UnixStream::pair()?;
+/// let mut reactor = Reactor::new()?;
+/// let key = reactor.add(LineReader::new(stream)?)?;
+/// writer.write_all(b"hello\n")?;
+/// drop(writer);
+/// let mut lines = vec![];
+/// while !reactor.is_empty() {
+///     for event in reactor.wait(None)? {
+///         match event {
+///             ReactorEvent::Lines(key) => lines.extend(reactor[key].lines_get()),
+///             ReactorEvent::Eof(key) => {
+///                 lines.extend(reactor[key].lines_get());
+///                 reactor.remove(key)?;
+///             }
+///             ReactorEvent::Error(key, err) => return Err(err.into()),
+///             _ => {}
+///         }
+///     }
+/// }
+/// assert_eq!(lines, vec!["hello\n"]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Reactor<L> {
+    // Declared before the readers, so that it is dropped first; wakers
+    // only have weak references to it.
+    poller: Arc<Poller>,
+    woken: Arc<AtomicBool>,
+    events: Events,
+    readers: BTreeMap<usize, L>,
+    suspended: BTreeSet<usize>,
+    throttled: BTreeSet<usize>,
+    next_key: usize,
+    #[cfg(feature = "signal")]
+    signals: BTreeMap<usize, SignalPipe>,
+}
+
+impl<L: LineRead> fmt::Debug for Reactor<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let readers = self
+            .readers
+            .iter()
+            .map(|(key, reader)| (key, reader.label()))
+            .collect::<BTreeMap<_, _>>();
+        f.debug_struct("Reactor")
+            .field("readers", &readers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<L: LineRead + AsFd> Reactor<L> {
+    /// Creates an empty reactor.
+    pub fn new() -> Result<Self, io::Error> {
+        Ok(Self {
+            poller: Arc::new(Poller::new()?),
+            woken: Arc::new(AtomicBool::new(false)),
+            events: Events::new(),
+            readers: BTreeMap::new(),
+            suspended: BTreeSet::new(),
+            throttled: BTreeSet::new(),
+            next_key: 0,
+            #[cfg(feature = "signal")]
+            signals: BTreeMap::new(),
+        })
+    }
+
+    /// Returns a handle that other threads can use to interrupt
+    /// [`Self::wait`].
+    pub fn waker(&self) -> ReactorWaker {
+        ReactorWaker {
+            poller: Arc::downgrade(&self.poller),
+            woken: Arc::clone(&self.woken),
+        }
+    }
+
+    /// Registers a handler for `signal` that makes [`Self::wait`]
+    /// return a [`ReactorEvent::Signal`], so that poll loops can
+    /// handle `SIGTERM` and `SIGINT` by draining the readers and
+    /// exiting cleanly.
+    ///
+    /// The handler is unregistered when the reactor is dropped.
+    #[cfg(feature = "signal")]
+    pub fn add_signal(&mut self, signal: c_int) -> Result<(), io::Error> {
+        let (reader, writer) = UnixStream::pair()?;
+        reader.set_nonblocking(true)?;
+        let key = self.next_key;
+        let id = signal_hook::low_level::pipe::register(signal, writer)?;
+        // Safety: the reactor owns the pipe, and the poller is dropped
+        // before it.
+        if let Err(err) = unsafe { self.poller.add(&reader, Event::readable(key)) } {
+            signal_hook::low_level::unregister(id);
+            return Err(err);
+        }
+        self.signals.insert(key, SignalPipe { signal, reader, id });
+        self.next_key += 1;
+        Ok(())
+    }
+
+    /// Adds a reader to the reactor, returning its key.
+    pub fn add(&mut self, reader: L) -> Result<usize, io::Error> {
+        let key = self.next_key;
+        // Safety: the reactor owns the reader, and deletes it from the
+        // poller before giving it back; the poller is dropped before
+        // the readers.
+        unsafe {
+            self.poller.add(&reader.as_fd(), Event::readable(key))?;
+        }
+        self.readers.insert(key, reader);
+        self.next_key += 1;
+        Ok(key)
+    }
+
+    /// Removes a reader from the reactor, returning it.
+    pub fn remove(&mut self, key: usize) -> Result<Option<L>, io::Error> {
+        let Some(reader) = self.readers.remove(&key) else {
+            return Ok(None);
+        };
+        self.suspended.remove(&key);
+        self.throttled.remove(&key);
+        self.poller.delete(reader.as_fd())?;
+        Ok(Some(reader))
+    }
+
+    /// Stops polling the reader with the given key until
+    /// [`Self::resume`] is called, leaving unread data in the kernel
+    /// buffers.
+    pub fn suspend(&mut self, key: usize) -> Result<(), io::Error> {
+        let Some(reader) = self.readers.get(&key) else {
+            return Ok(());
+        };
+        self.poller.modify(reader.as_fd(), Event::none(key))?;
+        self.suspended.insert(key);
+        Ok(())
+    }
+
+    /// Resumes polling the reader with the given key.
+    pub fn resume(&mut self, key: usize) -> Result<(), io::Error> {
+        if !self.suspended.remove(&key) {
+            return Ok(());
+        }
+        let reader = &self.readers[&key];
+        if !reader.eof() && !self.throttled.contains(&key) {
+            self.poller.modify(reader.as_fd(), Event::readable(key))?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the reader with the given key is suspended.
+    pub fn is_suspended(&self, key: usize) -> bool {
+        self.suspended.contains(&key)
+    }
+
+    /// Returns `true` if the reader with the given key is not polled
+    /// because its line queue is full; see [`LineRead::is_full`].
+    ///
+    /// Polling resumes automatically in the first [`Self::wait`] after
+    /// lines are retrieved from the reader.
+    pub fn is_throttled(&self, key: usize) -> bool {
+        self.throttled.contains(&key)
+    }
+
+    /// Returns the label of the reader with the given key, if it has
+    /// one.
+    pub fn label(&self, key: usize) -> Option<&str> {
+        self.readers.get(&key).and_then(L::label)
+    }
+
+    /// Returns a reference to the reader with the given key.
+    pub fn get(&self, key: usize) -> Option<&L> {
+        self.readers.get(&key)
+    }
+
+    /// Returns a mutable reference to the reader with the given key.
+    pub fn get_mut(&mut self, key: usize) -> Option<&mut L> {
+        self.readers.get_mut(&key)
+    }
+
+    /// Returns an iterator over the keys and readers.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &L)> {
+        self.readers.iter().map(|(k, r)| (*k, r))
+    }
+
+    /// Returns a mutable iterator over the keys and readers.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut L)> {
+        self.readers.iter_mut().map(|(k, r)| (*k, r))
+    }
+
+    /// Returns the number of readers in the reactor.
+    pub fn len(&self) -> usize {
+        self.readers.len()
+    }
+
+    /// Returns `true` if the reactor has no readers.
+    pub fn is_empty(&self) -> bool {
+        self.readers.is_empty()
+    }
+
+    /// Waits for readers to become readable, reads from them, and
+    /// returns the resulting events.
+    ///
+    /// Each ready reader is read once; if more data is available, it
+    /// becomes ready again in the next call.
+    ///
+    /// Waits at most `timeout`, or forever if `None`. Returns an empty
+    /// vector if the timeout expires without events.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> Result<Vec<ReactorEvent>, io::Error> {
+        self.wait_with(timeout, &mut vec![], &mut vec![])
+    }
+
+    /// Keeps waiting and reading until all readers reach EOF or until
+    /// `deadline`, returning the lines collected from each reader.
+    ///
+    /// Readers that fail or are suspended are not waited for. The
+    /// readers stay in the reactor. Signal events are discarded.
+    pub fn drain(&mut self, deadline: Instant) -> Result<BTreeMap<usize, Vec<String>>, io::Error> {
+        let mut result = BTreeMap::<usize, Vec<String>>::new();
+        let mut failed = BTreeSet::new();
+        for (key, reader) in self.readers.iter_mut() {
+            let lines = reader.lines_get();
+            if !lines.is_empty() {
+                result.entry(*key).or_default().extend(lines);
+            }
+        }
+        loop {
+            let pending = self.readers.iter().any(|(key, reader)| {
+                !reader.eof() && !self.suspended.contains(key) && !failed.contains(key)
+            });
+            let now = Instant::now();
+            if !pending || now >= deadline {
+                return Ok(result);
+            }
+            for event in self.wait(Some(deadline - now))? {
+                match event {
+                    ReactorEvent::Lines(key) => {
+                        let lines = self.readers.get_mut(&key).map(L::lines_get);
+                        result
+                            .entry(key)
+                            .or_default()
+                            .extend(lines.unwrap_or_default());
+                    }
+                    ReactorEvent::Error(key, _) => {
+                        failed.insert(key);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Registers a source that is not owned by the reactor, returning
+    /// its key; its events are returned by [`Self::wait_with`].
+    ///
+    /// # Safety
+    ///
+    /// The source must outlive the reactor.
+    pub(crate) unsafe fn add_foreign(
+        &mut self,
+        source: BorrowedFd<'_>,
+    ) -> Result<usize, io::Error> {
+        let key = self.next_key;
+        self.poller.add(&source, Event::readable(key))?;
+        self.next_key += 1;
+        Ok(key)
+    }
+
+    /// Re-arms the interest in a source registered with
+    /// [`Self::add_foreign`].
+    pub(crate) fn rearm_foreign(
+        &self,
+        source: BorrowedFd<'_>,
+        key: usize,
+    ) -> Result<(), io::Error> {
+        self.poller.modify(source, Event::readable(key))
+    }
+
+    /// Re-arms the interest of the throttled readers that are no
+    /// longer full.
+    fn rearm_throttled(&mut self) -> Result<(), io::Error> {
+        let readers = &self.readers;
+        let drained = self
+            .throttled
+            .iter()
+            .copied()
+            .filter(|key| readers.get(key).is_none_or(|reader| !reader.is_full()))
+            .collect::<Vec<_>>();
+        for key in drained {
+            self.throttled.remove(&key);
+            let Some(reader) = self.readers.get(&key) else {
+                continue;
+            };
+            if !reader.eof() && !self.suspended.contains(&key) {
+                self.poller.modify(reader.as_fd(), Event::readable(key))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::wait`], also appending the keys of the ready
+    /// foreign sources to `foreign`, and the keys of the readers that
+    /// were read to `read`.
+    pub(crate) fn wait_with(
+        &mut self,
+        timeout: Option<Duration>,
+        foreign: &mut Vec<usize>,
+        read: &mut Vec<usize>,
+    ) -> Result<Vec<ReactorEvent>, io::Error> {
+        self.rearm_throttled()?;
+        self.events.clear();
+        self.poller.wait(&mut self.events, timeout)?;
+        let mut result = vec![];
+        if self.woken.swap(false, Ordering::SeqCst) {
+            result.push(ReactorEvent::Wakeup);
+        }
+        for ev in self.events.iter() {
+            #[cfg(feature = "signal")]
+            if let Some(pipe) = self.signals.get_mut(&ev.key) {
+                let mut buf = [0_u8; 64];
+                while matches!(pipe.reader.read(&mut buf), Ok(n) if n > 0) {}
+                result.push(ReactorEvent::Signal(pipe.signal));
+                self.poller.modify(&pipe.reader, Event::readable(ev.key))?;
+                continue;
+            }
+            let Some(reader) = self.readers.get_mut(&ev.key) else {
+                foreign.push(ev.key);
+                continue;
+            };
+            read.push(ev.key);
+            // A single read per event: read_available would spin on a
+            // partial line, and the interest is level-triggered anyway.
+            if let Err(err) = reader.read_once() {
+                result.push(ReactorEvent::Error(ev.key, err));
+                continue;
+            }
+            if reader.has_lines() {
+                result.push(ReactorEvent::Lines(ev.key));
+            }
+            if reader.eof() {
+                result.push(ReactorEvent::Eof(ev.key));
+            } else if reader.is_full() {
+                // Re-armed by rearm_throttled once lines are retrieved:
+                self.throttled.insert(ev.key);
+            } else if !self.suspended.contains(&ev.key) {
+                self.poller
+                    .modify(reader.as_fd(), Event::readable(ev.key))?;
+            }
+        }
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!("lineriver_reactor_events").increment(result.len() as u64);
+            metrics::gauge!("lineriver_reactor_readers").set(self.readers.len() as f64);
+            metrics::gauge!("lineriver_reactor_throttled").set(self.throttled.len() as f64);
+            metrics::gauge!("lineriver_reactor_suspended").set(self.suspended.len() as f64);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(feature = "signal")]
+impl<L> Drop for Reactor<L> {
+    fn drop(&mut self) {
+        for pipe in self.signals.values() {
+            signal_hook::low_level::unregister(pipe.id);
+        }
+    }
+}
+
+impl<L> std::ops::Index<usize> for Reactor<L> {
+    type Output = L;
+
+    fn index(&self, key: usize) -> &L {
+        &self.readers[&key]
+    }
+}
+
+impl<L> std::ops::IndexMut<usize> for Reactor<L> {
+    fn index_mut(&mut self, key: usize) -> &mut L {
+        self.readers
+            .get_mut(&key)
+            .expect("key not found in reactor")
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`RecordAssembler`], a [`LineRead`] wrapper that
+//! groups multiline records, like stack traces, into single strings.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::time::{Duration, Instant};
+
+use crate::lineread::{FullLatch, LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+
+/// [`LineRead`] wrapper that groups a "start line" with the
+/// continuation lines that follow it into a single record.
+///
+/// A line for which the `is_start` predicate returns `true` begins a
+/// new record; all other lines are appended to the current one. This
+/// makes Java/Python tracebacks, for instance, come out as a single
+/// "line" when the predicate matches the timestamp prefix of the log
+/// lines.
+///
+/// The current record is also completed when:
+/// - it reaches the size cap set with [`Self::with_max_size`];
+/// - no line arrives for the duration set with [`Self::with_timeout`];
+/// - the inner reader reaches EOF.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lineriver::testing::ScriptedReader;
+/// use lineriver::{LineRead, LineReader, RecordAssembler};
+///
+/// let script = ScriptedReader::new().data(b"E: oops\n  at f()\n  at g()\nI: ok\n");
+/// let linereader = LineReader::from_nonblocking(script)?;
+/// let mut records = RecordAssembler::new(linereader, |l| !l.starts_with(' '));
+/// while !records.eof() {
+///     records.read_once()?;
+/// }
+/// assert_eq!(records.lines_get(), vec!["E: oops\n  at f()\n  at g()\n", "I: ok\n"]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct RecordAssembler<L> {
+    inner: L,
+    is_start: Box<dyn FnMut(&str) -> bool + Send>,
+    max_size: usize,
+    timeout: Option<Duration>,
+    current: String,
+    last_line: Instant,
+    records: VecDeque<String>,
+    full: FullLatch,
+}
+
+impl<L: LineRead> RecordAssembler<L> {
+    /// Creates a new `RecordAssembler` over `inner`, where `is_start`
+    /// identifies the lines that start a new record.
+    pub fn new<F>(inner: L, is_start: F) -> Self
+    where
+        F: FnMut(&str) -> bool + Send + 'static,
+    {
+        Self {
+            inner,
+            is_start: Box::new(is_start),
+            max_size: usize::MAX,
+            timeout: None,
+            current: Default::default(),
+            last_line: Instant::now(),
+            records: Default::default(),
+            full: FullLatch::default(),
+        }
+    }
+
+    /// Creates a new `RecordAssembler` over `inner` for streams where
+    /// each record starts with the `prefix` delimiter, like `--- `
+    /// document markers.
+    ///
+    /// The delimiter is kept at the start of the record it opens, and
+    /// each record is completed when the next delimiter or EOF is
+    /// seen. Lines before the first delimiter form a record of their
+    /// own.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lineriver::testing::ScriptedReader;
+    /// use lineriver::{LineRead, LineReader, RecordAssembler};
+    ///
+    /// let script = ScriptedReader::new().data(b"--- a\nb\n--- c\n");
+    /// let linereader = LineReader::from_nonblocking(script)?;
+    /// let mut records = RecordAssembler::with_prefix(linereader, "--- ");
+    /// while !records.eof() {
+    ///     records.read_once()?;
+    /// }
+    /// assert_eq!(records.lines_get(), vec!["--- a\nb\n", "--- c\n"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_prefix(inner: L, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        Self::new(inner, move |line| line.starts_with(&prefix))
+    }
+
+    /// Sets the maximum size of a record, in bytes.
+    ///
+    /// A line that doesn't fit in the current record starts a new
+    /// one, even if it is a continuation line. A single line bigger
+    /// than the cap becomes a record by itself.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Completes the current record if no new line arrives for
+    /// `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &L {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut L {
+        &mut self.inner
+    }
+
+    /// Returns the underlying reader, dropping the records that were
+    /// not retrieved yet.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+
+    fn flush(&mut self) {
+        if !self.current.is_empty() {
+            self.records.push_back(mem::take(&mut self.current));
+        }
+    }
+
+    /// Moves lines from the inner reader to the current record,
+    /// completing records as needed.
+    fn pull(&mut self) {
+        self.full
+            .update(self.inner.is_full(), !self.records.is_empty());
+        let lines = self.inner.lines_get();
+        if !lines.is_empty() {
+            self.last_line = Instant::now();
+        }
+        for line in lines {
+            if (self.is_start)(&line) || self.current.len() + line.len() > self.max_size {
+                self.flush();
+            }
+            self.current.push_str(&line);
+        }
+        if self.inner.eof()
+            || self
+                .timeout
+                .is_some_and(|timeout| self.last_line.elapsed() >= timeout)
+        {
+            self.flush();
+        }
+    }
+}
+
+impl<L: LineRead> LineRead for RecordAssembler<L> {
+    fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        let result = self.inner.read_once();
+        self.pull();
+        result
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.pull();
+        mem::take(&mut self.records).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        self.records.pop_front()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.pull();
+        !self.records.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.full
+            .is_full(self.inner.is_full(), !self.records.is_empty())
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: fmt::Debug> fmt::Debug for RecordAssembler<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordAssembler")
+            .field("inner", &self.inner)
+            .field("max_size", &self.max_size)
+            .field("timeout", &self.timeout)
+            .field("current", &self.current)
+            .field("records", &self.records)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<L: AsRawFd> AsRawFd for RecordAssembler<L> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd> AsFd for RecordAssembler<L> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd> LineReadRawFd for RecordAssembler<L> {}
+
+impl<L: LineRead + AsFd> LineReadFd for RecordAssembler<L> {}
+
+impl<L: LineRead + AsFd + AsRawFd> LineReadRawAndFd for RecordAssembler<L> {}
@@ -0,0 +1,207 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`RecordingReader`], which records the raw chunks
+//! read from a source along with their timing, and [`ReplayReader`],
+//! which plays such a recording back.
+//!
+//! The recording format is a `LRREC1\n` header followed by one entry
+//! per read: the time since the recording started in microseconds (a
+//! little-endian `u64`), the length of the chunk (a little-endian
+//! `u32`) and the chunk bytes. An empty chunk marks EOF.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const MAGIC: &[u8] = b"LRREC1\n";
+
+/// [`Read`] adapter that records every chunk read from the inner
+/// reader, with its timing, to a writer.
+///
+/// Wrap the source in a `RecordingReader` before handing it to a
+/// [`LineReader`](crate::LineReader) to capture exactly what arrived,
+/// and use [`ReplayReader`] to reproduce it later in a test.
+pub struct RecordingReader<R, W = File> {
+    inner: R,
+    writer: W,
+    start: Instant,
+}
+
+impl<R: fmt::Debug, W> fmt::Debug for RecordingReader<R, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordingReader")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: Read> RecordingReader<R> {
+    /// Creates a RecordingReader that records to a new file at `path`.
+    pub fn create<P: AsRef<Path>>(inner: R, path: P) -> Result<Self, io::Error> {
+        Self::new(inner, File::create(path)?)
+    }
+}
+
+impl<R: Read, W: Write> RecordingReader<R, W> {
+    /// Creates a RecordingReader that records to `writer`.
+    pub fn new(inner: R, mut writer: W) -> Result<Self, io::Error> {
+        writer.write_all(MAGIC)?;
+        Ok(Self {
+            inner,
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    /// Returns a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a reference to the writer that receives the recording.
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns the inner reader and the writer.
+    pub fn into_inner(self) -> (R, W) {
+        (self.inner, self.writer)
+    }
+}
+
+impl<R: Read, W: Write> Read for RecordingReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        let micros = u64::try_from(self.start.elapsed().as_micros()).unwrap_or(u64::MAX);
+        let mut entry = Vec::with_capacity(12 + len);
+        entry.extend_from_slice(&micros.to_le_bytes());
+        entry.extend_from_slice(&(len as u32).to_le_bytes());
+        entry.extend_from_slice(&buf[..len]);
+        self.writer.write_all(&entry)?;
+        self.writer.flush()?;
+        Ok(len)
+    }
+}
+
+impl<R: AsRawFd, W> AsRawFd for RecordingReader<R, W> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<R: AsFd, W> AsFd for RecordingReader<R, W> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+/// Non-blocking [`Read`] source that plays back a recording made by
+/// [`RecordingReader`], one chunk per read.
+///
+/// Use it with [`LineReader::from_nonblocking`](crate::LineReader::from_nonblocking)
+/// to get the same framing that the original source produced.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::io::Read;
+/// use lineriver::{LineRead, LineReader, RecordingReader, ReplayReader};
+///
+/// let mut recorder = RecordingReader::new(&b"1\n2"[..], Vec::new())?;
+/// recorder.read_to_end(&mut Vec::new())?;
+/// let (_, recording) = recorder.into_inner();
+///
+/// let replay = ReplayReader::from_reader(&recording[..])?;
+/// let mut reader = LineReader::from_nonblocking(replay)?;
+/// while reader.read_once()? {}
+/// assert_eq!(reader.lines_get(), vec!["1\n", "2"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ReplayReader {
+    chunks: VecDeque<(Duration, Vec<u8>)>,
+    timing: Option<Instant>,
+}
+
+impl ReplayReader {
+    /// Loads the recording at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, io::Error> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Loads a recording from a reader.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, io::Error> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a lineriver recording",
+            ));
+        }
+        let mut chunks = VecDeque::new();
+        let mut header = [0; 12];
+        loop {
+            match reader.read_exact(&mut header) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            }
+            let micros = u64::from_le_bytes(header[..8].try_into().unwrap());
+            let len = u32::from_le_bytes(header[8..].try_into().unwrap());
+            let mut chunk = vec![0; len as usize];
+            reader.read_exact(&mut chunk)?;
+            chunks.push_back((Duration::from_micros(micros), chunk));
+        }
+        Ok(Self {
+            chunks,
+            timing: None,
+        })
+    }
+
+    /// Makes the replay respect the original timing: each chunk is only
+    /// returned after the same delay, relative to the first read, that
+    /// it had in the recording; reads before that fail with
+    /// [`io::ErrorKind::WouldBlock`].
+    pub fn with_timing(mut self, timing: bool) -> Self {
+        self.timing = timing.then(Instant::now);
+        self
+    }
+
+    /// Returns the number of chunks not played back yet.
+    pub fn remaining(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+impl Read for ReplayReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some((at, chunk)) = self.chunks.front_mut() else {
+            return Ok(0);
+        };
+        if let Some(start) = self.timing {
+            if start.elapsed() < *at {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+        }
+        if chunk.is_empty() {
+            self.chunks.pop_front();
+            return Ok(0);
+        }
+        let len = chunk.len().min(buf.len());
+        buf[..len].copy_from_slice(&chunk[..len]);
+        chunk.drain(..len);
+        if chunk.is_empty() {
+            self.chunks.pop_front();
+        }
+        Ok(len)
+    }
+}
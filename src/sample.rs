@@ -0,0 +1,182 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineSampler`], a [`LineRead`] wrapper that only
+//! keeps a sample of the lines.
+
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+
+use crate::lineread::{FullLatch, LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+
+/// How [`LineSampler`] selects the lines it keeps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+    /// Keeps one line out of every `n`, starting with the first one.
+    EveryNth(usize),
+    /// Keeps each line with the given probability, between `0.0` and
+    /// `1.0`.
+    Fraction(f64),
+}
+
+/// [`LineRead`] wrapper that only keeps a sample of the lines,
+/// counting the ones skipped.
+///
+/// This is meant for high-volume telemetry streams where full fidelity
+/// isn't needed.
+#[derive(Debug)]
+pub struct LineSampler<L> {
+    inner: L,
+    sampling: Sampling,
+    rng: u64,
+    seen: u64,
+    skipped: u64,
+    lines: VecDeque<String>,
+    full: FullLatch,
+}
+
+impl<L: LineRead> LineSampler<L> {
+    /// Creates a new `LineSampler` over `inner`.
+    ///
+    /// The random number generator used by [`Sampling::Fraction`] is
+    /// seeded randomly; use [`Self::with_seed`] for reproducible
+    /// samples.
+    pub fn new(inner: L, sampling: Sampling) -> Self {
+        if let Sampling::EveryNth(n) = sampling {
+            assert!(n > 0, "EveryNth requires n greater than 0");
+        }
+        let seed = RandomState::new().build_hasher().finish();
+        Self {
+            inner,
+            sampling,
+            rng: 0,
+            seen: 0,
+            skipped: 0,
+            lines: Default::default(),
+            full: FullLatch::default(),
+        }
+        .with_seed(seed)
+    }
+
+    /// Seeds the random number generator used by
+    /// [`Sampling::Fraction`].
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        // xorshift can't have a zero state:
+        self.rng = seed | 1;
+        self
+    }
+
+    /// Returns the number of lines skipped so far.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &L {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut L {
+        &mut self.inner
+    }
+
+    /// Returns the underlying reader, dropping the lines that were
+    /// not retrieved yet.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+
+    /// Returns a random number in `[0.0, 1.0)`, using xorshift64.
+    fn random(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    fn keep(&mut self) -> bool {
+        let seen = self.seen;
+        self.seen += 1;
+        match self.sampling {
+            Sampling::EveryNth(n) => seen.is_multiple_of(n as u64),
+            Sampling::Fraction(fraction) => self.random() < fraction,
+        }
+    }
+
+    /// Moves the sampled lines from the inner reader to our buffer.
+    fn pull(&mut self) {
+        self.full
+            .update(self.inner.is_full(), !self.lines.is_empty());
+        for line in self.inner.lines_get() {
+            if self.keep() {
+                self.lines.push_back(line);
+            } else {
+                self.skipped += 1;
+            }
+        }
+    }
+}
+
+impl<L: LineRead> LineRead for LineSampler<L> {
+    fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        let result = self.inner.read_once();
+        self.pull();
+        result
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        self.pull();
+        mem::take(&mut self.lines).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.pull();
+        self.lines.pop_front()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        self.pull();
+        !self.lines.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.full
+            .is_full(self.inner.is_full(), !self.lines.is_empty())
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: AsRawFd> AsRawFd for LineSampler<L> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd> AsFd for LineSampler<L> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd> LineReadRawFd for LineSampler<L> {}
+
+impl<L: LineRead + AsFd> LineReadFd for LineSampler<L> {}
+
+impl<L: LineRead + AsFd + AsRawFd> LineReadRawAndFd for LineSampler<L> {}
@@ -0,0 +1,63 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`SharedLineReader`], a handle that allows a
+//! [`LineRead`] object to be used from multiple threads.
+
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::lineread::LineRead;
+
+/// Shared handle to a [`LineRead`] object.
+///
+/// This is a thin newtype over `Arc<Mutex<_>>` for the common pattern
+/// where one thread polls the reader and another one consumes the
+/// lines. Cloning the handle is cheap and returns another handle to
+/// the same reader.
+#[derive(Debug)]
+pub struct SharedLineReader<L> {
+    inner: Arc<Mutex<L>>,
+}
+
+impl<L> Clone for SharedLineReader<L> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<L: LineRead> SharedLineReader<L> {
+    /// Creates a new shared handle that owns the given reader.
+    pub fn new(linereader: L) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(linereader)),
+        }
+    }
+
+    /// Locks the reader and returns a guard that gives access to the
+    /// whole [`LineRead`] API.
+    ///
+    /// A panic in another thread while holding the lock doesn't
+    /// poison the reader, as it doesn't break its invariants.
+    pub fn lock(&self) -> MutexGuard<'_, L> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Calls [`LineRead::read_available`] on the reader.
+    pub fn read_available(&self) -> Result<(), io::Error> {
+        self.lock().read_available()
+    }
+
+    /// Calls [`LineRead::lines_get`] on the reader.
+    pub fn lines_get(&self) -> Vec<String> {
+        self.lock().lines_get()
+    }
+
+    /// Calls [`LineRead::eof`] on the reader.
+    pub fn eof(&self) -> bool {
+        self.lock().eof()
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has the [`ByteSource`] trait, for non-blocking byte
+//! sources that don't have a file descriptor.
+
+use std::fmt;
+use std::io::{self, Read};
+use std::sync::mpsc;
+
+use crate::LineReader;
+
+/// Result of [`ByteSource::fill`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fill {
+    /// This many bytes were written to the buffer; never 0.
+    Data(usize),
+    /// No data available at the moment.
+    Pending,
+    /// The source is exhausted.
+    Eof,
+}
+
+/// Non-blocking source of bytes that doesn't need a file descriptor.
+///
+/// In-memory channels, ring buffers shared with another thread and
+/// FFI callbacks can implement this trait and be read by a
+/// [`LineReader`] created with [`LineReader::from_source`], so that they
+/// can take part in [`LineRead`](crate::LineRead) collections.
+///
+/// It is implemented for closures with the signature of
+/// [`Self::fill`].
+pub trait ByteSource {
+    /// Fills `buf` with the data available, without blocking.
+    fn fill(&mut self, buf: &mut [u8]) -> Result<Fill, io::Error>;
+}
+
+impl<F> ByteSource for F
+where
+    F: FnMut(&mut [u8]) -> Result<Fill, io::Error>,
+{
+    fn fill(&mut self, buf: &mut [u8]) -> Result<Fill, io::Error> {
+        self(buf)
+    }
+}
+
+/// [`Read`] adapter for a [`ByteSource`], that fails with
+/// [`io::ErrorKind::WouldBlock`] when the source is pending.
+pub struct SourceReader<S>(pub S);
+
+impl<S> fmt::Debug for SourceReader<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SourceReader")
+            .field(&std::any::type_name::<S>())
+            .finish()
+    }
+}
+
+impl<S: ByteSource> Read for SourceReader<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.fill(buf)? {
+            Fill::Data(len) => Ok(len),
+            Fill::Pending => Err(io::ErrorKind::WouldBlock.into()),
+            Fill::Eof => Ok(0),
+        }
+    }
+}
+
+impl<S: ByteSource> LineReader<SourceReader<S>> {
+    /// Creates a new LineReader that reads from a [`ByteSource`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// use std::sync::mpsc;
+    /// use lineriver::{ChannelSource, LineRead, LineReader};
+    ///
+    /// let (sender, receiver) = mpsc::channel();
+    /// let mut reader = LineReader::from_source(ChannelSource::new(receiver))?;
+    /// sender.send(b"1\n2".to_vec()).unwrap();
+    /// drop(sender);
+    /// while reader.read_once()? {}
+    /// assert_eq!(reader.lines_get(), vec!["1\n", "2"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_source(source: S) -> Result<Self, io::Error> {
+        Self::from_nonblocking(SourceReader(source))
+    }
+}
+
+/// [`ByteSource`] that gets its data from the chunks sent through a
+/// [`mpsc::Receiver`].
+///
+/// It reaches EOF when all senders are dropped.
+#[derive(Debug)]
+pub struct ChannelSource {
+    receiver: mpsc::Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+}
+
+impl ChannelSource {
+    /// Creates a ChannelSource that reads from `receiver`.
+    pub fn new(receiver: mpsc::Receiver<Vec<u8>>) -> Self {
+        Self {
+            receiver,
+            chunk: Vec::new(),
+        }
+    }
+}
+
+impl ByteSource for ChannelSource {
+    fn fill(&mut self, buf: &mut [u8]) -> Result<Fill, io::Error> {
+        while self.chunk.is_empty() {
+            match self.receiver.try_recv() {
+                Ok(chunk) => self.chunk = chunk,
+                Err(mpsc::TryRecvError::Empty) => return Ok(Fill::Pending),
+                Err(mpsc::TryRecvError::Disconnected) => return Ok(Fill::Eof),
+            }
+        }
+        let len = self.chunk.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.chunk[..len]);
+        self.chunk.drain(..len);
+        Ok(Fill::Data(len))
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`ReadHalf`] and [`LinesHalf`], the two halves of a
+//! [`LineReader`] created by [`LineReader::split`].
+
+use std::fmt::Debug;
+use std::io::{self, Read};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::LineRead;
+use crate::LineReader;
+
+/// The I/O half of a split [`LineReader`].
+///
+/// It is meant to be driven by the poll loop: [`Self::read_once`] reads
+/// from the underlying reader and sends the complete lines to the
+/// corresponding [`LinesHalf`].
+#[derive(Debug)]
+pub struct ReadHalf<R> {
+    reader: LineReader<R>,
+    sender: Option<mpsc::Sender<String>>,
+}
+
+/// The consumer half of a split [`LineReader`].
+///
+/// It gets the lines read by the corresponding [`ReadHalf`], possibly in
+/// another thread. As an [`Iterator`], it blocks until the next line is
+/// available, and ends when the `ReadHalf` reaches EOF, fails to read,
+/// or is dropped.
+#[derive(Debug)]
+pub struct LinesHalf {
+    receiver: mpsc::Receiver<String>,
+}
+
+impl<R: Read + Debug> LineReader<R> {
+    /// Splits the reader into a [`ReadHalf`], that does the reading,
+    /// and a [`LinesHalf`], that gets the lines.
+    ///
+    /// The halves are connected by an [`mpsc`] channel, so that I/O and
+    /// processing can live in different threads without wrapping the
+    /// whole reader in a mutex. Lines already read go to the
+    /// `LinesHalf`.
+    pub fn split(mut self) -> (ReadHalf<R>, LinesHalf) {
+        let (sender, receiver) = mpsc::channel();
+        for line in self.lines_get() {
+            // The receiver is still alive, this can't fail:
+            let _ = sender.send(line);
+        }
+        let sender = (!self.eof()).then_some(sender);
+        let read_half = ReadHalf {
+            reader: self,
+            sender,
+        };
+        (read_half, LinesHalf { receiver })
+    }
+}
+
+impl<R: Read + Debug> ReadHalf<R> {
+    /// Reads once from the underlying reader and sends the complete
+    /// lines to the [`LinesHalf`].
+    ///
+    /// Returns `Ok(true)` if the reader is not at EOF, like
+    /// [`LineRead::read_once`]. Fails with
+    /// [`io::ErrorKind::BrokenPipe`] if the `LinesHalf` was dropped.
+    ///
+    /// A read error ends the [`LinesHalf`] after the lines already
+    /// read, so that it doesn't wait forever; later calls return
+    /// `Ok(false)`.
+    pub fn read_once(&mut self) -> Result<bool, io::Error> {
+        let Some(sender) = &self.sender else {
+            return Ok(false);
+        };
+        let result = self.reader.read_once();
+        for line in self.reader.lines_get() {
+            if sender.send(line).is_err() {
+                return Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "the LinesHalf was dropped",
+                ));
+            }
+        }
+        if self.reader.eof() || result.is_err() {
+            self.sender = None;
+        }
+        result
+    }
+
+    /// Returns true if the underlying reader reached EOF.
+    pub fn eof(&self) -> bool {
+        self.reader.eof()
+    }
+
+    /// Returns a reference to the underlying [`LineReader`].
+    pub fn get_ref(&self) -> &LineReader<R> {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the underlying [`LineReader`].
+    ///
+    /// Lines taken directly from it don't reach the [`LinesHalf`].
+    pub fn get_mut(&mut self) -> &mut LineReader<R> {
+        &mut self.reader
+    }
+}
+
+impl<R: AsRawFd> AsRawFd for ReadHalf<R> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.reader.as_raw_fd()
+    }
+}
+
+impl<R: AsFd> AsFd for ReadHalf<R> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.reader.as_fd()
+    }
+}
+
+impl LinesHalf {
+    /// Returns all lines available, without blocking.
+    pub fn lines_get(&mut self) -> Vec<String> {
+        self.receiver.try_iter().collect()
+    }
+
+    /// Waits up to `timeout` for the next line.
+    ///
+    /// Returns `None` on timeout, or if the [`ReadHalf`] reached EOF or
+    /// was dropped.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<String> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+}
+
+impl Iterator for LinesHalf {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.receiver.recv().ok()
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineSplitter`], the sans-IO line splitting state
+//! machine, which only needs `core` and `alloc`.
+
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::mem;
+use core::str::Utf8Error;
+
+/// Sans-IO line splitter: bytes are pushed in, complete lines come out.
+///
+/// It splits lines at `\n`, keeping it at the end of each line, like
+/// [`LineReader`] does by default, but doesn't do any I/O itself. That
+/// makes it usable with any byte source, even without `std`: building
+/// the crate with `default-features = false` leaves only this module,
+/// for `#![no_std]` targets with an allocator, like firmware that
+/// receives line-oriented data over a UART.
+///
+/// It is deliberately minimal: the other delimiters, terminators and
+/// line length limits of [`LineReader`] are not supported.
+///
+/// [`LineReader`]: https://docs.rs/lineriver/latest/lineriver/struct.LineReader.html
+///
+/// Example:
+///
+/// ```
+/// use lineriver::LineSplitter;
+///
+/// let mut splitter = LineSplitter::new();
+/// splitter.push(b"AT+OK\r\nAT+")?;
+/// assert_eq!(splitter.pop_line().as_deref(), Some("AT+OK\r\n"));
+/// assert_eq!(splitter.partial(), b"AT+");
+/// splitter.push(b"ERR")?;
+/// splitter.finish()?;
+/// assert_eq!(splitter.lines_get(), vec!["AT+ERR"]);
+/// # Ok::<(), core::str::Utf8Error>(())
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct LineSplitter {
+    partial: Vec<u8>,
+    lines: VecDeque<String>,
+}
+
+impl LineSplitter {
+    /// Creates an empty LineSplitter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pushes bytes into the splitter, splitting out the complete
+    /// lines.
+    ///
+    /// A line that is not valid UTF-8 is dropped and its error is
+    /// returned, after the lines before it were queued; the bytes after
+    /// it are still processed, so that the splitter can continue.
+    pub fn push(&mut self, mut data: &[u8]) -> Result<(), Utf8Error> {
+        let mut result = Ok(());
+        while let Some(inewline) = memchr::memchr(b'\n', data) {
+            self.partial.extend_from_slice(&data[..=inewline]);
+            data = &data[inewline + 1..];
+            if let Err(e) = self.take_partial() {
+                result = result.and(Err(e));
+            }
+        }
+        self.partial.extend_from_slice(data);
+        result
+    }
+
+    /// Signals the end of the input, turning the partial line, if any,
+    /// into the last line.
+    pub fn finish(&mut self) -> Result<(), Utf8Error> {
+        if self.partial.is_empty() {
+            return Ok(());
+        }
+        self.take_partial()
+    }
+
+    /// Moves the partial line to the line queue.
+    fn take_partial(&mut self) -> Result<(), Utf8Error> {
+        let line = mem::take(&mut self.partial);
+        let line = String::from_utf8(line).map_err(|e| e.utf8_error())?;
+        self.lines.push_back(line);
+        Ok(())
+    }
+
+    /// Returns `true` if there are complete lines available.
+    pub fn has_lines(&self) -> bool {
+        !self.lines.is_empty()
+    }
+
+    /// Takes the oldest complete line.
+    pub fn pop_line(&mut self) -> Option<String> {
+        self.lines.pop_front()
+    }
+
+    /// Takes all complete lines.
+    pub fn lines_get(&mut self) -> Vec<String> {
+        mem::take(&mut self.lines).into()
+    }
+
+    /// Returns the bytes of the line that is not complete yet.
+    pub fn partial(&self) -> &[u8] {
+        &self.partial
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`ReaderState`], a snapshot of the internal state
+//! of a [`LineReader`](crate::LineReader) returned by
+//! [`LineReader::dump_state`](crate::LineReader::dump_state).
+
+use std::fmt;
+
+/// Snapshot of the internal state of a
+/// [`LineReader`](crate::LineReader), for troubleshooting framing
+/// problems.
+///
+/// The [`Display`](fmt::Display) implementation shows the fields and a
+/// hexdump of the partial line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ReaderState {
+    /// Bytes of partial line in the buffer.
+    pub used: usize,
+    /// Capacity of the buffer, in bytes.
+    pub capacity: usize,
+    /// Copy of the partial line in the buffer.
+    pub partial: Vec<u8>,
+    /// Number of complete lines waiting to be retrieved.
+    pub queued_lines: usize,
+    /// The reader reached EOF.
+    pub eof: bool,
+    /// The reader is broken by an error.
+    pub broken: bool,
+    /// The reader is paused.
+    pub paused: bool,
+}
+
+impl fmt::Display for ReaderState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "used={} capacity={} queued_lines={} eof={} broken={} paused={}",
+            self.used, self.capacity, self.queued_lines, self.eof, self.broken, self.paused
+        )?;
+        for (i, chunk) in self.partial.chunks(16).enumerate() {
+            write!(f, "{:08x} ", i * 16)?;
+            for j in 0..16 {
+                match chunk.get(j) {
+                    Some(byte) => write!(f, " {:02x}", byte)?,
+                    None => write!(f, "   ")?,
+                }
+            }
+            write!(f, "  |")?;
+            for &byte in chunk {
+                let c = if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f, "|")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`LineStats`], the statistics collected by
+//! [`LineReader`](crate::LineReader) when enabled with
+//! [`LineReader::with_stats`](crate::LineReader::with_stats).
+
+/// Histogram with power-of-two buckets.
+///
+/// Bucket `0` counts the value `0`, and bucket `i` counts the values
+/// in `[2^(i-1), 2^i)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: [u64; 65],
+    count: u64,
+    sum: u64,
+    max: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; 65],
+            count: 0,
+            sum: 0,
+            max: 0,
+        }
+    }
+}
+
+impl Histogram {
+    /// Records a value.
+    pub fn record(&mut self, value: u64) {
+        self.buckets[(u64::BITS - value.leading_zeros()) as usize] += 1;
+        self.count += 1;
+        self.sum = self.sum.saturating_add(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Returns the number of values recorded.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the sum of the values recorded.
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// Returns the maximum value recorded.
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Returns the mean of the values recorded, or `None` if there
+    /// are none.
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum as f64 / self.count as f64)
+    }
+
+    /// Returns an upper bound of the given quantile (between `0.0` and
+    /// `1.0`) of the values recorded, or `None` if there are none.
+    ///
+    /// The result is the upper bound of the bucket where the quantile
+    /// falls, capped by the maximum value recorded.
+    pub fn quantile(&self, quantile: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let upper = if bucket == 0 {
+                    0
+                } else {
+                    u64::MAX >> (64 - bucket)
+                };
+                return Some(upper.min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+
+    /// Returns the non-empty buckets as `(upper bound, count)` pairs,
+    /// where the upper bound is inclusive.
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(bucket, count)| {
+                let upper = if bucket == 0 {
+                    0
+                } else {
+                    u64::MAX >> (64 - bucket)
+                };
+                (upper, *count)
+            })
+    }
+}
+
+/// Statistics about the lines of a [`LineReader`](crate::LineReader).
+///
+/// These help diagnose slow consumers and pathological inputs in
+/// production.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LineStats {
+    /// Length of the lines, in bytes.
+    pub line_len: Histogram,
+    /// Time between a line being completed by a read and it being
+    /// retrieved by the consumer, in microseconds.
+    pub latency_us: Histogram,
+}
@@ -0,0 +1,180 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`listen_fds`], which gets the descriptors passed
+//! by systemd socket activation.
+
+use std::env;
+use std::fs::File;
+use std::io;
+use std::mem;
+use std::net::{TcpListener, TcpStream};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use crate::linereader::LineReader;
+
+/// First descriptor passed by systemd.
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Descriptor passed by systemd socket activation.
+#[derive(Debug)]
+pub struct ListenFd {
+    fd: OwnedFd,
+    name: String,
+}
+
+/// Gets the descriptors passed by systemd socket activation, as
+/// described in `sd_listen_fds(3)`.
+///
+/// Returns an empty vector if the process was not socket-activated,
+/// i.e. if `LISTEN_PID` is not the PID of the current process. The
+/// descriptors are validated and marked close-on-exec.
+///
+/// If `unset_env` is `true`, the `LISTEN_PID`, `LISTEN_FDS` and
+/// `LISTEN_FDNAMES` environment variables are removed, so that child
+/// processes don't see them.
+pub fn listen_fds(unset_env: bool) -> Result<Vec<ListenFd>, io::Error> {
+    let pid = env::var("LISTEN_PID").ok();
+    let fds = env::var("LISTEN_FDS").ok();
+    let names = env::var("LISTEN_FDNAMES").ok();
+    if unset_env {
+        env::remove_var("LISTEN_PID");
+        env::remove_var("LISTEN_FDS");
+        env::remove_var("LISTEN_FDNAMES");
+    }
+    let (Some(pid), Some(fds)) = (pid, fds) else {
+        return Ok(vec![]);
+    };
+    let pid = pid.parse::<u32>().map_err(invalid_data)?;
+    if pid != std::process::id() {
+        return Ok(vec![]);
+    }
+    let num = fds.parse::<RawFd>().map_err(invalid_data)?;
+    let names = names
+        .map(|n| n.split(':').map(String::from).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let mut result = vec![];
+    for (i, fd) in (LISTEN_FDS_START..LISTEN_FDS_START + num).enumerate() {
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+        if flags < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        result.push(ListenFd {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            name: names
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| String::from("unknown")),
+        });
+    }
+    Ok(result)
+}
+
+fn invalid_data<E: std::error::Error + Send + Sync + 'static>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+impl ListenFd {
+    /// Returns the name of the descriptor, from `FileDescriptorName=`
+    /// in the socket unit, or `"unknown"`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the underlying descriptor.
+    pub fn into_fd(self) -> OwnedFd {
+        self.fd
+    }
+
+    fn sockopt(&self, option: libc::c_int) -> Result<libc::c_int, io::Error> {
+        let mut value: libc::c_int = 0;
+        let mut len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+        let r = unsafe {
+            libc::getsockopt(
+                self.fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                option,
+                &mut value as *mut libc::c_int as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if r < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(value)
+    }
+
+    fn family(&self) -> Result<libc::c_int, io::Error> {
+        let mut addr: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        let r = unsafe {
+            libc::getsockname(
+                self.fd.as_raw_fd(),
+                &mut addr as *mut libc::sockaddr_storage as *mut libc::sockaddr,
+                &mut len,
+            )
+        };
+        if r < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(addr.ss_family as libc::c_int)
+    }
+
+    /// Checks that the descriptor is a stream socket of one of the
+    /// given families, listening or not.
+    fn check(&self, families: &[libc::c_int], listening: bool) -> Result<(), io::Error> {
+        let ok = self.sockopt(libc::SO_TYPE)? == libc::SOCK_STREAM
+            && families.contains(&self.family()?)
+            && (self.sockopt(libc::SO_ACCEPTCONN)? != 0) == listening;
+        if ok {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "socket-activated descriptor {} has the wrong type",
+                    self.name
+                ),
+            ))
+        }
+    }
+
+    /// Converts the descriptor into a [`TcpListener`], checking that
+    /// it is a listening TCP socket.
+    pub fn into_tcp_listener(self) -> Result<TcpListener, io::Error> {
+        self.check(&[libc::AF_INET, libc::AF_INET6], true)?;
+        Ok(TcpListener::from(self.fd))
+    }
+
+    /// Converts the descriptor into a [`UnixListener`], checking that
+    /// it is a listening Unix stream socket.
+    pub fn into_unix_listener(self) -> Result<UnixListener, io::Error> {
+        self.check(&[libc::AF_UNIX], true)?;
+        Ok(UnixListener::from(self.fd))
+    }
+
+    /// Converts the descriptor into a [`TcpStream`], checking that it
+    /// is a connected TCP socket, as passed with `Accept=yes`.
+    pub fn into_tcp_stream(self) -> Result<TcpStream, io::Error> {
+        self.check(&[libc::AF_INET, libc::AF_INET6], false)?;
+        Ok(TcpStream::from(self.fd))
+    }
+
+    /// Converts the descriptor into a [`UnixStream`], checking that it
+    /// is a connected Unix stream socket, as passed with `Accept=yes`.
+    pub fn into_unix_stream(self) -> Result<UnixStream, io::Error> {
+        self.check(&[libc::AF_UNIX], false)?;
+        Ok(UnixStream::from(self.fd))
+    }
+
+    /// Creates a [`LineReader`] that reads from the descriptor, which
+    /// can be a connected socket or a FIFO.
+    pub fn into_line_reader(self) -> Result<LineReader<File>, io::Error> {
+        LineReader::new(File::from(self.fd))
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`TcpOptions`], the socket options used when
+//! connecting to TCP endpoints.
+
+use std::io;
+use std::mem;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::os::fd::AsRawFd;
+use std::time::Duration;
+
+/// Socket options for TCP connections, used by
+/// [`LineForwarder::tcp_with_options`](crate::LineForwarder::tcp_with_options).
+///
+/// The default enables `TCP_NODELAY`, as line-based protocols are
+/// latency-sensitive and Nagle's algorithm delays small writes; it
+/// doesn't enable keepalive or set a connect timeout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpOptions {
+    nodelay: bool,
+    keepalive: Option<Duration>,
+    connect_timeout: Option<Duration>,
+}
+
+impl Default for TcpOptions {
+    fn default() -> Self {
+        Self {
+            nodelay: true,
+            keepalive: None,
+            connect_timeout: None,
+        }
+    }
+}
+
+impl TcpOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets `TCP_NODELAY`.
+    pub fn with_nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = nodelay;
+        self
+    }
+
+    /// Enables keepalive probes after the connection is idle for the
+    /// given time, or disables them with `None`.
+    pub fn with_keepalive(mut self, idle: Option<Duration>) -> Self {
+        self.keepalive = idle;
+        self
+    }
+
+    /// Sets the timeout of each connection attempt, or waits for the
+    /// system timeout with `None`.
+    pub fn with_connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Connects to `addr`, trying each of its addresses in turn, and
+    /// applies the options to the resulting stream.
+    ///
+    /// The stream is left in blocking mode.
+    pub fn connect<A: ToSocketAddrs>(&self, addr: A) -> Result<TcpStream, io::Error> {
+        let mut last_err = None;
+        for addr in addr.to_socket_addrs()? {
+            let result = match self.connect_timeout {
+                Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+                None => TcpStream::connect(addr),
+            };
+            match result {
+                Ok(stream) => {
+                    self.apply(&stream)?;
+                    return Ok(stream);
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address")))
+    }
+
+    /// Applies the options to an existing stream.
+    pub fn apply(&self, stream: &TcpStream) -> Result<(), io::Error> {
+        stream.set_nodelay(self.nodelay)?;
+        let fd = stream.as_raw_fd();
+        setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_KEEPALIVE,
+            self.keepalive.is_some() as libc::c_int,
+        )?;
+        if let Some(idle) = self.keepalive {
+            let secs = idle.as_secs().clamp(1, libc::c_int::MAX as u64) as libc::c_int;
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            setsockopt(fd, libc::IPPROTO_TCP, libc::TCP_KEEPIDLE, secs)?;
+            #[cfg(target_vendor = "apple")]
+            setsockopt(fd, libc::IPPROTO_TCP, libc::TCP_KEEPALIVE, secs)?;
+            #[cfg(not(any(target_os = "linux", target_os = "android", target_vendor = "apple")))]
+            let _ = secs;
+        }
+        Ok(())
+    }
+}
+
+fn setsockopt(
+    fd: std::os::fd::RawFd,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: libc::c_int,
+) -> Result<(), io::Error> {
+    let r = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            name,
+            &value as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if r != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
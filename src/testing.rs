@@ -0,0 +1,285 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has test doubles that make it easy to exercise code
+//! that uses this crate deterministically.
+//!
+//! [`ScriptedReader`] is a [`Read`] object that returns a programmed
+//! sequence of chunks and errors, which can be used to cover partial
+//! reads and error paths that are hard to trigger with real file
+//! descriptors.
+//!
+//! [`MockLineRead`] is a [`LineRead`] object that returns programmed
+//! lines, EOF and errors, to test code that consumes lines through
+//! `&mut dyn LineRead` without sockets or subprocesses.
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+use crate::LineRead;
+
+/// An event returned by [`ScriptedReader::read`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptEvent {
+    /// Returns the given bytes; if the buffer passed to `read` is too
+    /// small, the rest is returned by the next call.
+    Data(Vec<u8>),
+    /// Fails with [`io::ErrorKind::WouldBlock`].
+    WouldBlock,
+    /// Fails with [`io::ErrorKind::Interrupted`].
+    Interrupted,
+    /// Fails with an error of the given kind.
+    Error(io::ErrorKind),
+    /// Returns `Ok(0)`, signalling end of file.
+    Eof,
+}
+
+/// [`Read`] object that returns a programmed sequence of events.
+///
+/// Once all events are consumed, every further `read` returns
+/// `Ok(0)`.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lineriver::testing::ScriptedReader;
+/// use lineriver::{LineRead, LineReader};
+///
+/// let script = ScriptedReader::new()
+///     .data(b"fir")
+///     .would_block()
+///     .data(b"st\n");
+/// let mut linereader = LineReader::from_nonblocking(script)?;
+/// while !linereader.eof() {
+///     linereader.read_once()?;
+/// }
+/// assert_eq!(linereader.lines_get(), vec!["first\n"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ScriptedReader {
+    events: VecDeque<ScriptEvent>,
+}
+
+impl ScriptedReader {
+    /// Creates a new reader with an empty script.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Appends an event to the script.
+    pub fn event(mut self, event: ScriptEvent) -> Self {
+        self.events.push_back(event);
+        self
+    }
+
+    /// Appends a [`ScriptEvent::Data`] event to the script.
+    pub fn data(self, data: &[u8]) -> Self {
+        self.event(ScriptEvent::Data(data.to_vec()))
+    }
+
+    /// Appends a [`ScriptEvent::WouldBlock`] event to the script.
+    pub fn would_block(self) -> Self {
+        self.event(ScriptEvent::WouldBlock)
+    }
+
+    /// Appends a [`ScriptEvent::Interrupted`] event to the script.
+    pub fn interrupted(self) -> Self {
+        self.event(ScriptEvent::Interrupted)
+    }
+
+    /// Appends a [`ScriptEvent::Error`] event to the script.
+    pub fn error(self, kind: io::ErrorKind) -> Self {
+        self.event(ScriptEvent::Error(kind))
+    }
+
+    /// Appends a [`ScriptEvent::Eof`] event to the script.
+    pub fn eof(self) -> Self {
+        self.event(ScriptEvent::Eof)
+    }
+
+    /// Returns the number of events not consumed yet.
+    pub fn remaining(&self) -> usize {
+        self.events.len()
+    }
+}
+
+impl FromIterator<ScriptEvent> for ScriptedReader {
+    fn from_iter<I: IntoIterator<Item = ScriptEvent>>(iter: I) -> Self {
+        Self {
+            events: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Read for ScriptedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.events.pop_front() {
+            None | Some(ScriptEvent::Eof) => Ok(0),
+            Some(ScriptEvent::WouldBlock) => Err(io::ErrorKind::WouldBlock.into()),
+            Some(ScriptEvent::Interrupted) => Err(io::ErrorKind::Interrupted.into()),
+            Some(ScriptEvent::Error(kind)) => Err(kind.into()),
+            Some(ScriptEvent::Data(mut data)) => {
+                let len = data.len().min(buf.len());
+                buf[..len].copy_from_slice(&data[..len]);
+                if len < data.len() {
+                    self.events
+                        .push_front(ScriptEvent::Data(data.split_off(len)));
+                }
+                Ok(len)
+            }
+        }
+    }
+}
+
+/// A step performed by each [`MockLineRead::read_once`] call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockStep {
+    /// Makes the given lines available.
+    Lines(Vec<String>),
+    /// Reads nothing, like a read that would block.
+    Pending,
+    /// Fails with an error of the given kind.
+    Error(io::ErrorKind),
+    /// Reaches EOF.
+    Eof,
+}
+
+/// [`LineRead`] object that performs a programmed sequence of steps,
+/// one per [`read_once`](LineRead::read_once) call.
+///
+/// Once all steps are performed, the next `read_once` reaches EOF.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lineriver::testing::MockLineRead;
+/// use lineriver::LineRead;
+///
+/// fn count_lines(reader: &mut dyn LineRead) -> std::io::Result<usize> {
+///     let mut count = 0;
+///     while !reader.eof() {
+///         reader.read_available()?;
+///         count += reader.lines_get().len();
+///     }
+///     Ok(count)
+/// }
+///
+/// let mut mock = MockLineRead::new().lines(&["a\n", "b\n"]).pending().line("c");
+/// assert_eq!(count_lines(&mut mock)?, 3);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct MockLineRead {
+    steps: VecDeque<MockStep>,
+    lines: VecDeque<String>,
+    at_eof: bool,
+    reads: usize,
+    label: Option<String>,
+}
+
+impl MockLineRead {
+    /// Creates a new mock with an empty script.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Appends a step to the script.
+    pub fn step(mut self, step: MockStep) -> Self {
+        self.steps.push_back(step);
+        self
+    }
+
+    /// Appends a [`MockStep::Lines`] step with the given lines.
+    pub fn lines(self, lines: &[&str]) -> Self {
+        self.step(MockStep::Lines(
+            lines.iter().map(|l| l.to_string()).collect(),
+        ))
+    }
+
+    /// Appends a [`MockStep::Lines`] step with a single line.
+    pub fn line(self, line: &str) -> Self {
+        self.lines(&[line])
+    }
+
+    /// Appends a [`MockStep::Pending`] step.
+    pub fn pending(self) -> Self {
+        self.step(MockStep::Pending)
+    }
+
+    /// Appends a [`MockStep::Error`] step.
+    pub fn error(self, kind: io::ErrorKind) -> Self {
+        self.step(MockStep::Error(kind))
+    }
+
+    /// Appends a [`MockStep::Eof`] step.
+    pub fn eof(self) -> Self {
+        self.step(MockStep::Eof)
+    }
+
+    /// Sets the label returned by [`LineRead::label`].
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Returns the number of steps not performed yet.
+    pub fn remaining(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns the number of `read_once` calls made so far.
+    pub fn reads(&self) -> usize {
+        self.reads
+    }
+}
+
+impl FromIterator<MockStep> for MockLineRead {
+    fn from_iter<I: IntoIterator<Item = MockStep>>(iter: I) -> Self {
+        Self {
+            steps: iter.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl LineRead for MockLineRead {
+    fn eof(&self) -> bool {
+        self.at_eof
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        if self.at_eof {
+            return Ok(false);
+        }
+        self.reads += 1;
+        match self.steps.pop_front() {
+            None | Some(MockStep::Eof) => self.at_eof = true,
+            Some(MockStep::Pending) => {}
+            Some(MockStep::Error(kind)) => return Err(kind.into()),
+            Some(MockStep::Lines(lines)) => self.lines.extend(lines),
+        }
+        Ok(true)
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.lines).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.lines.pop_front()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        !self.lines.is_empty()
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+}
@@ -0,0 +1,293 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`TimestampFormat`], used to extract the event time
+//! from the beginning of lines.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Format of the timestamp found at the beginning of lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// RFC 3339 timestamp, like `2024-02-18T10:20:30.123+01:00`.
+    ///
+    /// A space is also accepted as the date/time separator, and a
+    /// missing offset is taken as UTC.
+    Rfc3339,
+    /// strftime-style format.
+    ///
+    /// The supported specifiers are `%Y`, `%m`, `%d`, `%e`, `%H`,
+    /// `%M`, `%S`, `%f` (fraction of second), `%b` (abbreviated month
+    /// name), `%z` (`Z`, `+hhmm` or `+hh:mm`), `%F` (`%Y-%m-%d`), `%T`
+    /// (`%H:%M:%S`) and `%%`. Fields not in the format take their
+    /// value from the Unix epoch, and times are UTC unless `%z` is
+    /// used; without `%Y`, February 29 is then rejected as invalid.
+    Strftime(String),
+}
+
+/// Broken-down time being parsed.
+#[derive(Debug)]
+struct Fields {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+    nanos: u32,
+    offset: i64,
+}
+
+impl Default for Fields {
+    fn default() -> Self {
+        Self {
+            year: 1970,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            nanos: 0,
+            offset: 0,
+        }
+    }
+}
+
+impl Fields {
+    fn to_system_time(&self) -> Option<SystemTime> {
+        if !(1..=12).contains(&self.month)
+            || !(1..=days_in_month(self.year, self.month)).contains(&self.day)
+            || self.hour > 23
+            || self.minute > 59
+            || self.second > 60
+        {
+            return None;
+        }
+        let secs = days_from_civil(self.year, self.month, self.day) * 86400
+            + self.hour * 3600
+            + self.minute * 60
+            + self.second
+            - self.offset;
+        let time = if secs >= 0 {
+            UNIX_EPOCH.checked_add(Duration::from_secs(secs as u64))?
+        } else {
+            UNIX_EPOCH.checked_sub(Duration::from_secs(secs.unsigned_abs()))?
+        };
+        time.checked_add(Duration::from_nanos(self.nanos as u64))
+    }
+}
+
+/// Number of days of `month` in `year`, in the proleptic Gregorian
+/// calendar.
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since the Unix epoch of a date in the proleptic Gregorian
+/// calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Date in the proleptic Gregorian calendar from days since the Unix
+/// epoch; the inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Formats `time` as an RFC 3339 UTC timestamp with microseconds,
+/// like `2024-02-18T10:20:30.000000Z`.
+pub(crate) fn format_rfc3339(time: SystemTime) -> String {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since.as_secs() as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+    let secs = secs.rem_euclid(86400);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        since.subsec_micros()
+    )
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Cursor over the input being parsed.
+#[derive(Debug)]
+struct Cursor<'a> {
+    input: &'a [u8],
+}
+
+impl Cursor<'_> {
+    fn literal(&mut self, c: u8) -> Option<()> {
+        let (&first, rest) = self.input.split_first()?;
+        (first == c).then(|| self.input = rest)
+    }
+
+    fn digits(&mut self, width: usize) -> Option<i64> {
+        let digits = self.input.get(..width)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.input = &self.input[width..];
+        Some(digits.iter().fold(0, |n, d| n * 10 + (d - b'0') as i64))
+    }
+
+    /// Day of month, possibly padded with a space.
+    fn day_padded(&mut self) -> Option<i64> {
+        if self.input.first() == Some(&b' ') {
+            self.input = &self.input[1..];
+            self.digits(1)
+        } else {
+            self.digits(2)
+        }
+    }
+
+    fn fraction(&mut self) -> Option<u32> {
+        let len = self.input.iter().take_while(|d| d.is_ascii_digit()).count();
+        if len == 0 {
+            return None;
+        }
+        let nanos = self.input[..len.min(9)]
+            .iter()
+            .chain(std::iter::repeat(&b'0'))
+            .take(9)
+            .fold(0, |n, d| n * 10 + (d - b'0') as u32);
+        self.input = &self.input[len..];
+        Some(nanos)
+    }
+
+    fn month_name(&mut self) -> Option<i64> {
+        let name = self.input.get(..3)?;
+        let month = MONTHS.iter().position(|m| m.as_bytes() == name)?;
+        self.input = &self.input[3..];
+        Some(month as i64 + 1)
+    }
+
+    /// `Z`, `+hhmm`, `+hh:mm` and the `-` variants, in seconds.
+    fn offset(&mut self) -> Option<i64> {
+        let sign = match self.input.first()? {
+            b'Z' | b'z' => {
+                self.input = &self.input[1..];
+                return Some(0);
+            }
+            b'+' => 1,
+            b'-' => -1,
+            _ => return None,
+        };
+        self.input = &self.input[1..];
+        let hours = self.digits(2)?;
+        let _ = self.literal(b':');
+        let minutes = self.digits(2)?;
+        Some(sign * (hours * 3600 + minutes * 60))
+    }
+}
+
+impl TimestampFormat {
+    /// Parses the timestamp at the beginning of `line`.
+    ///
+    /// Returns `None` if the line doesn't start with a valid
+    /// timestamp in this format.
+    pub fn parse_prefix(&self, line: &str) -> Option<SystemTime> {
+        let mut cursor = Cursor {
+            input: line.as_bytes(),
+        };
+        let fields = match self {
+            TimestampFormat::Rfc3339 => Self::parse_rfc3339(&mut cursor)?,
+            TimestampFormat::Strftime(format) => Self::parse_strftime(&mut cursor, format)?,
+        };
+        fields.to_system_time()
+    }
+
+    fn parse_rfc3339(cursor: &mut Cursor) -> Option<Fields> {
+        let mut fields = Fields {
+            year: cursor.digits(4)?,
+            ..Default::default()
+        };
+        cursor.literal(b'-')?;
+        fields.month = cursor.digits(2)?;
+        cursor.literal(b'-')?;
+        fields.day = cursor.digits(2)?;
+        cursor
+            .literal(b'T')
+            .or_else(|| cursor.literal(b't'))
+            .or_else(|| cursor.literal(b' '))?;
+        fields.hour = cursor.digits(2)?;
+        cursor.literal(b':')?;
+        fields.minute = cursor.digits(2)?;
+        cursor.literal(b':')?;
+        fields.second = cursor.digits(2)?;
+        if cursor.literal(b'.').is_some() {
+            fields.nanos = cursor.fraction()?;
+        }
+        fields.offset = cursor.offset().unwrap_or(0);
+        Some(fields)
+    }
+
+    fn parse_strftime(cursor: &mut Cursor, format: &str) -> Option<Fields> {
+        let mut fields = Fields::default();
+        let mut format = format.bytes();
+        while let Some(c) = format.next() {
+            if c != b'%' {
+                cursor.literal(c)?;
+                continue;
+            }
+            match format.next()? {
+                b'Y' => fields.year = cursor.digits(4)?,
+                b'm' => fields.month = cursor.digits(2)?,
+                b'd' => fields.day = cursor.digits(2)?,
+                b'e' => fields.day = cursor.day_padded()?,
+                b'H' => fields.hour = cursor.digits(2)?,
+                b'M' => fields.minute = cursor.digits(2)?,
+                b'S' => fields.second = cursor.digits(2)?,
+                b'f' => fields.nanos = cursor.fraction()?,
+                b'b' => fields.month = cursor.month_name()?,
+                b'z' => fields.offset = cursor.offset()?,
+                b'F' => {
+                    fields.year = cursor.digits(4)?;
+                    cursor.literal(b'-')?;
+                    fields.month = cursor.digits(2)?;
+                    cursor.literal(b'-')?;
+                    fields.day = cursor.digits(2)?;
+                }
+                b'T' => {
+                    fields.hour = cursor.digits(2)?;
+                    cursor.literal(b':')?;
+                    fields.minute = cursor.digits(2)?;
+                    cursor.literal(b':')?;
+                    fields.second = cursor.digits(2)?;
+                }
+                b'%' => cursor.literal(b'%')?,
+                _ => return None,
+            }
+        }
+        Some(fields)
+    }
+}
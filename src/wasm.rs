@@ -0,0 +1,266 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! This module has [`WasmPlugin`], a per-line transform/filter loaded
+//! from a WebAssembly module, and [`WasmFilter`], a [`LineRead`]
+//! wrapper that runs it.
+//!
+//! Requires the `wasm` feature.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::path::Path;
+
+use wasmi::{Engine, Linker, Memory, Module, Store, TypedFunc};
+
+use crate::lineread::{FullLatch, LineRead, LineReadFd, LineReadRawAndFd, LineReadRawFd};
+
+fn wasm_error(err: impl fmt::Display) -> io::Error {
+    io::Error::other(format!("wasm plugin: {}", err))
+}
+
+/// Per-line transform/filter loaded from a WebAssembly module, run
+/// with the [`wasmi`] interpreter.
+///
+/// This allows operators to customize the filtering of deployed agents
+/// without recompiling them. The module can be in the binary or in the
+/// text format, and must export:
+/// - `memory`: its linear memory;
+/// - `alloc(len: i32) -> i32`: returns a pointer to `len` bytes where
+///   the host writes the line;
+/// - `transform(ptr: i32, len: i32) -> i64`: processes the line, and
+///   returns `-1` to drop it, or the pointer to the resulting line in
+///   the upper 32 bits and its length in the lower 32 bits.
+///
+/// The module doesn't get any imports.
+///
+/// Example:
+///
+/// ```
+/// # fn main() -> Result<(), std::io::Error> {
+/// use lineriver::WasmPlugin;
+///
+/// // Drops the lines that start with '#', keeps the others:
+/// let mut plugin = WasmPlugin::new(
+///     r#"(module
+///         (memory (export "memory") 1)
+///         (func (export "alloc") (param i32) (result i32) i32.const 0)
+///         (func (export "transform") (param $ptr i32) (param $len i32) (result i64)
+///             (if (i32.eq (i32.load8_u (local.get $ptr)) (i32.const 35))
+///                 (then (return (i64.const -1))))
+///             (i64.or
+///                 (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+///                 (i64.extend_i32_u (local.get $len)))))"#,
+/// )?;
+/// assert_eq!(plugin.apply("# comment\n")?, None);
+/// assert_eq!(plugin.apply("data\n")?, Some("data\n".to_string()));
+/// # Ok(())
+/// # }
+/// ```
+pub struct WasmPlugin {
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    transform: TypedFunc<(i32, i32), i64>,
+}
+
+impl WasmPlugin {
+    /// Loads the plugin from a WebAssembly module, in the binary or in
+    /// the text format.
+    pub fn new(wasm: impl AsRef<[u8]>) -> Result<Self, io::Error> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, wasm).map_err(wasm_error)?;
+        let mut store = Store::new(&engine, ());
+        let instance = Linker::<()>::new(&engine)
+            .instantiate_and_start(&mut store, &module)
+            .map_err(wasm_error)?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| wasm_error("missing memory export"))?;
+        let alloc = instance
+            .get_typed_func(&store, "alloc")
+            .map_err(wasm_error)?;
+        let transform = instance
+            .get_typed_func(&store, "transform")
+            .map_err(wasm_error)?;
+        Ok(Self {
+            store,
+            memory,
+            alloc,
+            transform,
+        })
+    }
+
+    /// Loads the plugin from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, io::Error> {
+        Self::new(std::fs::read(path)?)
+    }
+
+    /// Runs the plugin on a line, returning the resulting line, or
+    /// `None` if the plugin dropped it.
+    ///
+    /// Traps, out-of-bounds results and results that are not valid
+    /// UTF-8 are returned as errors.
+    pub fn apply(&mut self, line: &str) -> Result<Option<String>, io::Error> {
+        let len = i32::try_from(line.len()).map_err(wasm_error)?;
+        let ptr = self.alloc.call(&mut self.store, len).map_err(wasm_error)?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, line.as_bytes())
+            .map_err(wasm_error)?;
+        let result = self
+            .transform
+            .call(&mut self.store, (ptr, len))
+            .map_err(wasm_error)?;
+        if result < 0 {
+            return Ok(None);
+        }
+        let out_ptr = (result as u64 >> 32) as usize;
+        let out_len = (result as u64 & 0xffff_ffff) as usize;
+        let mut out = vec![0; out_len];
+        self.memory
+            .read(&self.store, out_ptr, &mut out)
+            .map_err(wasm_error)?;
+        String::from_utf8(out)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmPlugin").finish_non_exhaustive()
+    }
+}
+
+/// [`LineRead`] wrapper that runs the lines of the inner reader
+/// through a [`WasmPlugin`].
+///
+/// The plugin can be replaced at any time with [`Self::set_plugin`],
+/// to reload it after the operator changed it, for instance. Plugin
+/// errors are returned by [`LineRead::read_once`], and the line that
+/// caused it is dropped.
+#[derive(Debug)]
+pub struct WasmFilter<L> {
+    inner: L,
+    plugin: WasmPlugin,
+    pending: Vec<String>,
+    lines: VecDeque<String>,
+    full: FullLatch,
+}
+
+impl<L: LineRead> WasmFilter<L> {
+    /// Creates a new `WasmFilter` that runs the lines of `inner`
+    /// through `plugin`.
+    pub fn new(inner: L, plugin: WasmPlugin) -> Self {
+        Self {
+            inner,
+            plugin,
+            pending: Vec::new(),
+            lines: VecDeque::new(),
+            full: FullLatch::default(),
+        }
+    }
+
+    /// Replaces the plugin, returning the previous one.
+    ///
+    /// Lines already processed are not affected.
+    pub fn set_plugin(&mut self, plugin: WasmPlugin) -> WasmPlugin {
+        mem::replace(&mut self.plugin, plugin)
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &L {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut L {
+        &mut self.inner
+    }
+
+    /// Returns the underlying reader, dropping the lines that were
+    /// not retrieved yet.
+    pub fn into_inner(self) -> L {
+        self.inner
+    }
+
+    /// Runs the lines of the inner reader through the plugin; on
+    /// error, the lines after the failing one are kept for the next
+    /// call.
+    fn pull(&mut self) -> Result<(), io::Error> {
+        self.full
+            .update(self.inner.is_full(), !self.lines.is_empty());
+        self.pending.extend(self.inner.lines_get());
+        let mut pending = mem::take(&mut self.pending).into_iter();
+        while let Some(line) = pending.next() {
+            match self.plugin.apply(&line) {
+                Ok(Some(line)) => self.lines.push_back(line),
+                Ok(None) => {}
+                Err(err) => {
+                    self.pending = pending.collect();
+                    return Err(err);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<L: LineRead> LineRead for WasmFilter<L> {
+    fn eof(&self) -> bool {
+        self.inner.eof()
+    }
+
+    fn read_once(&mut self) -> Result<bool, io::Error> {
+        let result = self.inner.read_once();
+        self.pull()?;
+        result
+    }
+
+    fn lines_get(&mut self) -> Vec<String> {
+        mem::take(&mut self.lines).into()
+    }
+
+    fn pop_line(&mut self) -> Option<String> {
+        self.lines.pop_front()
+    }
+
+    fn has_lines(&mut self) -> bool {
+        !self.lines.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.full
+            .is_full(self.inner.is_full(), !self.lines.is_empty())
+    }
+
+    fn label(&self) -> Option<&str> {
+        self.inner.label()
+    }
+
+    fn is_terminated(&self, line: &str) -> bool {
+        self.inner.is_terminated(line)
+    }
+}
+
+impl<L: AsRawFd> AsRawFd for WasmFilter<L> {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<L: AsFd> AsFd for WasmFilter<L> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl<L: LineRead + AsRawFd> LineReadRawFd for WasmFilter<L> {}
+
+impl<L: LineRead + AsFd> LineReadFd for WasmFilter<L> {}
+
+impl<L: LineRead + AsFd + AsRawFd> LineReadRawAndFd for WasmFilter<L> {}
@@ -0,0 +1,195 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "polling")]
+
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::fd::{AsFd, BorrowedFd};
+use std::os::unix::net::{self, UnixListener, UnixStream};
+use std::time::Duration;
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_acceptor_tcp() -> Result<()> {
+    let mut acceptor = Acceptor::new(TcpListener::bind("127.0.0.1:0")?)?;
+    let addr = acceptor.listener().local_addr()?;
+    let mut client = TcpStream::connect(addr)?;
+    let events = acceptor.wait(Some(Duration::from_secs(5)))?;
+    let key = match events[..] {
+        [AcceptorEvent::Connected(key, peer)] => {
+            assert_eq!(peer, client.local_addr()?);
+            key
+        }
+        _ => panic!("unexpected events {:?}", events),
+    };
+    assert_eq!(acceptor.len(), 1);
+    client.write_all(b"hello\nwor")?;
+    let events = acceptor.wait(Some(Duration::from_secs(5)))?;
+    assert!(
+        matches!(&events[..], [AcceptorEvent::Lines(k, lines)] if *k == key && lines == &["hello\n"])
+    );
+    // Echo back through the connection:
+    acceptor
+        .get_mut(key)
+        .unwrap()
+        .get_mut()
+        .write_all(b"ok\n")?;
+    let mut reply = [0; 3];
+    client.read_exact(&mut reply)?;
+    assert_eq!(&reply, b"ok\n");
+    client.write_all(b"ld")?;
+    drop(client);
+    let mut closed = false;
+    let mut lines = vec![];
+    while !closed {
+        for event in acceptor.wait(Some(Duration::from_secs(5)))? {
+            match event {
+                AcceptorEvent::Lines(_, l) => lines.extend(l),
+                AcceptorEvent::Closed(k) => {
+                    assert_eq!(k, key);
+                    closed = true;
+                }
+                event => panic!("unexpected event {:?}", event),
+            }
+        }
+    }
+    assert_eq!(lines, vec!["world"]);
+    assert!(acceptor.is_empty());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_acceptor_unix() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("lineriver-acceptor-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut acceptor = Acceptor::new(UnixListener::bind(&path)?)?;
+    let mut client = UnixStream::connect(&path)?;
+    let events = acceptor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [AcceptorEvent::Connected(_, _)]));
+    client.write_all(b"status\n")?;
+    drop(client);
+    let mut events = vec![];
+    while !matches!(events.last(), Some(AcceptorEvent::Closed(_))) {
+        events.extend(acceptor.wait(Some(Duration::from_secs(5)))?);
+    }
+    assert!(
+        matches!(&events[..], [AcceptorEvent::Lines(_, lines), AcceptorEvent::Closed(_)] if lines == &["status\n"])
+    );
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+fn connect(acceptor: &mut Acceptor<TcpListener>) -> Result<(TcpStream, usize)> {
+    let client = TcpStream::connect(acceptor.listener().local_addr()?)?;
+    let events = acceptor.wait(Some(Duration::from_secs(5)))?;
+    match events[..] {
+        [AcceptorEvent::Connected(key, _)] => Ok((client, key)),
+        _ => panic!("unexpected events {:?}", events),
+    }
+}
+
+#[test_log::test]
+fn test_acceptor_limit_disconnect() -> Result<()> {
+    let mut acceptor = Acceptor::new(TcpListener::bind("127.0.0.1:0")?)?
+        .with_memory_limit(10, LimitPolicy::Disconnect);
+    let (mut client, key) = connect(&mut acceptor)?;
+    client.write_all(b"short\n")?;
+    let events = acceptor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(&events[..], [AcceptorEvent::Lines(k, _)] if *k == key));
+    assert_eq!(acceptor.buffered_bytes(key), Some(0));
+    client.write_all(b"a very long line without end")?;
+    let events = acceptor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [AcceptorEvent::LimitExceeded(k)] if k == key));
+    assert!(acceptor.is_empty());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_acceptor_limit_pause() -> Result<()> {
+    let mut acceptor =
+        Acceptor::new(TcpListener::bind("127.0.0.1:0")?)?.with_memory_limit(10, LimitPolicy::Pause);
+    let (mut client, key) = connect(&mut acceptor)?;
+    client.write_all(b"a very long line")?;
+    let events = acceptor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [AcceptorEvent::LimitExceeded(k)] if k == key));
+    assert_eq!(acceptor.total_buffered_bytes(), 16);
+    client.write_all(b" that ends\n")?;
+    assert!(acceptor.wait(Some(Duration::from_millis(50)))?.is_empty());
+    acceptor.resume(key)?;
+    let events = acceptor.wait(Some(Duration::from_secs(5)))?;
+    assert!(
+        matches!(&events[..], [AcceptorEvent::Lines(k, lines)] if *k == key && lines == &["a very long line that ends\n"])
+    );
+    Ok(())
+}
+
+#[derive(Debug)]
+struct FailingListener {
+    inner: UnixListener,
+    fail: Cell<bool>,
+}
+
+impl AsFd for FailingListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl Listener for FailingListener {
+    type Stream = UnixStream;
+    type Addr = net::SocketAddr;
+
+    fn set_nonblocking(&self, nonblocking: bool) -> Result<(), io::Error> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+
+    fn accept(&self) -> Result<(UnixStream, net::SocketAddr), io::Error> {
+        if self.fail.replace(false) {
+            return Err(io::Error::from_raw_os_error(24));
+        }
+        self.inner.accept()
+    }
+}
+
+#[test_log::test]
+fn test_acceptor_accept_error() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("lineriver-accepterr-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = FailingListener {
+        inner: UnixListener::bind(&path)?,
+        fail: Cell::new(false),
+    };
+    let mut acceptor = Acceptor::new(listener)?;
+    let mut client = UnixStream::connect(&path)?;
+    let events = acceptor.wait(Some(Duration::from_secs(5)))?;
+    let key = match events[..] {
+        [AcceptorEvent::Connected(key, _)] => key,
+        _ => panic!("unexpected events {:?}", events),
+    };
+    // The accept error comes with the lines of the connection:
+    client.write_all(b"line\n")?;
+    std::thread::sleep(Duration::from_millis(50));
+    acceptor.listener().fail.set(true);
+    let _client2 = UnixStream::connect(&path)?;
+    let mut events = acceptor.wait(Some(Duration::from_secs(5)))?;
+    if events.len() < 2 {
+        events.extend(acceptor.wait(Some(Duration::from_secs(5)))?);
+    }
+    assert!(
+        matches!(&events[..], [AcceptorEvent::AcceptError(_), AcceptorEvent::Lines(k, lines)] if *k == key && lines == &["line\n"]),
+        "unexpected events {:?}",
+        events
+    );
+    // The listener is still polled, and accepts the pending connection:
+    let events = acceptor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [AcceptorEvent::Connected(_, _)]));
+    assert_eq!(acceptor.len(), 2);
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
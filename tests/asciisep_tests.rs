@@ -0,0 +1,57 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_ascii_records() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"a\nb\x1fc\x1ed")
+        .would_block()
+        .data(b"e\x1ef")
+        .eof();
+    let mut reader = AsciiRecordReader::from_nonblocking(script);
+    reader.read_once()?;
+    assert_eq!(reader.records_get(), vec!["a\nb\x1fc"]);
+    reader.read_once()?;
+    assert!(!reader.has_records());
+    reader.read_once()?;
+    reader.read_once()?;
+    assert!(reader.eof());
+    assert_eq!(reader.fields_get(), vec![vec!["de"], vec!["f"]]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_ascii_nested() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"h\x1fv1\x1ex\x1fv2\x1e\x1d")
+        .data(b"y\x1f\x1fz\x1d")
+        .eof();
+    let mut reader =
+        AsciiRecordReader::from_nonblocking(script).with_separator(AsciiSeparator::Group);
+    while reader.read_once()? {}
+    assert_eq!(
+        reader.groups_get(),
+        vec![
+            vec![vec!["h", "v1"], vec!["x", "v2"]],
+            vec![vec!["y", "", "z"]],
+        ]
+    );
+    Ok(())
+}
+
+#[test_log::test]
+fn test_ascii_invalid_utf8() -> Result<()> {
+    let script = ScriptedReader::new().data(b"\xff\x1e").eof();
+    let mut reader = AsciiRecordReader::from_nonblocking(script);
+    assert_eq!(
+        reader.read_once().unwrap_err().kind(),
+        std::io::ErrorKind::InvalidData
+    );
+    Ok(())
+}
@@ -0,0 +1,45 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_batch_count() -> Result<()> {
+    let script = ScriptedReader::new().data(b"1\n2\n3\n4\n5\n").eof();
+    let mut batcher = LineBatcher::new(
+        LineReader::from_nonblocking(script)?,
+        2,
+        Duration::from_secs(3600),
+    );
+    batcher.read_once()?;
+    assert_eq!(batcher.batch_get(), Some(vec!["1\n".into(), "2\n".into()]));
+    assert_eq!(batcher.pop_line().as_deref(), Some("3\n"));
+    assert_eq!(batcher.lines_get(), vec!["4\n"]);
+    assert!(!batcher.has_lines());
+    // EOF completes the last batch:
+    batcher.read_once()?;
+    assert_eq!(batcher.batch_get(), Some(vec!["5\n".into()]));
+    assert_eq!(batcher.batch_get(), None);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_batch_delay() -> Result<()> {
+    let (mut wr, rd) = UnixStream::pair()?;
+    let mut batcher = LineBatcher::new(LineReader::new(rd)?, 10, Duration::from_millis(10));
+    wr.write_all(b"1\n2\n")?;
+    batcher.read_once()?;
+    assert!(!batcher.has_lines());
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(batcher.has_lines());
+    assert_eq!(batcher.lines_get(), vec!["1\n", "2\n"]);
+    Ok(())
+}
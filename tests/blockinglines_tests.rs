@@ -0,0 +1,39 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::Duration;
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_blocking_lines() -> Result<()> {
+    let (mut wr, rd) = UnixStream::pair()?;
+    let writer = thread::spawn(move || -> std::io::Result<()> {
+        wr.write_all(b"1\n2")?;
+        thread::sleep(Duration::from_millis(100));
+        wr.write_all(b"\n3")
+    });
+    let lines = BlockingLines::new(LineReader::new(rd)?).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(lines, vec!["1\n", "2\n", "3"]);
+    writer.join().unwrap()?;
+    Ok(())
+}
+
+#[test_log::test]
+fn test_blocking_lines_error() -> Result<()> {
+    let (mut wr, rd) = UnixStream::pair()?;
+    wr.write_all(b"1\n\xff\n")?;
+    drop(wr);
+    let mut lines = BlockingLines::new(LineReader::new(rd)?);
+    assert_eq!(lines.next().unwrap()?, "1\n");
+    assert!(lines.next().unwrap().is_err());
+    assert!(lines.next().is_none());
+    assert!(lines.get_ref().is_broken());
+    Ok(())
+}
@@ -0,0 +1,144 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::fs;
+use std::io::Write;
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_checkpoint_resume() -> Result<()> {
+    let script = ScriptedReader::new().data(b"1\n2\n3\npar");
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\n", "2\n", "3\n"]);
+    let checkpoint = reader.checkpoint();
+    assert_eq!(checkpoint.offset, 9);
+    assert_eq!(checkpoint.lines, 3);
+    assert_eq!(checkpoint.pending, b"par");
+    let script = ScriptedReader::new().data(b"tial\n4\n");
+    let mut reader = LineReader::from_nonblocking(script)?.with_checkpoint(checkpoint)?;
+    reader.read_available()?;
+    assert_eq!(reader.lines_get(), vec!["partial\n", "4\n"]);
+    assert_eq!(reader.checkpoint().lines, 5);
+    assert_eq!(reader.checkpoint().offset, 16);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_checkpoint_file() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("lineriver-checkpoint-{}", std::process::id()));
+    fs::File::create(&path)?.write_all(b"1\n2\n3\n4")?;
+    let mut reader = LineReader::new(fs::File::open(&path)?)?;
+    while reader.read_once()? {}
+    let lines = reader.lines_get();
+    assert_eq!(lines, vec!["1\n", "2\n", "3\n", "4"]);
+    let checkpoint = reader.checkpoint();
+    fs::OpenOptions::new()
+        .append(true)
+        .open(&path)?
+        .write_all(b"5\n")?;
+    let mut file = fs::File::open(&path)?;
+    checkpoint.seek(&mut file)?;
+    let mut reader = LineReader::new(file)?.with_checkpoint(checkpoint)?;
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get(), vec!["5\n"]);
+    fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test_log::test]
+fn test_commit() -> Result<()> {
+    let script = ScriptedReader::new().data(b"1\n22\n333\npar");
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    let offsets = reader
+        .lines_get_with_meta()
+        .into_iter()
+        .map(|(_, meta)| meta.offset)
+        .collect::<Vec<_>>();
+    assert_eq!(offsets, vec![2, 5, 9]);
+    assert_eq!(reader.committed(), 0);
+    reader.commit(5);
+    reader.commit(2);
+    assert_eq!(reader.committed(), 5);
+    assert_eq!(reader.committed_checkpoint(), Checkpoint::at_offset(5));
+    // Offsets continue from a checkpoint with a partial line:
+    let checkpoint = reader.checkpoint();
+    let script = ScriptedReader::new().data(b"tial\n");
+    let mut reader = LineReader::from_nonblocking(script)?.with_checkpoint(checkpoint)?;
+    assert_eq!(reader.committed(), 9);
+    reader.read_once()?;
+    let lines = reader.lines_get_with_meta();
+    assert_eq!(lines[0].0, "partial\n");
+    assert_eq!(lines[0].1.offset, 17);
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test_log::test]
+fn test_checkpoint_serde() -> Result<()> {
+    let script = ScriptedReader::new().data(b"1\n2");
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    reader.lines_get();
+    let checkpoint = reader.checkpoint();
+    let json = serde_json::to_string(&checkpoint)?;
+    assert_eq!(json, r#"{"offset":3,"lines":1,"pending":[50]}"#);
+    let restored: Checkpoint = serde_json::from_str(&json)?;
+    assert_eq!(restored, checkpoint);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_seek_to() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("lineriver-seek-{}", std::process::id()));
+    fs::File::create(&path)?.write_all(b"1\n22\n333\npar")?;
+    let mut reader = LineReader::new(fs::File::open(&path)?)?;
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\n", "22\n", "333\n"]);
+    assert_eq!(reader.current_offset(), 9);
+    reader.seek_to(2)?;
+    assert_eq!(reader.current_offset(), 2);
+    assert!(!reader.has_lines());
+    while reader.read_once()? {}
+    let lines = reader.lines_get_with_meta();
+    assert_eq!(lines[0].0, "22\n");
+    assert_eq!(lines[0].1.offset, 5);
+    assert_eq!(lines[2].0, "par");
+    assert_eq!(reader.current_offset(), 12);
+    fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test_log::test]
+fn test_checkpoint_invalid() -> Result<()> {
+    let mut checkpoint = Checkpoint::at_offset(2);
+    checkpoint.pending = b"abc".to_vec();
+    let err = LineReader::from_nonblocking(ScriptedReader::new())?
+        .with_checkpoint(checkpoint)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_seek_to_resets_splitter() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("lineriver-seekreset-{}", std::process::id()));
+    fs::File::create(&path)?.write_all(b"1\r\nabcdefgh")?;
+    let mut reader =
+        LineReader::new(fs::File::open(&path)?)?.with_max_line_len(4, LongLinePolicy::Truncate);
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\r\n", "abcd"]);
+    assert_eq!(reader.eol_style(), Some(EolStyle::Crlf));
+    reader.seek_to(0)?;
+    assert_eq!(reader.eol_style(), None);
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\r\n", "abcd"]);
+    fs::remove_file(&path)?;
+    Ok(())
+}
@@ -0,0 +1,44 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "codec")]
+
+use color_eyre::Result;
+use tokio_util::codec::{LengthDelimitedCodec, LinesCodec};
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_codec_lines() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1\n2")
+        .would_block()
+        .data(b"2\n3");
+    let mut reader = CodecReader::from_nonblocking(script, LinesCodec::new());
+    reader.read_once()?;
+    assert_eq!(reader.frames_get(), vec!["1"]);
+    reader.read_once()?;
+    assert!(!reader.has_frames());
+    while reader.read_once()? {}
+    assert!(reader.eof());
+    assert_eq!(reader.frames_get(), vec!["22", "3"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_codec_length_delimited() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"\x00\x00\x00\x03ab")
+        .data(b"c\x00\x00\x00\x01\n");
+    let mut reader = CodecReader::from_nonblocking(script, LengthDelimitedCodec::new());
+    while !reader.eof() {
+        reader.read_available()?;
+    }
+    let frames = reader.frames_get();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(&frames[0][..], b"abc");
+    assert_eq!(&frames[1][..], b"\n");
+    Ok(())
+}
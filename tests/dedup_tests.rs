@@ -0,0 +1,43 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_dedup() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"a\na\na\nb\n")
+        .data(b"b\nc\nc\n")
+        .eof();
+    let mut dedup = LineDedup::new(LineReader::from_nonblocking(script)?);
+    dedup.read_once()?;
+    assert_eq!(
+        dedup.lines_get(),
+        vec!["a\n", "last message repeated 2 times\n", "b\n"]
+    );
+    dedup.read_once()?;
+    assert_eq!(
+        dedup.lines_get(),
+        vec!["last message repeated 1 times\n", "c\n"]
+    );
+    dedup.read_once()?;
+    assert!(dedup.eof());
+    assert_eq!(dedup.lines_get(), vec!["last message repeated 1 times\n"]);
+    assert_eq!(dedup.suppressed(), 4);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_dedup_summary() -> Result<()> {
+    let script = ScriptedReader::new().data(b"x\nx\nx").eof();
+    let mut dedup = LineDedup::new(LineReader::from_nonblocking(script)?)
+        .with_summary(|line, n| format!("{} x{}\n", line.trim_end(), n + 1));
+    dedup.read_available()?;
+    dedup.read_available()?;
+    assert_eq!(dedup.lines_get(), vec!["x\n", "x x2\n", "x"]);
+    Ok(())
+}
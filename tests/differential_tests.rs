@@ -0,0 +1,87 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+//! Differential tests: [`LineReader`] must return the same lines as
+//! [`BufRead::read_line`] for the same input.
+
+use std::io::{BufRead, BufReader};
+
+use proptest::prelude::*;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+/// Lines as returned by std's blocking `BufReader::read_line`.
+fn bufreader_lines(input: &[u8]) -> Vec<String> {
+    let mut bufreader = BufReader::new(input);
+    let mut lines = vec![];
+    loop {
+        let mut line = String::new();
+        if bufreader.read_line(&mut line).unwrap() == 0 {
+            return lines;
+        }
+        lines.push(line);
+    }
+}
+
+/// Lines as returned by `LineReader`, reading `input` in chunks of
+/// `chunk` bytes with a `WouldBlock` between them.
+fn linereader_lines(input: &[u8], chunk: usize) -> Vec<String> {
+    let script = input
+        .chunks(chunk)
+        .fold(ScriptedReader::new(), |s, c| s.data(c).would_block());
+    let mut reader = LineReader::from_nonblocking(script).unwrap();
+    let mut lines = vec![];
+    while !reader.eof() {
+        reader.read_once().unwrap();
+        lines.extend(reader.lines_get());
+    }
+    lines
+}
+
+fn assert_same(input: &[u8]) {
+    let expected = bufreader_lines(input);
+    for chunk in [1, 2, 3, 7, 4096] {
+        assert_eq!(
+            linereader_lines(input, chunk),
+            expected,
+            "input {:?}, chunk {}",
+            input,
+            chunk
+        );
+    }
+}
+
+#[test_log::test]
+fn test_differential_corpus() {
+    for input in [
+        &b""[..],
+        b"\n",
+        b"\n\n",
+        b"a",
+        b"a\n",
+        b"a\nb",
+        b"a\r\nb\r\n",
+        b"\ra\r",
+        b"  \t\n\x00\n",
+        "\u{1f496}\n\u{1f496}".as_bytes(),
+    ] {
+        assert_same(input);
+    }
+}
+
+#[test_log::test]
+fn test_differential_long_line() {
+    let mut input = vec![b'x'; 3 * 8192 + 1];
+    input.push(b'\n');
+    input.extend_from_slice(b"tail");
+    assert_same(&input);
+}
+
+proptest! {
+    #[test]
+    fn prop_differential(input in "[ -~\\n\\r\\t\\u{e9}\\u{1f496}]{0,200}") {
+        assert_same(input.as_bytes());
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::TrySendError;
+use std::sync::{Arc, Mutex};
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_dispatch_key_affinity() -> Result<()> {
+    let seen = Arc::new(Mutex::new(BTreeMap::<String, Vec<String>>::new()));
+    let handled = seen.clone();
+    let mut dispatcher = LineDispatcher::new(3, 4, move |line| {
+        let (key, value) = line.trim_end().split_once(' ').unwrap();
+        let (key, value) = (key.to_string(), value.to_string());
+        handled.lock().unwrap().entry(key).or_default().push(value);
+    })
+    .with_key(|line| line.split(' ').next().unwrap_or_default());
+    assert_eq!(dispatcher.workers(), 3);
+    let mut input = Vec::new();
+    for i in 0..50 {
+        input.extend_from_slice(format!("k{} {}\n", i % 5, i).as_bytes());
+    }
+    let mut reader = LineReader::from_nonblocking(ScriptedReader::new().data(&input))?;
+    while reader.read_once()? {
+        for line in reader.lines_get() {
+            dispatcher.dispatch(line).unwrap();
+        }
+    }
+    dispatcher.join().unwrap();
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 5);
+    for (key, values) in seen.iter() {
+        let key: usize = key[1..].parse()?;
+        let expected = (0..50).filter(|i| i % 5 == key).map(|i| i.to_string());
+        assert_eq!(*values, expected.collect::<Vec<_>>());
+    }
+    Ok(())
+}
+
+#[test_log::test]
+fn test_dispatch_full() {
+    let gate = Arc::new(Mutex::new(()));
+    let handled = Arc::new(AtomicUsize::new(0));
+    let (worker_gate, counter) = (gate.clone(), handled.clone());
+    let mut dispatcher = LineDispatcher::new(2, 1, move |_| {
+        let _open = worker_gate.lock().unwrap();
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    let closed = gate.lock().unwrap();
+    let mut accepted = 0;
+    let line = loop {
+        match dispatcher.try_dispatch(accepted.to_string()) {
+            Ok(()) => accepted += 1,
+            Err(TrySendError::Full(line)) => break line,
+            Err(err) => panic!("{}", err),
+        }
+    };
+    assert_eq!(line, accepted.to_string());
+    assert!((2..=4).contains(&accepted));
+    drop(closed);
+    dispatcher.join().unwrap();
+    assert_eq!(handled.load(Ordering::SeqCst), accepted);
+}
+
+#[test_log::test]
+fn test_dispatch_panic() {
+    let mut dispatcher = LineDispatcher::new(1, 1, |line| {
+        if line == "boom" {
+            panic!("boom");
+        }
+    });
+    dispatcher.dispatch("boom".to_string()).unwrap();
+    assert!(dispatcher.join().is_err());
+}
@@ -0,0 +1,37 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "duct")]
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+fn read_all(reader: &mut LineReader<DuctReader>) -> std::io::Result<Vec<String>> {
+    let mut lines = vec![];
+    while !reader.eof() {
+        reader.read_available()?;
+        lines.extend(reader.lines_get());
+    }
+    Ok(lines)
+}
+
+#[test_log::test]
+fn test_duct_pipeline() -> Result<()> {
+    let expression = duct::cmd!("printf", "1\\n2\\n3").pipe(duct::cmd!("tac"));
+    let mut reader = LineReader::from_duct(&expression)?;
+    assert_eq!(read_all(&mut reader)?, vec!["32\n", "1\n"]);
+    assert!(reader.get_ref().handle().try_wait()?.is_some());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_duct_exit_status() -> Result<()> {
+    let mut reader = LineReader::from_duct(&duct::cmd!("sh", "-c", "echo 1; exit 3"))?;
+    assert!(read_all(&mut reader).is_err());
+    let expression = duct::cmd!("sh", "-c", "echo 1; exit 3").unchecked();
+    let mut reader = LineReader::from_duct(&expression)?;
+    assert_eq!(read_all(&mut reader)?, vec!["1\n"]);
+    Ok(())
+}
@@ -0,0 +1,55 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+fn detect(chunks: &[&[u8]]) -> EolDetector {
+    let mut detector = EolDetector::default();
+    for chunk in chunks {
+        detector.feed(chunk);
+    }
+    detector
+}
+
+#[test_log::test]
+fn test_eol_styles() {
+    assert_eq!(detect(&[b"no ending"]).style(), None);
+    assert_eq!(detect(&[b"1\n2\n"]).style(), Some(EolStyle::Lf));
+    assert_eq!(detect(&[b"1\r\n2\r\n"]).style(), Some(EolStyle::Crlf));
+    assert_eq!(detect(&[b"1\r2\r3"]).style(), Some(EolStyle::Cr));
+    assert_eq!(detect(&[b"1\r\n2\n"]).style(), Some(EolStyle::Mixed));
+}
+
+#[test_log::test]
+fn test_eol_split_chunks() {
+    assert_eq!(detect(&[b"1\r", b"\n2\r", b"\n"]).counts(), (0, 2, 0));
+    assert_eq!(detect(&[b"1\r", b"2\r", b"\r", b"3"]).counts(), (0, 0, 3));
+    // A trailing \r is only counted once the next byte is known:
+    assert_eq!(detect(&[b"1\r"]).style(), None);
+}
+
+#[test_log::test]
+fn test_eol_sample() {
+    let detector = detect(&[&b"x\n".repeat(200), b"y\r\n"]);
+    assert!(detector.is_done());
+    assert_eq!(detector.style(), Some(EolStyle::Lf));
+}
+
+#[test_log::test]
+fn test_eol_linereader() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1\r")
+        .would_block()
+        .data(b"\n2\r\n")
+        .eof();
+    let mut reader = LineReader::from_nonblocking(script)?;
+    assert_eq!(reader.eol_style(), None);
+    while reader.read_once()? {}
+    assert_eq!(reader.eol_style(), Some(EolStyle::Crlf));
+    assert_eq!(reader.lines_get(), vec!["1\r\n", "2\r\n"]);
+    Ok(())
+}
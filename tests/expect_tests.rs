@@ -0,0 +1,105 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::time::{Duration, Instant};
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_expect() -> Result<()> {
+    let (stream, mut device) = UnixStream::pair()?;
+    let mut expect = Expect::new(LineStream::new(stream.try_clone()?, stream)?);
+    device.write_all(b"banner\nlogin: ok\n$ ")?;
+    let found = expect.wait_for("login:", Duration::from_secs(5))?;
+    assert_eq!(found.before, vec!["banner\n"]);
+    assert_eq!(found.matched, "login: ok\n");
+    expect.send_line("show version", Duration::from_secs(5))?;
+    let mut sent = [0; 13];
+    device.read_exact(&mut sent)?;
+    assert_eq!(&sent, b"show version\n");
+    let start = Instant::now();
+    let err = expect
+        .wait_for(
+            |l: &str| l.starts_with("version"),
+            Duration::from_millis(100),
+        )
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    device.write_all(b"\nfoo\nversion 1.2\nbar\n")?;
+    let found = expect.wait_for(|l: &str| l.starts_with("version"), Duration::from_secs(5))?;
+    assert_eq!(found.before, vec!["$ \n", "foo\n"]);
+    assert_eq!(found.matched, "version 1.2\n");
+    drop(device);
+    let err = expect
+        .wait_for("never", Duration::from_secs(5))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(expect.lines_get(), vec!["bar\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_expect_prompt() -> Result<()> {
+    let (stream, mut device) = UnixStream::pair()?;
+    let mut expect = Expect::new(LineStream::new(stream.try_clone()?, stream)?)
+        .with_prompt(Prompt::Suffix("router# ".into()));
+    assert!(!expect.at_prompt());
+    device.write_all(b"welcome\nrouter")?;
+    let err = expect
+        .wait_for_prompt(Duration::from_millis(50))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+    device.write_all(b"# ")?;
+    let lines = expect.wait_for_prompt(Duration::from_secs(5))?;
+    assert_eq!(lines, vec!["welcome\n"]);
+    assert!(expect.at_prompt());
+    assert_eq!(expect.get_ref().reader().partial_line(), b"router# ");
+    Ok(())
+}
+
+#[cfg(feature = "regex")]
+#[test_log::test]
+fn test_expect_prompt_regex() -> Result<()> {
+    let (stream, mut device) = UnixStream::pair()?;
+    let mut expect = Expect::new(LineStream::new(stream.try_clone()?, stream)?)
+        .with_prompt(Prompt::Regex(regex::Regex::new(r"^\(Pdb\) $")?));
+    device.write_all(b"> main.py(3)\n(Pdb) ")?;
+    let lines = expect.wait_for_prompt(Duration::from_secs(5))?;
+    assert_eq!(lines, vec!["> main.py(3)\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_expect_echo_suppression() -> Result<()> {
+    let (stream, mut device) = UnixStream::pair()?;
+    let mut expect = Expect::new(LineStream::new(stream.try_clone()?, stream)?)
+        .with_prompt(Prompt::Suffix("> ".into()))
+        .with_echo_suppression(true);
+    expect.send_line("uptime", Duration::from_secs(5))?;
+    expect.send_line("uptime", Duration::from_secs(5))?;
+    device.write_all(b"uptime\r\nup 3 days\r\n> uptime\r\nup 3 days\r\nuptime\r\n> ")?;
+    let lines = expect.wait_for_prompt(Duration::from_secs(5))?;
+    assert_eq!(lines, vec!["up 3 days\r\n", "up 3 days\r\n", "uptime\r\n"]);
+    let mut sent = vec![0; 14];
+    device.read_exact(&mut sent)?;
+    Ok(())
+}
+
+#[test_log::test]
+fn test_expect_max_timeout() -> Result<()> {
+    let (stream, mut device) = UnixStream::pair()?;
+    let mut expect = Expect::new(LineStream::new(stream.try_clone()?, stream)?)
+        .with_prompt(Prompt::Suffix("$ ".into()));
+    expect.send_line("ls", Duration::MAX)?;
+    device.write_all(b"file\n$ ")?;
+    assert_eq!(expect.wait_for_prompt(Duration::MAX)?, vec!["file\n"]);
+    device.write_all(b"done\n")?;
+    assert_eq!(expect.wait_for("done", Duration::MAX)?.matched, "$ done\n");
+    Ok(())
+}
@@ -0,0 +1,135 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_ext_map_filter() -> Result<()> {
+    let (mut tx, rx) = UnixStream::pair()?;
+    let fd = rx.as_raw_fd();
+    let mut reader = LineReader::new(rx)?
+        .filter_lines(|line| line.len() > 2)
+        .map_lines(|line| format!("> {}", line));
+    tx.write_all(b"1\n22\n333\n")?;
+    reader.read_available()?;
+    assert!(reader.has_lines());
+    assert_eq!(reader.lines_get(), vec!["> 22\n", "> 333\n"]);
+    assert_eq!(reader.as_raw_fd(), fd);
+    drop(tx);
+    while reader.read_once()? {}
+    assert!(reader.eof());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_ext_merge_tag() -> Result<()> {
+    let first = MockLineRead::new().line("a\n").pending().line("b\n");
+    let second = MockLineRead::new().line("1\n").tag("second");
+    let mut merged = first.merge(second);
+    assert_eq!(merged.label(), Some("second"));
+    assert!(merged.read_once()?);
+    assert_eq!(merged.lines_get(), vec!["a\n", "1\n"]);
+    while merged.read_once()? {}
+    assert!(merged.eof());
+    assert_eq!(merged.lines_get(), vec!["b\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_ext_take_lines() -> Result<()> {
+    let script = ScriptedReader::new().data(b"1\n2\n").data(b"3\n4\n");
+    let mut reader = LineReader::from_nonblocking(script)?.take_lines(3);
+    assert!(reader.read_once()?);
+    assert!(!reader.eof());
+    assert!(!reader.read_once()?);
+    assert!(reader.eof());
+    assert_eq!(reader.lines_get(), vec!["1\n", "2\n", "3\n"]);
+    assert!(!reader.read_once()?);
+    assert!(!reader.has_lines());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_ext_chain() -> Result<()> {
+    let first = ScriptedReader::new().data(b"1\n2").would_block().data(b"2");
+    let second = ScriptedReader::new().data(b"2\n3\n");
+    let mut reader = LineReader::from_nonblocking(first)?
+        .tag("first")
+        .chain(LineReader::from_nonblocking(second)?.tag("second"));
+    assert_eq!(reader.label(), Some("first"));
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\n"]);
+    reader.read_once()?;
+    reader.read_once()?;
+    reader.read_once()?;
+    assert!(!reader.has_lines());
+    assert_eq!(reader.label(), Some("second"));
+    while reader.read_once()? {}
+    assert!(reader.eof());
+    assert_eq!(reader.lines_get(), vec!["222\n", "3\n"]);
+    // The partial line of the first reader is kept if the second one
+    // is empty:
+    let first = MockLineRead::new().lines(&["a\n", "b"]);
+    let mut reader = first.chain(MockLineRead::new());
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get(), vec!["a\n", "b"]);
+    // Complete lines end with the delimiter of the first reader:
+    let first = LineReader::from_nonblocking(&b"a\0b\n"[..])?.with_delimiter(b'\0');
+    let second = LineReader::from_nonblocking(&b"c\0"[..])?.with_delimiter(b'\0');
+    let mut reader = first.chain(second);
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get(), vec!["a\0", "b\nc\0"]);
+    assert!(reader.is_terminated("c\0"));
+    assert!(!reader.is_terminated("c\n"));
+    // Also through map_lines:
+    let first = LineReader::from_nonblocking(&b"a\0"[..])?
+        .with_delimiter(b'\0')
+        .map_lines(|line| line.to_uppercase());
+    let second = LineReader::from_nonblocking(&b"b\0"[..])?.with_delimiter(b'\0');
+    let mut reader = first.chain(second);
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get(), vec!["A\0", "b\0"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_ext_merge_time_order() -> Result<()> {
+    let first = MockLineRead::new()
+        .lines(&["00:00:01 a\n", "00:00:04 b\n", "  more b\n"])
+        .pending()
+        .line("00:00:09 c\n");
+    let second = MockLineRead::new()
+        .lines(&["x\n", "00:00:02 y\n"])
+        .pending()
+        .line("00:00:03 z\n");
+    let format = TimestampFormat::Strftime("%T".into());
+    let mut merged = first
+        .merge(second)
+        .with_time_order(format, Duration::from_secs(2));
+    assert!(merged.read_once()?);
+    assert_eq!(
+        merged.lines_get(),
+        vec!["x\n", "00:00:01 a\n", "00:00:02 y\n"]
+    );
+    assert!(merged.read_once()?);
+    assert!(!merged.has_lines());
+    assert!(merged.read_once()?);
+    assert_eq!(
+        merged.lines_get(),
+        vec!["00:00:03 z\n", "00:00:04 b\n", "  more b\n"]
+    );
+    assert!(!merged.eof());
+    while merged.read_once()? {}
+    assert!(merged.eof());
+    assert_eq!(merged.lines_get(), vec!["00:00:09 c\n"]);
+    Ok(())
+}
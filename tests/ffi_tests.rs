@@ -0,0 +1,56 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "ffi")]
+
+use std::ffi::CStr;
+use std::io::Write;
+use std::os::fd::IntoRawFd;
+use std::os::raw::c_char;
+use std::os::unix::net::UnixStream;
+use std::ptr;
+
+use color_eyre::Result;
+
+use ::lineriver::ffi::*;
+
+fn next_line(reader: *mut LineriverReader) -> Option<String> {
+    let mut line: *const c_char = ptr::null();
+    let mut len = 0;
+    if unsafe { lineriver_next_line(reader, &mut line, &mut len) } == 0 {
+        return None;
+    }
+    let cstr = unsafe { CStr::from_ptr(line) };
+    assert_eq!(cstr.to_bytes().len(), len);
+    Some(cstr.to_str().ok()?.to_string())
+}
+
+#[test_log::test]
+fn test_ffi() -> Result<()> {
+    let (mut writer, reader) = UnixStream::pair()?;
+    let reader = unsafe { lineriver_create(reader.into_raw_fd()) };
+    assert!(!reader.is_null());
+    writer.write_all(b"1\n2\n3")?;
+    assert_eq!(unsafe { lineriver_read_available(reader) }, 1);
+    assert_eq!(next_line(reader).as_deref(), Some("1\n"));
+    assert_eq!(next_line(reader).as_deref(), Some("2\n"));
+    assert_eq!(next_line(reader), None);
+    drop(writer);
+    assert_eq!(unsafe { lineriver_read_available(reader) }, 0);
+    assert_eq!(unsafe { lineriver_eof(reader) }, 1);
+    assert_eq!(next_line(reader).as_deref(), Some("3"));
+    unsafe { lineriver_destroy(reader) };
+    Ok(())
+}
+
+#[test_log::test]
+fn test_ffi_create_error() -> Result<()> {
+    let reader = unsafe { lineriver_create(-1) };
+    assert!(reader.is_null());
+    assert_eq!(
+        std::io::Error::last_os_error().raw_os_error(),
+        Some(libc::EBADF)
+    );
+    Ok(())
+}
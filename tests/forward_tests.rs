@@ -0,0 +1,94 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::Read;
+use std::net::{Shutdown, TcpListener, UdpSocket};
+use std::time::{Duration, SystemTime};
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+fn syslog_config() -> SyslogConfig {
+    SyslogConfig {
+        facility: 16,
+        severity: 3,
+        hostname: "host".into(),
+        app_name: "app".into(),
+        procid: "42".into(),
+    }
+}
+
+#[test_log::test]
+fn test_forward_tcp_plain() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let mut forwarder = LineForwarder::tcp(listener.local_addr()?)?;
+    let (mut server, _) = listener.accept()?;
+    let mut reader = LineReader::from_nonblocking(ScriptedReader::new().data(b"1\n2"))?;
+    reader.read_once()?;
+    reader.read_once()?;
+    assert_eq!(forwarder.forward(&mut reader)?, 2);
+    assert!(forwarder.flush()?);
+    assert_eq!(forwarder.pending_bytes(), 0);
+    drop(forwarder);
+    let mut received = String::new();
+    server.read_to_string(&mut received)?;
+    assert_eq!(received, "1\n2\n");
+    server.shutdown(Shutdown::Both)?;
+    Ok(())
+}
+
+#[test_log::test]
+fn test_forward_tcp_syslog() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let mut forwarder = LineForwarder::tcp(listener.local_addr()?)?.with_syslog(syslog_config());
+    let (mut server, _) = listener.accept()?;
+    forwarder.send_line("hello\n")?;
+    drop(forwarder);
+    let mut received = String::new();
+    server.read_to_string(&mut received)?;
+    let (len, message) = received.split_once(' ').unwrap();
+    assert_eq!(len.parse::<usize>()?, message.len());
+    assert!(message.starts_with("<131>1 "));
+    assert!(message.ends_with(" host app 42 - - hello"));
+    let timestamp = TimestampFormat::Rfc3339
+        .parse_prefix(&message[7..])
+        .unwrap();
+    let age = SystemTime::now().duration_since(timestamp)?;
+    assert!(age < Duration::from_secs(60));
+    Ok(())
+}
+
+#[test_log::test]
+fn test_forward_udp_syslog() -> Result<()> {
+    let server = UdpSocket::bind("127.0.0.1:0")?;
+    let mut forwarder = LineForwarder::udp(server.local_addr()?)?.with_syslog(syslog_config());
+    forwarder.send_line("a")?;
+    forwarder.send_line("b\n")?;
+    let mut buf = [0_u8; 1024];
+    for expected in ["a", "b"] {
+        let len = server.recv(&mut buf)?;
+        let message = std::str::from_utf8(&buf[..len])?;
+        assert!(message.starts_with("<131>1 "));
+        assert!(message.ends_with(&format!(" - - {}", expected)));
+    }
+    Ok(())
+}
+
+#[test_log::test]
+fn test_forward_error_keeps_lines() -> Result<()> {
+    let server = UdpSocket::bind("127.0.0.1:0")?;
+    let mut forwarder = LineForwarder::udp(server.local_addr()?)?;
+    drop(server);
+    // The first datagram gets the port unreachable reply that makes
+    // the next send fail:
+    forwarder.send_line("probe")?;
+    std::thread::sleep(Duration::from_millis(50));
+    let mut reader = LineReader::from_nonblocking(ScriptedReader::new().data(b"1\n2\n"))?;
+    reader.read_once()?;
+    assert!(forwarder.forward(&mut reader).is_err());
+    assert_eq!(forwarder.pending_bytes(), 4);
+    Ok(())
+}
@@ -0,0 +1,48 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "regex")]
+
+use color_eyre::Result;
+use regex::Regex;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+fn grep_for(input: &[u8], regexes: &[&str]) -> Result<LineGrep<LineReader<ScriptedReader>>> {
+    let linereader = LineReader::from_nonblocking(ScriptedReader::new().data(input))?;
+    let regexes = regexes
+        .iter()
+        .map(|r| Regex::new(r))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(LineGrep::with_regexes(linereader, regexes))
+}
+
+#[test_log::test]
+fn test_grep_filter() -> Result<()> {
+    let mut grep = grep_for(b"a1\nb2\na3\nc4", &["^a"])?;
+    grep.read_once()?;
+    assert!(grep.has_lines());
+    assert_eq!(grep.lines_get(), vec!["a1\n", "a3\n"]);
+    grep.read_once()?;
+    assert!(grep.eof());
+    assert!(!grep.has_lines());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_grep_captures() -> Result<()> {
+    let mut grep = grep_for(b"x=1\nnope\ny=2\n", &[r"^(x)=(\d)", r"^(y)=(\d)"])?;
+    grep.read_available()?;
+    let matches = grep.matches_get();
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].regex, 0);
+    assert_eq!(matches[1].regex, 1);
+    assert_eq!(
+        matches[1].groups,
+        vec![Some("y=2".into()), Some("y".into()), Some("2".into())]
+    );
+    assert_eq!(matches[1].line, "y=2\n");
+    Ok(())
+}
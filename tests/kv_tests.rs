@@ -0,0 +1,41 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::borrow::Cow;
+
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_kv_pairs() {
+    let line = "ts=2024-01-01T00:00:00Z level=warn msg=\"disk \\\"sda\\\" full\" empty= bare\n";
+    let pairs = kv_pairs(line).collect::<Vec<_>>();
+    assert_eq!(
+        pairs,
+        vec![
+            ("ts", Cow::from("2024-01-01T00:00:00Z")),
+            ("level", Cow::from("warn")),
+            ("msg", Cow::from("disk \"sda\" full")),
+            ("empty", Cow::from("")),
+            ("bare", Cow::from("")),
+        ]
+    );
+    assert!(matches!(pairs[0].1, Cow::Borrowed(_)));
+    assert!(matches!(pairs[2].1, Cow::Owned(_)));
+}
+
+#[test_log::test]
+fn test_kv_pairs_quotes() {
+    let pairs = kv_pairs("a=\"x y\"b=1 c=\"unterminated").collect::<Vec<_>>();
+    assert_eq!(
+        pairs,
+        vec![
+            ("a", Cow::from("x y")),
+            ("b", Cow::from("1")),
+            ("c", Cow::from("unterminated")),
+        ]
+    );
+    assert!(matches!(pairs[0].1, Cow::Borrowed(_)));
+    assert_eq!(kv_pairs("").count(), 0);
+    assert_eq!(kv_pairs(" \n").count(), 0);
+}
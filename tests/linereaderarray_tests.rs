@@ -0,0 +1,65 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_linereaderarray() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1\n22")
+        .would_block()
+        .data(b"2\n4444")
+        .eof();
+    let mut reader = LineReaderArray::<_, 8>::from_nonblocking(script);
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\n"]);
+    assert_eq!(reader.partial_line(), b"22");
+    reader.read_once()?;
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["222\n"]);
+    reader.read_once()?;
+    assert!(reader.eof());
+    assert_eq!(reader.lines_get(), vec!["4444"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_linereaderarray_too_long() -> Result<()> {
+    let script = ScriptedReader::new().data(b"12345").data(b"\n").eof();
+    let mut reader = LineReaderArray::<_, 4>::from_nonblocking(script);
+    reader.read_once()?;
+    assert_eq!(
+        reader.read_once().unwrap_err().kind(),
+        std::io::ErrorKind::InvalidData
+    );
+    Ok(())
+}
+
+#[test_log::test]
+fn test_linereaderarray_socket() -> Result<()> {
+    let (mut wr, rd) = UnixStream::pair()?;
+    let mut reader = LineReaderArray::<_, 16>::new(rd)?;
+    wr.write_all(b"1\n2\n")?;
+    drop(wr);
+    assert_eq!(reader.collect_all(None)?, vec!["1\n", "2\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_linereaderarray_chain() -> Result<()> {
+    let first = LineReaderArray::<_, 8>::from_nonblocking(&b"a\nb"[..]);
+    let second = LineReader::from_nonblocking(&b"c\0d\0"[..])?.with_delimiter(b'\0');
+    assert!(first.is_terminated("a\n"));
+    assert!(!first.is_terminated("b"));
+    let mut reader = first.chain(second);
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get(), vec!["a\n", "bc\0", "d\0"]);
+    Ok(())
+}
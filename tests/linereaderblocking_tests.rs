@@ -0,0 +1,61 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::thread;
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+fn is_nonblocking(fd: std::os::fd::RawFd) -> bool {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    flags & libc::O_NONBLOCK != 0
+}
+
+#[test_log::test]
+fn test_linereaderblocking() -> Result<()> {
+    let (mut tx, rx) = UnixStream::pair()?;
+    let mut reader = LineReaderBlocking::new(rx)?;
+    assert!(!is_nonblocking(reader.get_ref().as_raw_fd()));
+    let producer = thread::spawn(move || -> std::io::Result<()> {
+        tx.write_all(b"a\nb")?;
+        tx.flush()?;
+        thread::sleep(std::time::Duration::from_millis(50));
+        tx.write_all(b"\nc\n")?;
+        Ok(())
+    });
+    let mut lines = vec![];
+    while !reader.eof() {
+        reader.read_available()?;
+        lines.extend(reader.lines_get());
+    }
+    producer.join().unwrap()?;
+    assert_eq!(lines, vec!["a\n", "b\n", "c\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_linereaderblocking_mixed() -> Result<()> {
+    let (mut tx, rx) = UnixStream::pair()?;
+    tx.write_all(b"socket\n")?;
+    drop(tx);
+    let mut readers: Vec<Box<dyn LineRead + Send>> = vec![
+        LineReader::boxed(rx)?,
+        LineReaderBlocking::boxed(&b"memory\n"[..])?,
+        Box::new(LineReaderBlocking::from_line_reader(
+            LineReader::from_nonblocking(&b"tagged\n"[..])?.with_label("blocking"),
+        )),
+    ];
+    let mut lines = vec![];
+    for reader in &mut readers {
+        while reader.read_once()? {}
+        lines.extend(reader.lines_get());
+    }
+    assert_eq!(lines, vec!["socket\n", "memory\n", "tagged\n"]);
+    assert_eq!(readers[2].label(), Some("blocking"));
+    Ok(())
+}
@@ -2,6 +2,8 @@
 // This file is subject to the terms and conditions defined in
 // file 'LICENSE', which is part of this source code package.
 
+#![allow(clippy::redundant_pattern_matching, clippy::useless_vec)]
+
 use std::io::Write;
 use std::net::Shutdown;
 use std::os::unix::net::UnixStream;
@@ -10,6 +12,7 @@ use std::process::Stdio;
 
 use color_eyre::{eyre::eyre, Result};
 
+use ::lineriver::testing::*;
 use ::lineriver::*;
 
 const SPARKLE_HEART: [u8; 4] = [240, 159, 146, 150];
@@ -189,3 +192,1052 @@ fn test_trat_readerfd() -> Result<()> {
     let _fds2 = linereaders.iter().map(|s| s.as_fd()).collect::<Vec<_>>();
     Ok(())
 }
+
+#[test_log::test]
+fn test_eintr_noretry() -> Result<()> {
+    let script = ScriptedReader::new().interrupted().data(b"1\n");
+    let mut reader = LineReader::from_nonblocking(script)?;
+    assert!(reader.read_once()?);
+    assert!(!reader.has_lines());
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_eintr_retries() -> Result<()> {
+    let script = ScriptedReader::new()
+        .interrupted()
+        .interrupted()
+        .interrupted()
+        .data(b"1\n");
+    let mut reader = LineReader::from_nonblocking(script)?.with_eintr_retries(3);
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_sticky_error() -> Result<()> {
+    let mut invalid = Vec::from(INVALID_UTF8);
+    invalid.push(b'\n');
+    let mut reader = reader_for(&invalid)?;
+    assert!(!reader.is_broken());
+    assert!(reader.read_once().is_err());
+    assert!(reader.is_broken());
+    assert!(reader.read_once().is_err());
+    assert!(reader.read_available().is_err());
+    let err = reader
+        .take_error()
+        .ok_or_else(|| eyre!("no error recorded"))?;
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(reader.take_error().is_none());
+    assert!(reader.is_broken());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_cancel() -> Result<()> {
+    let (_wr, rd) = std::os::unix::net::UnixStream::pair()?;
+    let token = CancelToken::new();
+    let mut reader = LineReader::new(rd)?.with_cancel_token(token.clone());
+    assert!(!reader.is_cancelled());
+    let canceller = token.clone();
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        canceller.cancel();
+    });
+    // Would spin forever without the cancellation:
+    reader.read_available()?;
+    handle.join().expect("canceller thread panicked");
+    assert!(reader.is_cancelled());
+    assert!(!reader.has_lines());
+    token.reset();
+    assert!(!reader.is_cancelled());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_pause_resume() -> Result<()> {
+    let mut reader = reader_for(b"1\n2\n")?;
+    reader.pause();
+    assert!(reader.is_paused());
+    assert!(reader.read_once()?);
+    reader.read_available()?;
+    assert!(!reader.has_lines());
+    reader.resume();
+    assert!(!reader.is_paused());
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\n", "2\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_scripted_partial_reads() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1")
+        .would_block()
+        .data(b"\n2")
+        .interrupted()
+        .data(b"\n3")
+        .eof();
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    assert!(!reader.has_lines());
+    reader.read_once()?;
+    assert!(!reader.has_lines());
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\n"]);
+    reader.read_once()?;
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["2\n"]);
+    assert!(!reader.eof());
+    reader.read_once()?;
+    assert!(reader.eof());
+    assert_eq!(reader.lines_get(), vec!["3"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_scripted_error() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1\n")
+        .error(std::io::ErrorKind::ConnectionReset);
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    let err = reader.read_once().err().ok_or_else(|| eyre!("no error"))?;
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset);
+    assert_eq!(reader.lines_get(), vec!["1\n"]);
+    assert!(reader.is_broken());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_idle_flush() -> Result<()> {
+    let (mut wr, rd) = std::os::unix::net::UnixStream::pair()?;
+    let mut reader = LineReader::new(rd)?.with_idle_flush(std::time::Duration::from_millis(10));
+    // Prompt with an incomplete UTF-8 sequence at the end:
+    wr.write_all(b"1\nprompt> ")?;
+    wr.write_all(&SPARKLE_HEART[..2])?;
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\n"]);
+    reader.read_once()?;
+    assert!(!reader.has_lines());
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["prompt> "]);
+    wr.write_all(&SPARKLE_HEART[2..])?;
+    wr.write_all(b"\n")?;
+    reader.read_once()?;
+    assert_eq!(
+        reader.lines_get(),
+        vec![format!("{}\n", std::str::from_utf8(&SPARKLE_HEART)?)]
+    );
+    Ok(())
+}
+
+#[test_log::test]
+fn test_stall() -> Result<()> {
+    let (mut wr, rd) = std::os::unix::net::UnixStream::pair()?;
+    let mut reader = LineReader::new(rd)?.with_stall_timeout(std::time::Duration::from_millis(10));
+    assert!(!reader.is_stalled());
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert!(reader.idle_for() >= std::time::Duration::from_millis(20));
+    assert!(reader.is_stalled());
+    assert!(reader.take_stalled());
+    assert!(!reader.take_stalled());
+    assert!(reader.is_stalled());
+    wr.write_all(b"1\n")?;
+    reader.read_once()?;
+    assert!(!reader.is_stalled());
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert!(reader.take_stalled());
+    drop(wr);
+    reader.read_once()?;
+    assert!(reader.eof());
+    assert!(!reader.is_stalled());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_history() -> Result<()> {
+    let script = ScriptedReader::new().data(b"1\n2\n").data(b"3\n4");
+    let mut reader = LineReader::from_nonblocking(script)?.with_history(3);
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\n", "2\n"]);
+    assert_eq!(
+        reader.recent_lines().collect::<Vec<_>>(),
+        vec!["1\n", "2\n"]
+    );
+    reader.read_once()?;
+    reader.read_once()?;
+    assert_eq!(
+        reader.recent_lines().collect::<Vec<_>>(),
+        vec!["2\n", "3\n", "4"]
+    );
+    Ok(())
+}
+
+#[test_log::test]
+fn test_skip_take() -> Result<()> {
+    let script = ScriptedReader::new().data(b"header\n1\n2\n3\n4\n");
+    let mut reader = LineReader::from_nonblocking(script)?
+        .with_skip(1)
+        .with_take(2);
+    reader.read_once()?;
+    assert!(reader.eof());
+    assert_eq!(reader.lines_get(), vec!["1\n", "2\n"]);
+    assert!(!reader.read_once()?);
+    assert!(reader.lines_get().is_empty());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_checksums() -> Result<()> {
+    let script = ScriptedReader::new().data(b"123456789\nabc");
+    let mut reader = LineReader::from_nonblocking(script)?.with_checksums();
+    reader.read_once()?;
+    reader.read_once()?;
+    let lines = reader.lines_get_with_meta();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].0, "123456789\n");
+    assert_eq!(lines[0].1.checksum, Some(0xe011_7757));
+    assert_eq!(lines[1].1.checksum, Some(0x3524_41c2));
+    let mut reader = reader_for(b"1\n")?;
+    reader.read_once()?;
+    assert_eq!(reader.lines_get_with_meta()[0].1.checksum, None);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_hooks() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+    let events = Arc::new(Mutex::new(Vec::<String>::new()));
+    let (on_line, on_eof, on_error) = (events.clone(), events.clone(), events.clone());
+    let script = ScriptedReader::new()
+        .data(b"1\n2")
+        .error(std::io::ErrorKind::ConnectionReset);
+    let mut reader = LineReader::from_nonblocking(script)?
+        .on_line(move |line| on_line.lock().unwrap().push(line))
+        .on_eof(move || on_eof.lock().unwrap().push("eof".into()))
+        .on_error(move |err| on_error.lock().unwrap().push(format!("{:?}", err.kind())));
+    reader.read_once()?;
+    assert!(!reader.has_lines());
+    assert!(reader.read_once().is_err());
+    assert_eq!(*events.lock().unwrap(), vec!["1\n", "ConnectionReset"]);
+    let events = Arc::new(Mutex::new(Vec::<String>::new()));
+    let (on_line, on_eof) = (events.clone(), events.clone());
+    let mut reader = LineReader::from_nonblocking(ScriptedReader::new().data(b"3"))?
+        .on_line(move |line| on_line.lock().unwrap().push(line))
+        .on_eof(move || on_eof.lock().unwrap().push("eof".into()));
+    while !reader.eof() {
+        reader.read_once()?;
+    }
+    reader.read_once()?;
+    assert_eq!(*events.lock().unwrap(), vec!["3", "eof"]);
+    let _ = format!("{:?}", reader);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_hooks_read_available() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+    let lines = Arc::new(Mutex::new(Vec::<String>::new()));
+    let on_line = lines.clone();
+    let script = ScriptedReader::new()
+        .data(b"1\n2")
+        .would_block()
+        .data(b"\n3\n");
+    let mut reader = LineReader::from_nonblocking(script)?
+        .on_line(move |line| on_line.lock().unwrap().push(line));
+    // Returns after the pass that gave lines to the hook:
+    reader.read_available()?;
+    assert_eq!(*lines.lock().unwrap(), vec!["1\n"]);
+    assert!(!reader.eof());
+    reader.read_available()?;
+    assert_eq!(*lines.lock().unwrap(), vec!["1\n", "2\n", "3\n"]);
+    assert!(!reader.eof());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_tap() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+    let data = Arc::new(Mutex::new(Vec::<u8>::new()));
+    let on_data = data.clone();
+    let script = ScriptedReader::new().data(b"1\r\n2").data(b"\xff\n").eof();
+    let mut reader = LineReader::from_nonblocking(script)?
+        .on_data(move |chunk| on_data.lock().unwrap().extend_from_slice(chunk));
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\r\n"]);
+    assert!(reader.read_once().is_err());
+    assert_eq!(*data.lock().unwrap(), b"1\r\n2\xff\n");
+    let path = std::env::temp_dir().join(format!("lineriver-tap-{}", std::process::id()));
+    let script = ScriptedReader::new().data(b"a\nb").data(b"c\n").eof();
+    let mut reader = LineReader::from_nonblocking(script)?.with_tap(std::fs::File::create(&path)?);
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get(), vec!["a\n", "bc\n"]);
+    assert_eq!(std::fs::read(&path)?, b"a\nbc\n");
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test_log::test]
+fn test_lines_get_shared() -> Result<()> {
+    let mut reader = reader_for(b"1\n2\n")?;
+    reader.read_once()?;
+    let lines = reader.lines_get_shared();
+    assert_eq!(lines.len(), 2);
+    let consumer = std::thread::spawn({
+        let lines = lines.clone();
+        move || lines.concat()
+    });
+    assert_eq!(consumer.join().unwrap(), "1\n2\n");
+    assert_eq!(&*lines[1], "2\n");
+    assert!(reader.lines_get_shared().is_empty());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_read_and_get() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1\n2")
+        .would_block()
+        .data(b"\n")
+        .error(std::io::ErrorKind::BrokenPipe);
+    let mut reader = LineReader::from_nonblocking(script)?;
+    assert_eq!(reader.read_and_get()?, vec!["1\n"]);
+    assert_eq!(reader.read_and_get()?, vec!["2\n"]);
+    assert!(reader.read_and_get().is_err());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_consume_lines() -> Result<()> {
+    let mut reader = reader_for(b"1\n2\n")?.with_stats();
+    reader.read_once()?;
+    let mut lines = vec![];
+    reader.consume_lines(&mut |line| lines.push(line.to_owned()));
+    assert_eq!(lines, vec!["1\n", "2\n"]);
+    assert!(!reader.has_lines());
+    assert_eq!(reader.checkpoint().lines, 2);
+    // Borrowed lines:
+    let mut reader = reader_for(b"1\n2\n")?.with_borrowed_lines(true);
+    reader.read_once()?;
+    let mut count = 0;
+    reader.consume_lines(&mut |_| count += 1);
+    assert_eq!(count, 2);
+    assert!(!reader.has_lines());
+    // Trait objects:
+    let mut reader: Box<dyn LineRead> = Box::new(reader_for(b"1\n")?.map_lines(|l| l + "x"));
+    reader.read_once()?;
+    let mut lines = vec![];
+    reader.consume_lines(&mut |line| lines.push(line.to_owned()));
+    assert_eq!(lines, vec!["1\nx"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_lines_iter() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1\n2\n3")
+        .would_block()
+        .data(b"\n\xff\n4")
+        .eof();
+    let mut reader = LineReader::from_nonblocking(script)?.with_borrowed_lines(true);
+    reader.read_once()?;
+    assert!(reader.has_lines());
+    assert_eq!(reader.lines_iter().next().unwrap()?, "1\n");
+    assert_eq!(
+        reader
+            .lines_iter()
+            .map_while(Result::ok)
+            .collect::<Vec<_>>(),
+        vec!["2\n"]
+    );
+    assert!(!reader.has_lines());
+    reader.read_once()?;
+    reader.read_once()?;
+    reader.read_once()?;
+    assert!(reader.eof());
+    let mut lines = reader.lines_iter();
+    assert_eq!(lines.next().unwrap()?, "3\n");
+    assert!(lines.next().unwrap().is_err());
+    assert_eq!(lines.next().unwrap()?, "4");
+    assert!(lines.next().is_none());
+    // Readers that split lines don't leave any in the buffer:
+    let mut reader = reader_for(b"1\n")?;
+    reader.read_once()?;
+    assert_eq!(reader.lines_iter().count(), 0);
+    assert_eq!(reader.lines_get(), vec!["1\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_borrowed_invalid_utf8() -> Result<()> {
+    let data = b"1\n\xff\n2\n";
+    let mut reader = LineReader::from_nonblocking(&data[..])?.with_borrowed_lines(true);
+    reader.read_once()?;
+    assert!(reader.has_lines());
+    assert_eq!(reader.lines_get(), vec!["1\n", "\u{fffd}\n", "2\n"]);
+    let mut reader = LineReader::from_nonblocking(&data[..])?.with_borrowed_lines(true);
+    reader.read_once()?;
+    assert_eq!(reader.pop_line().as_deref(), Some("1\n"));
+    assert_eq!(reader.pop_line().as_deref(), Some("\u{fffd}\n"));
+    let mut lines = vec![];
+    reader.consume_lines(&mut |line| lines.push(line.to_owned()));
+    assert_eq!(lines, vec!["2\n"]);
+    let mut reader = LineReader::from_nonblocking(&data[..])?.with_borrowed_lines(true);
+    reader.read_once()?;
+    let mut count = 0;
+    reader.consume_lines(&mut |_| count += 1);
+    assert_eq!(count, 3);
+    assert!(!reader.has_lines());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_borrowed_consumed() -> Result<()> {
+    let borrowed = || -> Result<LineReader<&[u8]>> {
+        let mut reader = LineReader::from_nonblocking(&b"1\n22\n333\npar"[..])?
+            .with_borrowed_lines(true)
+            .with_max_queued_lines(2);
+        reader.read_once()?;
+        assert_eq!(reader.lines_iter().next().unwrap()?, "1\n");
+        Ok(reader)
+    };
+    let reader = borrowed()?;
+    assert_eq!(reader.partial_line(), b"par");
+    assert_eq!(reader.buffered_bytes(), 10);
+    assert!(reader.is_full());
+    let checkpoint = reader.checkpoint();
+    assert_eq!(checkpoint.pending, b"22\n333\npar");
+    assert_eq!(checkpoint.offset, 12);
+    let state = reader.dump_state();
+    assert_eq!(state.used, 3);
+    assert_eq!(state.partial, b"par");
+    assert_eq!(state.queued_lines, 2);
+    let mut rest = String::new();
+    std::io::Read::read_to_string(&mut reader.into_buf_read(), &mut rest)?;
+    assert_eq!(rest, "22\n333\npar");
+    let mut reader = borrowed()?;
+    assert_eq!(reader.lines_iter().next().unwrap()?, "22\n");
+    assert!(!reader.is_full());
+    reader.switch_to_raw();
+    assert_eq!(reader.raw_get(), b"333\npar");
+    Ok(())
+}
+
+#[test_log::test]
+fn test_deferred_utf8() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1\n\xff\n2")
+        .would_block()
+        .data(b"\n3")
+        .eof();
+    let mut reader = LineReader::from_nonblocking(script)?.with_deferred_utf8(true);
+    reader.read_once()?;
+    assert!(reader.lines_get_str().is_err());
+    assert_eq!(
+        reader.lines_get_bytes(),
+        vec![b"1\n".to_vec(), b"\xff\n".to_vec()]
+    );
+    assert!(reader.lines_get_str()?.is_empty());
+    reader.read_once()?;
+    reader.read_once()?;
+    reader.read_once()?;
+    assert!(reader.eof());
+    assert_eq!(reader.lines_get_str()?, vec!["2\n", "3"]);
+    // Readers that split lines validate them as they are read:
+    let mut reader = reader_for(b"1\n")?;
+    reader.read_once()?;
+    assert_eq!(reader.lines_get_bytes(), vec![b"1\n".to_vec()]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_boxed() -> Result<()> {
+    use std::os::fd::{AsFd, AsRawFd};
+    let (mut tx1, rx1) = UnixStream::pair()?;
+    let path = std::env::temp_dir().join(format!("lineriver-boxed-{}", std::process::id()));
+    std::fs::write(&path, b"2\n")?;
+    let mut readers = [
+        LineReader::boxed_fd(rx1)?,
+        LineReader::boxed_fd(std::fs::File::open(&path)?)?,
+    ];
+    std::fs::remove_file(&path)?;
+    tx1.write_all(b"1\n")?;
+    let lines = readers
+        .iter_mut()
+        .map(|reader| {
+            reader.read_available()?;
+            Ok(reader.lines_get())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(lines, vec![vec!["1\n"], vec!["2\n"]]);
+    let mut reader = LineReader::boxed(UnixStream::pair()?.1)?;
+    assert!(reader.read_once()?);
+    assert!(!reader.has_lines());
+    let reader = LineReader::boxed_raw_fd(UnixStream::pair()?.1)?;
+    assert!(reader.as_raw_fd() >= 0);
+    let reader = LineReader::boxed_raw_and_fd(UnixStream::pair()?.1)?;
+    assert_eq!(reader.as_raw_fd(), reader.as_fd().as_raw_fd());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_downcast() -> Result<()> {
+    let (mut tx, rx) = UnixStream::pair()?;
+    let mut readers: Vec<Box<dyn LineReadAny>> = vec![
+        Box::new(LineReader::new(rx)?),
+        Box::new(MockLineRead::new().line("mock\n")),
+    ];
+    tx.write_all(b"1\n")?;
+    for reader in readers.iter_mut() {
+        reader.read_available()?;
+    }
+    assert!(readers[0].downcast_ref::<MockLineRead>().is_none());
+    let reader = readers[0]
+        .downcast_mut::<LineReader<UnixStream>>()
+        .ok_or_else(|| eyre!("downcast failed"))?;
+    reader.get_mut().shutdown(Shutdown::Both)?;
+    assert_eq!(reader.lines_get(), vec!["1\n"]);
+    let mock = readers[1]
+        .downcast_ref::<MockLineRead>()
+        .ok_or_else(|| eyre!("downcast failed"))?;
+    assert_eq!(mock.reads(), 1);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_read_until_sentinel() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"220 banner\n")
+        .data(b"more banner\nEND\nDATA\n.\nQUIT\n")
+        .eof();
+    let mut reader = LineReader::from_nonblocking(script)?;
+    let is_end = |line: &str| line == "END\n";
+    let scope = reader.read_until_sentinel(is_end, true)?;
+    assert_eq!(scope.lines, vec!["220 banner\n"]);
+    assert!(!scope.complete);
+    let scope = reader.read_until_sentinel(is_end, true)?;
+    assert_eq!(scope.lines, vec!["more banner\n", "END\n"]);
+    assert!(scope.complete);
+    // The rest is still buffered, and found without reading:
+    let scope = reader.read_until_sentinel(|line| line == ".\n", false)?;
+    assert_eq!(
+        scope,
+        SentinelScope {
+            lines: vec!["DATA\n".to_string()],
+            complete: true
+        }
+    );
+    assert!(!reader.eof());
+    assert_eq!(reader.lines_get(), vec!["QUIT\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_take_until() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"a\nb")
+        .would_block()
+        .data(b"\nEND\nc\n")
+        .eof();
+    let mut reader = LineReader::from_nonblocking(script)?.with_label("outer");
+    {
+        let mut view = reader.take_until("END");
+        assert_eq!(view.label(), Some("outer"));
+        view.read_once()?;
+        assert_eq!(view.lines_get(), vec!["a\n"]);
+        view.read_once()?;
+        assert!(!view.eof());
+        view.read_once()?;
+        assert!(view.is_complete());
+        assert!(view.eof());
+        assert!(!view.read_once()?);
+        assert_eq!(view.lines_get(), vec!["b\n", "END\n"]);
+    }
+    assert!(!reader.eof());
+    assert_eq!(reader.lines_get(), vec!["c\n"]);
+    // The view also ends if the reader reaches EOF:
+    let mut view = reader.take_until("END");
+    while view.read_once()? {}
+    assert!(!view.is_complete());
+    assert!(reader.eof());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_dump_state() -> Result<()> {
+    let script = ScriptedReader::new().data(b"1\n2\nthe partial line\x00");
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    let state = reader.dump_state();
+    assert_eq!(state.used, 17);
+    assert_eq!(state.partial, b"the partial line\x00");
+    assert_eq!(state.queued_lines, 2);
+    assert_eq!(state.capacity, reader.buffer_capacity());
+    assert!(state.capacity >= 17);
+    assert!(!state.eof && !state.broken && !state.paused);
+    assert_eq!(
+        state.to_string(),
+        format!(
+            "used=17 capacity={} queued_lines=2 eof=false broken=false paused=false\n\
+             00000000  74 68 65 20 70 61 72 74 69 61 6c 20 6c 69 6e 65  |the partial line|\n\
+             00000010  00                                               |.|\n",
+            state.capacity
+        )
+    );
+    Ok(())
+}
+
+#[test_log::test]
+fn test_into_buf_read() -> Result<()> {
+    use std::io::{BufRead, Read};
+    let script = ScriptedReader::new()
+        .data(b"1\n2\npar")
+        .would_block()
+        .data(b"tial\n3\n");
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    let mut bufread = reader.into_buf_read();
+    let mut line = String::new();
+    bufread.read_line(&mut line)?;
+    assert_eq!(line, "1\n");
+    let mut rest = Vec::new();
+    let err = bufread.read_to_end(&mut rest).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+    assert_eq!(rest, b"2\npar");
+    bufread.read_to_end(&mut rest)?;
+    assert_eq!(rest, b"2\npartial\n3\n");
+    Ok(())
+}
+
+#[test_log::test]
+fn test_switch_to_raw() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"HEAD\n\nbody\nstart")
+        .would_block()
+        .data(b"\x00\x01\n")
+        .eof();
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    // Switching puts the queued lines back in the raw buffer:
+    reader.switch_to_raw();
+    assert!(reader.is_raw());
+    assert!(!reader.has_lines());
+    let mut body = reader.raw_get();
+    assert_eq!(body, b"HEAD\n\nbody\nstart");
+    while reader.read_once()? {
+        body.extend(reader.raw_get());
+    }
+    assert_eq!(body, b"HEAD\n\nbody\nstart\x00\x01\n");
+    assert!(reader.lines_get().is_empty());
+    assert!(reader.eof());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_drain() -> Result<()> {
+    use std::time::{Duration, Instant};
+    let mut child = Command::new("sh")
+        .args(["-c", "echo 1; sleep 0.1; echo 2; printf 3; exec sleep 10"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut reader = LineReader::new(
+        child
+            .stdout
+            .take()
+            .ok_or_else(|| eyre!("error taking stdout"))?,
+    )?;
+    let start = Instant::now();
+    let lines = reader.drain(start + Duration::from_millis(500))?;
+    assert!(start.elapsed() >= Duration::from_millis(500));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(lines, vec!["1\n", "2\n"]);
+    assert!(!reader.eof());
+    child.kill()?;
+    child.wait()?;
+    let lines = reader.drain(Instant::now() + Duration::from_secs(5))?;
+    assert_eq!(lines, vec!["3"]);
+    assert!(reader.eof());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_collect_all() -> Result<()> {
+    use std::time::{Duration, Instant};
+    let mut child = Command::new("sh")
+        .args(["-c", "echo 1; sleep 0.1; printf 2; exec sleep 10"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| eyre!("error taking stdout"))?;
+    let mut reader = LineReader::new(stdout)?;
+    let start = Instant::now();
+    let lines = reader.collect_all(Some(Duration::from_millis(300)))?;
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    assert_eq!(lines, vec!["1\n"]);
+    child.kill()?;
+    child.wait()?;
+    assert_eq!(reader.collect_all(None)?, vec!["2"]);
+    assert!(reader.eof());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_for_each_line() -> Result<()> {
+    use std::ops::ControlFlow;
+    use std::time::{Duration, Instant};
+    let mut child = Command::new("sh")
+        .args(["-c", "echo 1; sleep 0.1; echo 2; exec sleep 10"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| eyre!("error taking stdout"))?;
+    let mut reader = LineReader::new(stdout)?;
+    let mut lines = vec![];
+    let start = Instant::now();
+    let flow = reader.for_each_line(Some(Duration::from_millis(300)), |line| {
+        lines.push(line);
+        ControlFlow::Continue(())
+    })?;
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    assert!(flow.is_continue());
+    assert!(!reader.eof());
+    assert_eq!(lines, vec!["1\n", "2\n"]);
+    child.kill()?;
+    child.wait()?;
+    let flow = reader.for_each_line(None, |_| ControlFlow::Break(()))?;
+    assert!(flow.is_continue());
+    assert!(reader.eof());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_replace_reader() -> Result<()> {
+    let (mut old, rd) = UnixStream::pair()?;
+    let mut reader = LineReader::new(rd)?;
+    old.write_all(b"1\n2")?;
+    drop(old);
+    reader.read_once()?;
+    reader.read_once()?;
+    assert!(reader.eof());
+    let (mut new, rd) = UnixStream::pair()?;
+    let old = reader.replace_reader(rd)?;
+    assert!(old.peer_addr().is_ok());
+    assert!(!reader.eof());
+    assert_eq!(reader.generation(), 1);
+    new.write_all(b"3\n")?;
+    reader.read_once()?;
+    let lines = reader
+        .lines_get_with_meta()
+        .into_iter()
+        .map(|(line, meta)| (line, meta.generation))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![("1\n".into(), 0), ("2".into(), 0), ("3\n".into(), 1)]
+    );
+    Ok(())
+}
+
+#[test_log::test]
+fn test_replace_reader_resets_splitter() -> Result<()> {
+    let (mut old, rd) = UnixStream::pair()?;
+    let mut reader = LineReader::new(rd)?.with_max_line_len(4, LongLinePolicy::Truncate);
+    old.write_all(b"1\r\nabcdefgh")?;
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1\r\n", "abcd"]);
+    assert_eq!(reader.eol_style(), Some(EolStyle::Crlf));
+    let (mut new, rd) = UnixStream::pair()?;
+    reader.replace_reader(rd)?;
+    assert_eq!(reader.eol_style(), None);
+    new.write_all(b"x\n")?;
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["x\n"]);
+    assert_eq!(reader.eol_style(), Some(EolStyle::Lf));
+    Ok(())
+}
+
+#[test_log::test]
+fn test_delimiters() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"a;b,c")
+        .would_block()
+        .data(b"\nd")
+        .eof();
+    let mut reader = LineReader::from_nonblocking(script)?.with_delimiters(b"\n;,");
+    while reader.read_once()? {}
+    let lines = reader
+        .lines_get_with_meta()
+        .into_iter()
+        .map(|(line, meta)| (line, meta.delimiter))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            ("a;".into(), Some(b';')),
+            ("b,".into(), Some(b',')),
+            ("c\n".into(), Some(b'\n')),
+            ("d".into(), None),
+        ]
+    );
+    // Borrowed lines:
+    let script = ScriptedReader::new().data(b"a;b|c;d|e").eof();
+    let mut reader = LineReader::from_nonblocking(script)?
+        .with_delimiters(b";|\n\t")
+        .with_borrowed_lines(true);
+    reader.read_once()?;
+    assert!(reader.has_lines());
+    assert_eq!(reader.lines_iter().next().unwrap()?, "a;");
+    assert_eq!(reader.lines_get_str()?, vec!["b|", "c;", "d|"]);
+    reader.read_once()?;
+    assert_eq!(reader.lines_get_bytes(), vec![b"e".to_vec()]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_terminator_crlf() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"a\nb\r")
+        .would_block()
+        .data(b"\nc\r\r\n\rd")
+        .eof();
+    let mut reader = LineReader::from_nonblocking(script)?.with_terminator("\r\n");
+    reader.read_once()?;
+    assert!(!reader.has_lines());
+    while reader.read_once()? {}
+    let lines = reader
+        .lines_get_with_meta()
+        .into_iter()
+        .map(|(line, meta)| (line, meta.delimiter))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            ("a\nb\r\n".into(), Some(b'\n')),
+            ("c\r\r\n".into(), Some(b'\n')),
+            ("\rd".into(), None),
+        ]
+    );
+    // Borrowed lines, with a self-overlapping terminator:
+    let script = ScriptedReader::new()
+        .data(b"1--2---3-")
+        .would_block()
+        .data(b"-")
+        .eof();
+    let mut reader = LineReader::from_nonblocking(script)?
+        .with_terminator("--")
+        .with_borrowed_lines(true);
+    reader.read_once()?;
+    assert_eq!(reader.queued_lines(), 2);
+    assert_eq!(reader.lines_get_str()?, vec!["1--", "2--"]);
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get_bytes(), vec![b"-3--".to_vec()]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_delimiter_nul() -> Result<()> {
+    let output = Command::new("printf")
+        .arg("a\\0b c\\0\\0d")
+        .stdout(Stdio::piped())
+        .spawn()?
+        .stdout
+        .ok_or_else(|| eyre!("no stdout"))?;
+    let mut reader = LineReader::new(output)?.with_delimiter(b'\0');
+    let mut lines = vec![];
+    while !reader.eof() {
+        reader.read_available()?;
+        lines.extend(reader.lines_get());
+    }
+    assert_eq!(lines, vec!["a\0", "b c\0", "\0", "d"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_max_line_len_error() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1234\n12")
+        .would_block()
+        .data(b"3456");
+    let mut reader = LineReader::from_nonblocking(script)?
+        .with_label("peer")
+        .with_max_line_len(5, LongLinePolicy::Error);
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["1234\n"]);
+    assert!(reader.read_once()?);
+    let err = reader.read_once().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let too_long = LineTooLong::from_io(&err).ok_or_else(|| eyre!("not LineTooLong"))?;
+    assert_eq!(too_long.max(), 5);
+    assert!(reader.is_broken());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_max_line_len_truncate() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"ab\xc3\xa7def")
+        .would_block()
+        .data(b"gh\r")
+        .would_block()
+        .data(b"\nok\r\nlonger\r\nx\r\n");
+    let mut reader = LineReader::from_nonblocking(script)?
+        .with_terminator("\r\n")
+        .with_max_line_len(4, LongLinePolicy::Truncate);
+    while reader.read_once()? {}
+    let lines = reader
+        .lines_get_with_meta()
+        .into_iter()
+        .map(|(line, meta)| (line, meta.offset))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            // The offset of a truncated line includes the bytes
+            // discarded so far:
+            ("ab\u{e7}".into(), 6),
+            ("ok\r\n".into(), 15),
+            ("long".into(), 23),
+            ("x\r\n".into(), 26),
+        ]
+    );
+    assert_eq!(reader.checkpoint().offset, 26);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_label() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1\n")
+        .error(std::io::ErrorKind::ConnectionReset);
+    let mut reader = LineReader::from_nonblocking(script)?.with_label("worker-3 stderr");
+    assert_eq!(reader.label(), Some("worker-3 stderr"));
+    assert!(format!("{:?}", reader).contains("worker-3 stderr"));
+    reader.read_once()?;
+    let err = reader.read_once().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset);
+    assert!(err.to_string().starts_with("worker-3 stderr: "));
+    let mut dedup = LineDedup::new(reader);
+    assert_eq!(dedup.label(), Some("worker-3 stderr"));
+    assert_eq!(dedup.lines_get(), vec!["1\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_error_context() -> Result<()> {
+    let (mut writer, stream) = UnixStream::pair()?;
+    let fd = std::os::fd::AsRawFd::as_raw_fd(&stream);
+    let mut reader = LineReader::new(stream)?
+        .with_label("conn-7")
+        .with_peer("10.0.0.7:4242");
+    writer.write_all(b"\xff\n")?;
+    let err = reader.read_once().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let context = ReadError::from_io(&err).ok_or_else(|| eyre!("no context"))?;
+    assert_eq!(context.label(), Some("conn-7"));
+    assert_eq!(context.fd(), Some(fd));
+    assert_eq!(context.peer(), Some("10.0.0.7:4242"));
+    assert_eq!(context.io_error().kind(), std::io::ErrorKind::InvalidData);
+    assert!(err
+        .to_string()
+        .starts_with(&format!("conn-7, fd {}, peer 10.0.0.7:4242: ", fd)));
+    // The sticky error also has the context:
+    let err = reader.read_once().unwrap_err();
+    assert!(ReadError::from_io(&err).is_some());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_buffer_introspection() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1\n22\npar")
+        .would_block()
+        .data(b"t");
+    let mut reader = LineReader::from_nonblocking(script)?;
+    assert_eq!(reader.queued_lines(), 0);
+    reader.read_once()?;
+    assert_eq!(reader.queued_lines(), 2);
+    assert_eq!(reader.buffered_bytes(), 8);
+    assert!(reader.buffer_capacity() >= 3);
+    reader.lines_get();
+    assert_eq!(reader.queued_lines(), 0);
+    assert_eq!(reader.buffered_bytes(), 3);
+    let script = ScriptedReader::new().data(b"1\n22\npar");
+    let mut reader = LineReader::from_nonblocking(script)?.with_borrowed_lines(true);
+    reader.read_once()?;
+    assert_eq!(reader.queued_lines(), 2);
+    while reader.read_once()? {}
+    assert_eq!(reader.queued_lines(), 3);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_linereaderbytes() -> Result<()> {
+    let (mut tx, rx) = UnixStream::pair()?;
+    let mut reader = LineReaderBytes::new(rx)?;
+    tx.write_all(&INVALID_UTF8)?;
+    tx.write_all(b"\n")?;
+    tx.write_all(&SPARKLE_HEART[..2])?;
+    reader.read_available()?;
+    assert_eq!(
+        reader.lines_get(),
+        vec![[&INVALID_UTF8[..], b"\n"].concat()]
+    );
+    assert!(!reader.has_lines());
+    tx.write_all(&SPARKLE_HEART[2..])?;
+    drop(tx);
+    while reader.read_once()? {}
+    assert!(reader.eof());
+    assert_eq!(reader.lines_get(), vec![SPARKLE_HEART.to_vec()]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_lines_drain() -> Result<()> {
+    let script = ScriptedReader::new().data(b"1\n2\n3").would_block().eof();
+    let mut reader = LineReader::from_nonblocking(script)?.with_stats();
+    reader.read_once()?;
+    let lines = reader.lines_drain().collect::<Vec<_>>();
+    assert_eq!(lines, vec!["1\n", "2\n"]);
+    assert_eq!(reader.lines_drain().count(), 0);
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_drain().collect::<Vec<_>>(), vec!["3"]);
+    assert_eq!(reader.checkpoint().lines, 3);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_lines_peek() -> Result<()> {
+    let script = ScriptedReader::new().data(b"a\nb\nc").would_block().eof();
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    assert_eq!(reader.lines_peek().collect::<Vec<_>>(), vec!["a\n", "b\n"]);
+    assert_eq!(reader.queued_lines(), 2);
+    reader.lines_clear();
+    assert_eq!(reader.lines_peek().count(), 0);
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_peek().collect::<Vec<_>>(), vec!["c"]);
+    // Borrowed lines:
+    let script = ScriptedReader::new()
+        .data(b"a\nb\n\xff\nc")
+        .would_block()
+        .eof();
+    let mut reader = LineReader::from_nonblocking(script)?.with_borrowed_lines(true);
+    reader.read_once()?;
+    assert_eq!(reader.lines_peek().collect::<Vec<_>>(), vec!["a\n", "b\n"]);
+    assert_eq!(reader.queued_lines(), 3);
+    reader.lines_clear();
+    assert!(!reader.has_lines());
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_peek().collect::<Vec<_>>(), vec!["c"]);
+    assert_eq!(reader.lines_get(), vec!["c"]);
+    Ok(())
+}
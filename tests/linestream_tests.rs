@@ -0,0 +1,128 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_linestream() -> Result<()> {
+    let (stream, peer) = UnixStream::pair()?;
+    let mut linestream = LineStream::new(stream.try_clone()?, stream)?;
+    linestream.write_line("ping")?;
+    let mut peer_reader = BufReader::new(peer.try_clone()?);
+    let mut line = String::new();
+    peer_reader.read_line(&mut line)?;
+    assert_eq!(line, "ping\n");
+    (&peer).write_all(b"pong\n")?;
+    drop(peer_reader);
+    drop(peer);
+    while linestream.read_once()? {}
+    assert_eq!(linestream.lines_get(), vec!["pong\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_linewriter_wouldblock() -> Result<()> {
+    let (stream, mut peer) = UnixStream::pair()?;
+    let mut writer = LineWriter::new(stream)?;
+    let line = "x".repeat(1023);
+    let mut sent = 0;
+    while writer.pending_bytes() == 0 {
+        writer.write_line(&line)?;
+        sent += 1024;
+    }
+    assert!(!writer.flush()?);
+    let mut received = vec![];
+    let mut buf = [0; 65536];
+    while !writer.flush()? {
+        let len = peer.read(&mut buf)?;
+        received.extend_from_slice(&buf[..len]);
+    }
+    assert_eq!(writer.pending_bytes(), 0);
+    drop(writer);
+    peer.read_to_end(&mut received)?;
+    assert_eq!(received.len(), sent);
+    assert!(received.chunks(1024).all(|c| c.ends_with(b"\n")));
+    Ok(())
+}
+
+#[test_log::test]
+fn test_linewriter_terminator() -> Result<()> {
+    let mut writer = LineWriter::from_nonblocking(Vec::new());
+    writer.write_line("1")?;
+    writer.write_line("2\r\n")?;
+    assert_eq!(writer.get_ref(), b"1\n2\r\n");
+    let mut writer = LineWriter::from_nonblocking(Vec::new()).with_terminator("\r\n");
+    writer.write_line("1")?;
+    writer.write_line("2\n")?;
+    writer.write_line("3\r\n")?;
+    assert_eq!(writer.get_ref(), b"1\r\n2\r\n3\r\n");
+    let mut writer = LineWriter::from_nonblocking(Vec::new()).with_terminator("");
+    writer.write_line("1")?;
+    writer.write_line("2\n")?;
+    assert_eq!(writer.get_ref(), b"12\n");
+    let mut writer = LineWriter::from_nonblocking(Vec::new()).with_terminator(vec![0]);
+    writer.write_line("1\n")?;
+    assert_eq!(writer.get_ref(), b"1\0");
+    Ok(())
+}
+
+#[test_log::test]
+fn test_linewriter_dot_stuffing() -> Result<()> {
+    let mut writer = LineWriter::from_nonblocking(Vec::new()).with_dot_stuffing();
+    writer.write_line("Subject: test\n")?;
+    writer.write_line("\n")?;
+    writer.write_line(".\n")?;
+    writer.write_line("..x")?;
+    writer.write_line("a.b\n")?;
+    writer.write_terminating_dot()?;
+    assert_eq!(
+        writer.get_ref(),
+        b"Subject: test\r\n\r\n..\r\n...x\r\na.b\r\n.\r\n"
+    );
+    Ok(())
+}
+
+#[test_log::test]
+fn test_linestream_finish_writing_socket() -> Result<()> {
+    let (stream, peer) = UnixStream::pair()?;
+    let mut linestream = LineStream::new(stream.try_clone()?, stream)?;
+    linestream.write_line("question")?;
+    assert!(linestream.finish_writing()?);
+    assert!(linestream.is_write_closed());
+    assert!(linestream.write_line("more").is_err());
+    // The peer sees EOF and can still answer:
+    let mut received = String::new();
+    (&peer).read_to_string(&mut received)?;
+    assert_eq!(received, "question\n");
+    (&peer).write_all(b"answer\n")?;
+    drop(peer);
+    assert_eq!(linestream.collect_all(None)?, vec!["answer\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_linestream_finish_writing_pipe() -> Result<()> {
+    let child = std::process::Command::new("tac")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()?;
+    let stdin = child
+        .stdin
+        .ok_or_else(|| std::io::Error::other("no stdin"))?;
+    let stdout = child
+        .stdout
+        .ok_or_else(|| std::io::Error::other("no stdout"))?;
+    let mut linestream = LineStream::new(stdout, stdin)?;
+    for i in 0..3 {
+        linestream.write_line(&i.to_string())?;
+    }
+    while !linestream.finish_writing()? {}
+    assert_eq!(linestream.collect_all(None)?, vec!["2\n", "1\n", "0\n"]);
+    Ok(())
+}
@@ -0,0 +1,59 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use color_eyre::Result;
+use log::Level;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+/// Logger that keeps the records in memory.
+struct Capture(Mutex<Vec<(Level, String, String)>>);
+
+impl log::Log for Capture {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.0.lock().unwrap().push((
+            record.level(),
+            record.target().to_string(),
+            record.args().to_string(),
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+// A single test, as the logger is global and test-log would replace it:
+#[test]
+fn test_logbridge() -> Result<()> {
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+    let script = ScriptedReader::new().data(b"ERROR: boom\n[warn] hmm\nplain\ntrace: x\n");
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    let logger = LineLogger::new("child");
+    assert_eq!(logger.forward(&mut reader), 4);
+    assert_eq!(
+        *CAPTURE.0.lock().unwrap(),
+        vec![
+            (Level::Error, "child".to_string(), "ERROR: boom".to_string()),
+            (Level::Warn, "child".to_string(), "[warn] hmm".to_string()),
+            (Level::Info, "child".to_string(), "plain".to_string()),
+        ]
+    );
+    let logger = LineLogger::new("x")
+        .with_level(Level::Debug)
+        .with_level_detection(false);
+    assert_eq!(logger.level_for("ERROR"), Level::Debug);
+    Ok(())
+}
@@ -0,0 +1,97 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "metrics")]
+
+use color_eyre::Result;
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_metrics() -> Result<()> {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || -> Result<()> {
+        let script = ScriptedReader::new().data(b"1\n2\n3").would_block();
+        let mut reader = LineReader::from_nonblocking(script)?;
+        reader.read_once()?;
+        reader.read_once()?;
+        Ok(())
+    })?;
+    let mut values = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| (key.key().name().to_string(), value))
+        .collect::<Vec<_>>();
+    values.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        values,
+        vec![
+            (
+                "lineriver_buffer_bytes".to_string(),
+                DebugValue::Gauge(1.0.into())
+            ),
+            ("lineriver_bytes_read".to_string(), DebugValue::Counter(5)),
+            ("lineriver_lines_read".to_string(), DebugValue::Counter(2)),
+            ("lineriver_wouldblock".to_string(), DebugValue::Counter(1)),
+        ]
+    );
+    Ok(())
+}
+
+#[cfg(feature = "polling")]
+#[test_log::test]
+fn test_metrics_reactor() -> Result<()> {
+    use std::io::Write;
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let (mut writer, stream) = UnixStream::pair()?;
+    let (_idle, idle_stream) = UnixStream::pair()?;
+    metrics::with_local_recorder(&recorder, || -> Result<()> {
+        let mut reactor = Reactor::new()?;
+        reactor.add(LineReader::new(stream)?.with_max_queued_lines(1))?;
+        let idle = reactor.add(LineReader::new(idle_stream)?)?;
+        reactor.suspend(idle)?;
+        writer.write_all(b"1\n2\n")?;
+        let events = reactor.wait(Some(Duration::from_secs(5)))?;
+        assert_eq!(events.len(), 1);
+        Ok(())
+    })?;
+    let mut values = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| (key.key().name().to_string(), value))
+        .filter(|(name, _)| name.starts_with("lineriver_reactor"))
+        .collect::<Vec<_>>();
+    values.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        values,
+        vec![
+            (
+                "lineriver_reactor_events".to_string(),
+                DebugValue::Counter(1)
+            ),
+            (
+                "lineriver_reactor_readers".to_string(),
+                DebugValue::Gauge(2.0.into())
+            ),
+            (
+                "lineriver_reactor_suspended".to_string(),
+                DebugValue::Gauge(1.0.into())
+            ),
+            (
+                "lineriver_reactor_throttled".to_string(),
+                DebugValue::Gauge(1.0.into())
+            ),
+        ]
+    );
+    Ok(())
+}
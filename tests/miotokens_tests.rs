@@ -0,0 +1,51 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "mio")]
+
+use std::io::{self, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use color_eyre::Result;
+use mio::{Events, Poll, Token};
+
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_mio_line_readers() -> Result<()> {
+    let mut poll = Poll::new()?;
+    let mut readers = MioLineReaders::new();
+    let (mut writer1, stream1) = UnixStream::pair()?;
+    let (mut writer2, stream2) = UnixStream::pair()?;
+    readers.insert(poll.registry(), Token(1), LineReader::new(stream1)?)?;
+    readers.insert(poll.registry(), Token(2), LineReader::new(stream2)?)?;
+    let dup = LineReader::new(UnixStream::pair()?.1)?;
+    let err = readers.insert(poll.registry(), Token(1), dup).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    // More than a LineReader buffer, to check that the reader is
+    // drained on each edge-triggered event:
+    let big = format!("{}\n", "x".repeat(20000));
+    writer1.write_all(big.as_bytes())?;
+    writer2.write_all(b"2\n")?;
+    drop(writer1);
+    let mut events = Events::with_capacity(16);
+    let mut lines = vec![];
+    while readers.len() > 1 {
+        poll.poll(&mut events, Some(Duration::from_secs(5)))?;
+        for event in events.iter() {
+            for line in readers.handle_event(poll.registry(), event)? {
+                lines.push((event.token(), line));
+            }
+        }
+    }
+    lines.sort();
+    assert_eq!(lines, vec![(Token(1), big), (Token(2), "2\n".to_string())]);
+    assert!(readers.get(Token(1)).is_none());
+    let reader = readers.remove(poll.registry(), Token(2))?;
+    assert!(reader.is_some());
+    assert!(readers.is_empty());
+    drop(writer2);
+    Ok(())
+}
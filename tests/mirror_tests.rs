@@ -0,0 +1,49 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::fs;
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_mirror() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("lineriver-mirror-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let script = ScriptedReader::new().data(b"1\n2\n").data(b"3");
+    let mut mirror = LineMirror::open(
+        LineReader::from_nonblocking(script)?,
+        &path,
+        SyncPolicy::EveryBatch,
+    )?;
+    mirror.read_once()?;
+    assert_eq!(fs::read_to_string(&path)?, "1\n2\n");
+    assert_eq!(mirror.lines_get(), vec!["1\n", "2\n"]);
+    mirror.read_once()?;
+    mirror.read_once()?;
+    assert!(mirror.eof());
+    assert_eq!(mirror.lines_get(), vec!["3"]);
+    assert_eq!(fs::read_to_string(&path)?, "1\n2\n3");
+    fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test_log::test]
+fn test_mirror_sync_error() -> Result<()> {
+    // Syncing /dev/null fails, after the lines are written:
+    let file = fs::OpenOptions::new().write(true).open("/dev/null")?;
+    let script = ScriptedReader::new().data(b"1\n2\n");
+    let mut mirror = LineMirror::new(
+        LineReader::from_nonblocking(script)?,
+        file,
+        SyncPolicy::EveryBatch,
+    );
+    mirror.read_once()?;
+    assert!(!mirror.has_lines());
+    assert!(mirror.read_once().is_err());
+    assert!(mirror.lines_get().is_empty());
+    Ok(())
+}
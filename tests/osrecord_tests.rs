@@ -0,0 +1,54 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::ffi::OsString;
+use std::io::Write;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::net::UnixStream;
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_osrecord_nul() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"dir/a\0dir/\xff")
+        .would_block()
+        .data(b"b\0c\nd")
+        .eof();
+    let mut reader = OsRecordReader::from_nonblocking(script).with_nul_terminator();
+    reader.read_once()?;
+    assert_eq!(reader.records_get(), vec![OsString::from("dir/a")]);
+    assert!(reader.read_once()?);
+    assert!(!reader.has_records());
+    while reader.read_once()? {}
+    assert!(reader.eof());
+    assert_eq!(
+        reader.records_get(),
+        vec![
+            OsString::from_vec(b"dir/\xffb".to_vec()),
+            OsString::from("c\nd")
+        ]
+    );
+    Ok(())
+}
+
+#[test_log::test]
+fn test_osrecord_newline_paths() -> Result<()> {
+    let (mut tx, rx) = UnixStream::pair()?;
+    let mut reader = OsRecordReader::new(rx)?;
+    tx.write_all(b"/tmp/x\n/tmp/y\n")?;
+    reader.read_available()?;
+    let paths = reader.paths_get();
+    assert_eq!(
+        paths,
+        vec![std::path::PathBuf::from("/tmp/x"), "/tmp/y".into()]
+    );
+    drop(tx);
+    while reader.read_once()? {}
+    assert!(!reader.has_records());
+    Ok(())
+}
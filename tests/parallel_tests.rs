@@ -0,0 +1,47 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "rayon")]
+
+use std::io;
+use std::sync::Mutex;
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_par_for_each_line() -> Result<()> {
+    let mut script = ScriptedReader::new();
+    for i in 0..1000 {
+        script = script.data(format!("{}\n", i).as_bytes());
+        if i % 7 == 0 {
+            script = script.would_block();
+        }
+    }
+    let mut reader = LineReader::from_nonblocking(script)?;
+    let seen = Mutex::new(Vec::new());
+    par_for_each_line(&mut reader, 64, |line| {
+        seen.lock()
+            .unwrap()
+            .push(line.trim_end().parse::<usize>().unwrap());
+    })?;
+    assert!(reader.eof());
+    let mut seen = seen.into_inner().unwrap();
+    seen.sort();
+    assert_eq!(seen, (0..1000).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_par_for_each_line_error() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"1\n2\n")
+        .error(io::ErrorKind::ConnectionReset);
+    let mut reader = LineReader::from_nonblocking(script)?;
+    let err = par_for_each_line(&mut reader, 1, |_| {}).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+    Ok(())
+}
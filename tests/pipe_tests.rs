@@ -0,0 +1,36 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::thread;
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+fn is_nonblocking(fd: std::os::fd::RawFd) -> bool {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    flags & libc::O_NONBLOCK != 0
+}
+
+#[test_log::test]
+fn test_pipe() -> Result<()> {
+    let (mut writer, mut reader) = pipe()?;
+    assert!(is_nonblocking(reader.as_raw_fd()));
+    assert!(!is_nonblocking(writer.as_raw_fd()));
+    reader.read_once()?;
+    assert!(!reader.has_lines());
+    let producer = thread::spawn(move || -> std::io::Result<()> {
+        for i in 0..100 {
+            writeln!(writer, "{}", i)?;
+        }
+        Ok(())
+    });
+    let lines = reader.collect_all(None)?;
+    producer.join().unwrap()?;
+    assert_eq!(lines.len(), 100);
+    assert_eq!(lines[99], "99\n");
+    Ok(())
+}
@@ -0,0 +1,75 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use proptest::prelude::*;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+/// Strings with plenty of newlines.
+fn input_strategy() -> impl Strategy<Value = String> {
+    proptest::collection::vec(prop_oneof![3 => any::<char>(), 1 => Just('\n')], 0..256)
+        .prop_map(|chars| chars.into_iter().collect())
+}
+
+/// Splits the input in chunks of the given sizes, cycling over them;
+/// chunk boundaries can fall in the middle of UTF-8 sequences.
+fn script_for(input: &[u8], sizes: &[usize], eof: bool) -> ScriptedReader {
+    let mut script = ScriptedReader::new();
+    let mut rest = input;
+    for size in sizes.iter().cycle() {
+        if rest.is_empty() {
+            break;
+        }
+        let (chunk, tail) = rest.split_at((*size).min(rest.len()));
+        script = script.data(chunk).would_block();
+        rest = tail;
+    }
+    if eof {
+        script.eof()
+    } else {
+        script.would_block()
+    }
+}
+
+fn read_all(script: ScriptedReader, reads: usize) -> Vec<String> {
+    let mut reader = LineReader::from_nonblocking(script).unwrap();
+    for _ in 0..reads {
+        reader.read_once().unwrap();
+    }
+    reader.lines_get()
+}
+
+proptest! {
+    #[test]
+    fn prop_lines_concat_to_input(
+        input in input_strategy(),
+        sizes in proptest::collection::vec(1_usize..64, 1..8),
+    ) {
+        let script = script_for(input.as_bytes(), &sizes, true);
+        let reads = script.remaining() + 1;
+        let lines = read_all(script, reads);
+        prop_assert_eq!(lines.concat(), input);
+        if let Some((_, complete)) = lines.split_last() {
+            prop_assert!(complete.iter().all(|l| l.ends_with('\n')));
+        }
+        prop_assert!(lines.iter().all(|l| !l.is_empty()));
+    }
+
+    #[test]
+    fn prop_partial_is_kept(
+        input in input_strategy(),
+        sizes in proptest::collection::vec(1_usize..64, 1..8),
+    ) {
+        let script = script_for(input.as_bytes(), &sizes, false);
+        let reads = script.remaining();
+        let lines = read_all(script, reads);
+        prop_assert!(lines.iter().all(|l| l.ends_with('\n')));
+        // The remaining partial data is everything after the last
+        // newline:
+        let concat = lines.concat();
+        prop_assert!(input.starts_with(&concat));
+        prop_assert!(!input[concat.len()..].contains('\n'));
+    }
+}
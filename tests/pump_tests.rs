@@ -0,0 +1,95 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::thread;
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+fn pump_all<R, W>(pump: &mut LinePump<R, W>) -> Result<()>
+where
+    R: Read + std::os::fd::AsRawFd + std::fmt::Debug,
+    W: Write + std::os::fd::AsRawFd + std::fmt::Debug,
+{
+    while pump.pump_once()? {
+        thread::yield_now();
+    }
+    Ok(())
+}
+
+#[test_log::test]
+fn test_pump_sockets() -> Result<()> {
+    let (mut input, src) = UnixStream::pair()?;
+    let (dst, mut output) = UnixStream::pair()?;
+    let mut pump = LinePump::new(src, dst)?;
+    #[cfg(target_os = "linux")]
+    assert!(pump.is_zero_copy());
+    input.write_all(b"1\n2\n3")?;
+    drop(input);
+    pump_all(&mut pump)?;
+    assert!(pump.eof());
+    assert_eq!(pump.lines(), 3);
+    assert_eq!(pump.bytes(), 5);
+    drop(pump);
+    let mut received = String::new();
+    output.read_to_string(&mut received)?;
+    assert_eq!(received, "1\n2\n3");
+    Ok(())
+}
+
+#[test_log::test]
+fn test_pump_pipes_large() -> Result<()> {
+    let (src, mut input) = std::io::pipe()?;
+    let (mut output, dst) = std::io::pipe()?;
+    let data = (0..100000)
+        .map(|i| format!("line {}\n", i))
+        .collect::<String>();
+    let expected = data.clone();
+    let writer = thread::spawn(move || input.write_all(data.as_bytes()));
+    let reader = thread::spawn(move || {
+        let mut received = String::new();
+        output.read_to_string(&mut received).map(|_| received)
+    });
+    let mut pump = LinePump::new(src, dst)?;
+    pump_all(&mut pump)?;
+    assert_eq!(pump.lines(), 100000);
+    assert_eq!(pump.bytes(), expected.len() as u64);
+    drop(pump);
+    writer.join().unwrap()?;
+    assert_eq!(reader.join().unwrap()?, expected);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_pump_no_line_count() -> Result<()> {
+    let (mut input, src) = UnixStream::pair()?;
+    let (dst, mut output) = UnixStream::pair()?;
+    let mut pump = LinePump::new(src, dst)?.with_line_count(false);
+    input.write_all(b"1\n2\n")?;
+    drop(input);
+    pump_all(&mut pump)?;
+    assert_eq!(pump.lines(), 0);
+    assert_eq!(pump.bytes(), 4);
+    drop(pump);
+    let mut received = String::new();
+    output.read_to_string(&mut received)?;
+    assert_eq!(received, "1\n2\n");
+    Ok(())
+}
+
+#[test_log::test]
+fn test_pump_pending() -> Result<()> {
+    let (mut input, src) = UnixStream::pair()?;
+    let (dst, _output) = UnixStream::pair()?;
+    let mut pump = LinePump::new(src, dst)?;
+    assert!(pump.pump_once()?);
+    input.write_all(b"1\n")?;
+    assert!(pump.pump_once()?);
+    assert_eq!(pump.lines(), 1);
+    assert!(!pump.eof());
+    Ok(())
+}
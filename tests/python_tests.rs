@@ -0,0 +1,40 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "python")]
+
+use std::ffi::CString;
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
+
+use color_eyre::Result;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+#[test_log::test]
+fn test_python() -> Result<()> {
+    let (mut writer, reader) = UnixStream::pair()?;
+    writer.write_all(b"1\n2\n3")?;
+    drop(writer);
+    pyo3::prepare_freethreaded_python();
+    let lines = Python::with_gil(|py| -> PyResult<Vec<String>> {
+        let module = PyModule::new(py, "lineriver")?;
+        ::lineriver::python::lineriver(&module)?;
+        let locals = PyDict::new(py);
+        locals.set_item("lineriver", module)?;
+        locals.set_item("fd", reader.as_raw_fd())?;
+        let code = CString::new(
+            "reader = lineriver.LineReader(fd)\n\
+             lines = []\n\
+             while not reader.eof():\n    \
+                 reader.read_available()\n    \
+                 lines.extend(reader.lines())\n",
+        )?;
+        py.run(&code, None, Some(&locals))?;
+        locals.get_item("lines")?.unwrap().extract()
+    })?;
+    assert_eq!(lines, vec!["1\n", "2\n", "3"]);
+    Ok(())
+}
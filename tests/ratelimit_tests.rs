@@ -0,0 +1,73 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+fn limiter_for(
+    input: &[u8],
+    policy: OverflowPolicy,
+) -> Result<LineRateLimiter<LineReader<ScriptedReader>>> {
+    let script = ScriptedReader::new().data(input).eof();
+    Ok(LineRateLimiter::new(
+        LineReader::from_nonblocking(script)?,
+        policy,
+    ))
+}
+
+#[test_log::test]
+fn test_ratelimit_buffer() -> Result<()> {
+    let mut limiter =
+        limiter_for(b"1\n2\n3\n4\n", OverflowPolicy::Buffer)?.with_lines_per_sec(20.0);
+    limiter.read_available()?;
+    // The initial burst is one second worth of lines:
+    assert_eq!(limiter.lines_get().len(), 4);
+    let mut limiter = limiter_for(b"1\n2\n3\n4\n", OverflowPolicy::Buffer)?.with_lines_per_sec(2.0);
+    limiter.read_once()?;
+    assert_eq!(limiter.lines_get(), vec!["1\n", "2\n"]);
+    assert_eq!(limiter.queued(), 2);
+    assert!(limiter.read_once()?);
+    assert!(!limiter.eof());
+    std::thread::sleep(Duration::from_millis(600));
+    assert_eq!(limiter.lines_get(), vec!["3\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_ratelimit_drop() -> Result<()> {
+    let mut limiter = limiter_for(b"1\n22\n333\n", OverflowPolicy::Drop)?.with_bytes_per_sec(5.0);
+    limiter.read_once()?;
+    assert_eq!(limiter.lines_get(), vec!["1\n", "22\n"]);
+    assert_eq!(limiter.dropped(), 1);
+    assert_eq!(limiter.queued(), 0);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_ratelimit_error() -> Result<()> {
+    let mut limiter = limiter_for(b"1\n2\n", OverflowPolicy::Error)?.with_lines_per_sec(1.0);
+    limiter.read_once()?;
+    assert!(limiter.read_once().is_err());
+    assert_eq!(limiter.lines_get(), vec!["1\n"]);
+    assert_eq!(limiter.queued(), 1);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_ratelimit_invalid_rate() -> Result<()> {
+    for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        let limiter = limiter_for(b"", OverflowPolicy::Buffer)?;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| limiter.with_lines_per_sec(rate)));
+        assert!(result.is_err(), "rate {} accepted", rate);
+        let limiter = limiter_for(b"", OverflowPolicy::Buffer)?;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| limiter.with_bytes_per_sec(rate)));
+        assert!(result.is_err(), "rate {} accepted", rate);
+    }
+    Ok(())
+}
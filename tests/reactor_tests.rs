@@ -0,0 +1,165 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "polling")]
+
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_reactor() -> Result<()> {
+    let mut reactor = Reactor::new()?;
+    let (mut writer1, stream1) = UnixStream::pair()?;
+    let (mut writer2, stream2) = UnixStream::pair()?;
+    let key1 = reactor.add(LineReader::new(stream1)?)?;
+    let key2 = reactor.add(LineReader::new(stream2)?)?;
+    assert_eq!(reactor.len(), 2);
+    assert!(reactor.wait(Some(Duration::from_millis(10)))?.is_empty());
+    writer2.write_all(b"2a\n2b")?;
+    let events = reactor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [ReactorEvent::Lines(k)] if k == key2));
+    assert_eq!(reactor[key2].lines_get(), vec!["2a\n"]);
+    writer1.write_all(b"1a\n")?;
+    drop(writer1);
+    let mut eof = false;
+    let mut lines = vec![];
+    while !eof {
+        for event in reactor.wait(Some(Duration::from_secs(5)))? {
+            match event {
+                ReactorEvent::Lines(key) => lines.extend(reactor[key].lines_get()),
+                ReactorEvent::Eof(key) => {
+                    assert_eq!(key, key1);
+                    eof = true;
+                }
+                event => panic!("unexpected event {:?}", event),
+            }
+        }
+    }
+    assert_eq!(lines, vec!["1a\n"]);
+    assert!(reactor.remove(key1)?.is_some());
+    assert!(reactor.remove(key1)?.is_none());
+    writer2.write_all(b"\n")?;
+    let events = reactor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [ReactorEvent::Lines(k)] if k == key2));
+    assert_eq!(reactor[key2].lines_get(), vec!["2b\n"]);
+    Ok(())
+}
+
+#[cfg(feature = "signal")]
+#[test_log::test]
+fn test_reactor_signal() -> Result<()> {
+    let mut reactor = Reactor::<LineReader<UnixStream>>::new()?;
+    reactor.add_signal(libc::SIGUSR1)?;
+    assert!(reactor.wait(Some(Duration::from_millis(10)))?.is_empty());
+    unsafe { libc::raise(libc::SIGUSR1) };
+    unsafe { libc::raise(libc::SIGUSR1) };
+    let events = reactor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [ReactorEvent::Signal(libc::SIGUSR1)]));
+    assert!(reactor.wait(Some(Duration::from_millis(10)))?.is_empty());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_reactor_drain() -> Result<()> {
+    let mut reactor = Reactor::new()?;
+    let (mut writer1, stream1) = UnixStream::pair()?;
+    let (mut writer2, stream2) = UnixStream::pair()?;
+    let key1 = reactor.add(LineReader::new(stream1)?)?;
+    let key2 = reactor.add(LineReader::new(stream2)?)?;
+    writer1.write_all(b"1a\n1b")?;
+    drop(writer1);
+    writer2.write_all(b"2a\n2b")?;
+    let start = std::time::Instant::now();
+    let lines = reactor.drain(start + Duration::from_millis(200))?;
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    assert_eq!(lines[&key1], vec!["1a\n", "1b"]);
+    assert_eq!(lines[&key2], vec!["2a\n"]);
+    drop(writer2);
+    let lines = reactor.drain(std::time::Instant::now() + Duration::from_secs(5))?;
+    assert_eq!(lines[&key2], vec!["2b"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_reactor_waker() -> Result<()> {
+    let mut reactor = Reactor::<LineReader<UnixStream>>::new()?;
+    let (tx, rx) = std::sync::mpsc::channel();
+    let waker = reactor.waker();
+    let requester = std::thread::spawn(move || -> Result<UnixStream> {
+        let (writer, stream) = UnixStream::pair()?;
+        tx.send(stream)?;
+        waker.wake()?;
+        Ok(writer)
+    });
+    let events = reactor.wait(None)?;
+    assert!(matches!(events[..], [ReactorEvent::Wakeup]));
+    let key = reactor.add(LineReader::new(rx.recv()?)?)?;
+    let mut writer = requester.join().unwrap()?;
+    writer.write_all(b"added\n")?;
+    let events = reactor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [ReactorEvent::Lines(k)] if k == key));
+    // A wakeup before the wait is not lost:
+    reactor.waker().wake()?;
+    let events = reactor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [ReactorEvent::Wakeup, ..]));
+    // Waking a dropped reactor does nothing:
+    let waker = reactor.waker();
+    drop(reactor);
+    waker.wake()?;
+    Ok(())
+}
+
+#[test_log::test]
+fn test_reactor_throttle() -> Result<()> {
+    let mut reactor = Reactor::new()?;
+    let (mut writer, stream) = UnixStream::pair()?;
+    let key = reactor.add(LineReader::new(stream)?.with_max_queued_lines(1))?;
+    writer.write_all(b"1\n")?;
+    let events = reactor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [ReactorEvent::Lines(k)] if k == key));
+    assert!(reactor.is_throttled(key));
+    assert!(reactor[key].is_full());
+    // Not polled while full, even with data available:
+    writer.write_all(b"2\n")?;
+    assert!(reactor.wait(Some(Duration::from_millis(50)))?.is_empty());
+    assert_eq!(reactor[key].lines_get(), vec!["1\n"]);
+    // Polled again once drained:
+    let events = reactor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [ReactorEvent::Lines(k)] if k == key));
+    assert_eq!(reactor[key].lines_get(), vec!["2\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_reactor_throttle_wrapped() -> Result<()> {
+    let mut reactor = Reactor::new()?;
+    let (mut writer, stream) = UnixStream::pair()?;
+    let reader = LineReader::new(stream)?
+        .with_max_queued_lines(1)
+        .filter_lines(|line| line != "skip\n");
+    let key = reactor.add(reader)?;
+    writer.write_all(b"1\n")?;
+    let events = reactor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [ReactorEvent::Lines(k)] if k == key));
+    // The wrapper took the line, and stays full until it is retrieved:
+    assert!(reactor.is_throttled(key));
+    assert!(reactor[key].is_full());
+    writer.write_all(b"2\n")?;
+    assert!(reactor.wait(Some(Duration::from_millis(50)))?.is_empty());
+    assert_eq!(reactor[key].lines_get(), vec!["1\n"]);
+    assert!(!reactor[key].is_full());
+    let events = reactor.wait(Some(Duration::from_secs(5)))?;
+    assert!(matches!(events[..], [ReactorEvent::Lines(k)] if k == key));
+    assert_eq!(reactor[key].lines_get(), vec!["2\n"]);
+    // Lines that are filtered out don't keep the wrapper full:
+    writer.write_all(b"skip\n")?;
+    assert!(reactor.wait(Some(Duration::from_secs(5)))?.is_empty());
+    assert!(!reactor[key].is_full());
+    Ok(())
+}
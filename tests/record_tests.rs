@@ -0,0 +1,100 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+const TRACEBACK: &[u8] = b"2024 start\n\
+      Traceback:\n  File a\n  File b\n\
+      2024 next\n";
+
+fn is_start(line: &str) -> bool {
+    line.starts_with("2024")
+}
+
+#[test_log::test]
+fn test_record_traceback() -> Result<()> {
+    let script = ScriptedReader::new().data(TRACEBACK).eof();
+    let mut records = RecordAssembler::new(LineReader::from_nonblocking(script)?, is_start);
+    records.read_once()?;
+    assert_eq!(
+        records.lines_get(),
+        vec!["2024 start\nTraceback:\n  File a\n  File b\n"]
+    );
+    // The last record is only complete at EOF:
+    assert!(!records.has_lines());
+    records.read_once()?;
+    assert!(records.eof());
+    assert_eq!(records.lines_get(), vec!["2024 next\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_record_max_size() -> Result<()> {
+    let script = ScriptedReader::new().data(TRACEBACK).eof();
+    let mut records =
+        RecordAssembler::new(LineReader::from_nonblocking(script)?, is_start).with_max_size(24);
+    records.read_once()?;
+    records.read_once()?;
+    assert_eq!(
+        records.lines_get(),
+        vec![
+            "2024 start\nTraceback:\n",
+            "  File a\n  File b\n",
+            "2024 next\n"
+        ]
+    );
+    Ok(())
+}
+
+#[test_log::test]
+fn test_record_timeout() -> Result<()> {
+    let (mut wr, rd) = UnixStream::pair()?;
+    let mut records = RecordAssembler::new(LineReader::new(rd)?, is_start)
+        .with_timeout(Duration::from_millis(10));
+    wr.write_all(b"2024 alone\n  continued\n")?;
+    records.read_once()?;
+    assert!(!records.has_lines());
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(records.lines_get(), vec!["2024 alone\n  continued\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_record_prefix() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"header\n--- a\nb\n--- c\n")
+        .would_block()
+        .data(b"d\n")
+        .eof();
+    let linereader = LineReader::from_nonblocking(script)?;
+    let mut records = RecordAssembler::with_prefix(linereader, "--- ");
+    records.read_once()?;
+    assert_eq!(records.lines_get(), vec!["header\n", "--- a\nb\n"]);
+    records.read_once()?;
+    records.read_once()?;
+    // The last record is only complete at EOF:
+    assert!(!records.has_lines());
+    records.read_once()?;
+    assert!(records.eof());
+    assert_eq!(records.lines_get(), vec!["--- c\nd\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_record_chain_delimiter() -> Result<()> {
+    let linereader = LineReader::from_nonblocking(&b"--- a\0b\0"[..])?.with_delimiter(b'\0');
+    let records = RecordAssembler::with_prefix(linereader, "--- ");
+    let second = LineReader::from_nonblocking(&b"--- c\0"[..])?.with_delimiter(b'\0');
+    let mut reader = records.chain(second);
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get(), vec!["--- a\0b\0", "--- c\0"]);
+    Ok(())
+}
@@ -0,0 +1,23 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "redact")]
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_redacted_debug() -> Result<()> {
+    let script = ScriptedReader::new().data(b"password=hunter2\npartial secret");
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    let debug = format!("{:?}", reader);
+    assert!(!debug.contains("hunter2"));
+    assert!(!debug.contains("secret"));
+    assert!(debug.contains("num_lines: 1"));
+    assert!(debug.contains("used: 14"));
+    Ok(())
+}
@@ -0,0 +1,87 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io;
+use std::time::Duration;
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+fn record(script: ScriptedReader) -> Result<(Vec<String>, Vec<u8>)> {
+    let recorder = RecordingReader::new(script, Vec::new())?;
+    let mut reader = LineReader::from_nonblocking(recorder)?;
+    let mut lines = vec![];
+    while reader.read_once()? {
+        lines.extend(reader.lines_get());
+    }
+    lines.extend(reader.lines_get());
+    let recording = reader.get_ref().writer().clone();
+    Ok((lines, recording))
+}
+
+#[test_log::test]
+fn test_replay_same_lines() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"abc")
+        .would_block()
+        .data(b"def\nghi\n")
+        .data(b"jkl")
+        .eof();
+    let (lines, recording) = record(script)?;
+    assert_eq!(lines, vec!["abcdef\n", "ghi\n", "jkl"]);
+    let mut reader = LineReader::from_nonblocking(ReplayReader::from_reader(&recording[..])?)?;
+    let mut replayed = vec![];
+    while reader.read_once()? {
+        replayed.extend(reader.lines_get());
+    }
+    replayed.extend(reader.lines_get());
+    assert_eq!(replayed, lines);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_replay_file() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("lineriver-replay-{}", std::process::id()));
+    let script = ScriptedReader::new().data(b"1\n2\n").eof();
+    let mut reader = LineReader::from_nonblocking(RecordingReader::create(script, &path)?)?;
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get(), vec!["1\n", "2\n"]);
+    let replay = ReplayReader::open(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(replay.remaining(), 2);
+    let mut reader = LineReader::from_nonblocking(replay)?;
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get(), vec!["1\n", "2\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_replay_timing() -> Result<()> {
+    let mut recording = b"LRREC1\n".to_vec();
+    for (micros, chunk) in [(0_u64, &b"a\n"[..]), (50_000, b"b\n"), (50_000, b"")] {
+        recording.extend_from_slice(&micros.to_le_bytes());
+        recording.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        recording.extend_from_slice(chunk);
+    }
+    let replay = ReplayReader::from_reader(&recording[..])?.with_timing(true);
+    let mut reader = LineReader::from_nonblocking(replay)?;
+    assert!(reader.read_once()?);
+    assert_eq!(reader.lines_get(), vec!["a\n"]);
+    assert!(reader.read_once()?);
+    assert!(!reader.has_lines());
+    std::thread::sleep(Duration::from_millis(60));
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["b\n"]);
+    reader.read_once()?;
+    assert!(reader.eof());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_replay_bad_magic() {
+    let err = ReplayReader::from_reader(&b"garbage\n"[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
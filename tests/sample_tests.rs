@@ -0,0 +1,52 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+fn numbers(count: usize) -> Vec<u8> {
+    (0..count)
+        .map(|i| format!("{}\n", i))
+        .collect::<String>()
+        .into_bytes()
+}
+
+#[test_log::test]
+fn test_sample_every_nth() -> Result<()> {
+    let script = ScriptedReader::new().data(&numbers(10));
+    let mut sampler =
+        LineSampler::new(LineReader::from_nonblocking(script)?, Sampling::EveryNth(3));
+    sampler.read_once()?;
+    assert_eq!(sampler.lines_get(), vec!["0\n", "3\n", "6\n", "9\n"]);
+    assert_eq!(sampler.skipped(), 6);
+    Ok(())
+}
+
+fn sample_fraction(input: &[u8], seed: u64) -> Result<(Vec<String>, u64)> {
+    let script = ScriptedReader::new().data(input).eof();
+    let mut sampler = LineSampler::new(
+        LineReader::from_nonblocking(script)?,
+        Sampling::Fraction(0.25),
+    )
+    .with_seed(seed);
+    let mut lines = vec![];
+    while !sampler.eof() {
+        sampler.read_once()?;
+        lines.extend(sampler.lines_get());
+    }
+    Ok((lines, sampler.skipped()))
+}
+
+#[test_log::test]
+fn test_sample_fraction() -> Result<()> {
+    let input = numbers(10000);
+    let (lines, skipped) = sample_fraction(&input, 42)?;
+    assert_eq!(lines.len() as u64 + skipped, 10000);
+    assert!((2000..3000).contains(&lines.len()));
+    // Same seed, same sample:
+    assert_eq!(sample_fraction(&input, 42)?.0, lines);
+    Ok(())
+}
@@ -0,0 +1,42 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::Write;
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::thread;
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+#[test_log::test]
+fn test_send_sync() {
+    assert_send::<LineReader<UnixStream>>();
+    assert_sync::<LineReader<UnixStream>>();
+    assert_send::<SharedLineReader<LineReader<UnixStream>>>();
+    assert_sync::<SharedLineReader<LineReader<UnixStream>>>();
+}
+
+#[test_log::test]
+fn test_shared_threads() -> Result<()> {
+    let (mut wr, rd) = UnixStream::pair()?;
+    let shared = SharedLineReader::new(LineReader::new(rd)?);
+    let poller = shared.clone();
+    let handle = thread::spawn(move || -> std::io::Result<()> {
+        while !poller.eof() {
+            poller.read_available()?;
+        }
+        Ok(())
+    });
+    wr.write_all(b"1\n2\n3")?;
+    wr.shutdown(Shutdown::Write)?;
+    handle.join().expect("poller thread panicked")?;
+    assert_eq!(shared.lines_get(), vec!["1\n", "2\n", "3"]);
+    assert!(shared.lock().lines_get().is_empty());
+    Ok(())
+}
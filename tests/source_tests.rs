@@ -0,0 +1,58 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::mpsc;
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_source_closure() -> Result<()> {
+    let mut script = VecDeque::from([Fill::Data(2), Fill::Pending, Fill::Data(3), Fill::Eof]);
+    let source = move |buf: &mut [u8]| {
+        let fill = script.pop_front().unwrap_or(Fill::Eof);
+        if let Fill::Data(len) = fill {
+            buf[..len].fill(b'x');
+            buf[len - 1] = b'\n';
+        }
+        Ok(fill)
+    };
+    let mut readers: Vec<Box<dyn LineRead>> = vec![Box::new(LineReader::from_source(source)?)];
+    let reader = &mut readers[0];
+    reader.read_once()?;
+    assert_eq!(reader.lines_get(), vec!["x\n"]);
+    assert!(reader.read_once()?);
+    assert!(!reader.has_lines());
+    while reader.read_once()? {}
+    assert!(reader.eof());
+    assert_eq!(reader.lines_get(), vec!["xx\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_source_channel() -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let mut reader = LineReader::from_source(ChannelSource::new(receiver))?;
+    let producer = std::thread::spawn(move || {
+        for chunk in [&b"ab"[..], b"c\nd", b"\n"] {
+            sender.send(chunk.to_vec()).unwrap();
+        }
+    });
+    producer.join().unwrap();
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get(), vec!["abc\n", "d\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_source_error() -> Result<()> {
+    let source = |_: &mut [u8]| Err(io::Error::from(io::ErrorKind::ConnectionAborted));
+    let mut reader = LineReader::from_source(source)?;
+    let err = reader.read_once().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionAborted);
+    Ok(())
+}
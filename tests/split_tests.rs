@@ -0,0 +1,73 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::{self, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_split_threads() -> Result<()> {
+    let (mut tx, rx) = UnixStream::pair()?;
+    let (mut read_half, lines_half) = LineReader::new(rx)?.split();
+    let consumer = std::thread::spawn(move || lines_half.collect::<Vec<_>>());
+    tx.write_all(b"1\n2")?;
+    read_half.read_once()?;
+    tx.write_all(b"\n3\n")?;
+    drop(tx);
+    while read_half.read_once()? {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(read_half.eof());
+    assert_eq!(consumer.join().unwrap(), vec!["1\n", "2\n", "3\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_split_queued_lines() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"a\nb\n")
+        .would_block()
+        .data(b"c");
+    let mut reader = LineReader::from_nonblocking(script)?;
+    reader.read_once()?;
+    let (mut read_half, mut lines_half) = reader.split();
+    assert_eq!(lines_half.lines_get(), vec!["a\n", "b\n"]);
+    assert!(read_half.read_once()?);
+    assert_eq!(lines_half.next_timeout(Duration::from_millis(1)), None);
+    read_half.read_once()?;
+    read_half.read_once()?;
+    assert_eq!(lines_half.next(), Some("c".to_string()));
+    assert_eq!(lines_half.next(), None);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_split_lines_half_dropped() -> Result<()> {
+    let script = ScriptedReader::new().data(b"a\n");
+    let (mut read_half, lines_half) = LineReader::from_nonblocking(script)?.split();
+    drop(lines_half);
+    let err = read_half.read_once().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_split_read_error() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"a\nb")
+        .error(io::ErrorKind::ConnectionReset);
+    let (mut read_half, lines_half) = LineReader::from_nonblocking(script)?.split();
+    let consumer = std::thread::spawn(move || lines_half.collect::<Vec<_>>());
+    read_half.read_once()?;
+    let err = read_half.read_once().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+    assert!(!read_half.read_once()?);
+    assert_eq!(consumer.join().unwrap(), vec!["a\n"]);
+    Ok(())
+}
@@ -0,0 +1,39 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_splitter_matches_linereader() -> Result<()> {
+    let input = b"1\n22\n\n333\r\n4444";
+    for chunk in 1..input.len() {
+        let mut splitter = LineSplitter::new();
+        for data in input.chunks(chunk) {
+            splitter.push(data)?;
+        }
+        splitter.finish()?;
+        let mut reader = LineReader::from_nonblocking(ScriptedReader::new().data(input))?;
+        while reader.read_once()? {}
+        assert_eq!(splitter.lines_get(), reader.lines_get());
+    }
+    Ok(())
+}
+
+#[test_log::test]
+fn test_splitter_invalid_utf8() -> Result<()> {
+    let mut splitter = LineSplitter::new();
+    assert!(splitter.push(b"1\n\xff\n2\n3").is_err());
+    assert_eq!(splitter.pop_line().as_deref(), Some("1\n"));
+    assert_eq!(splitter.pop_line().as_deref(), Some("2\n"));
+    assert!(!splitter.has_lines());
+    assert_eq!(splitter.partial(), b"3");
+    splitter.finish()?;
+    assert_eq!(splitter.lines_get(), vec!["3"]);
+    splitter.finish()?;
+    assert!(!splitter.has_lines());
+    Ok(())
+}
@@ -0,0 +1,47 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::time::Duration;
+
+use color_eyre::{eyre::eyre, Result};
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+#[test_log::test]
+fn test_histogram() {
+    let mut histogram = Histogram::default();
+    assert_eq!(histogram.mean(), None);
+    assert_eq!(histogram.quantile(0.5), None);
+    for value in [0, 1, 2, 3, 100] {
+        histogram.record(value);
+    }
+    assert_eq!(histogram.count(), 5);
+    assert_eq!(histogram.sum(), 106);
+    assert_eq!(histogram.max(), 100);
+    assert_eq!(
+        histogram.buckets().collect::<Vec<_>>(),
+        vec![(0, 1), (1, 1), (3, 2), (127, 1)]
+    );
+    assert_eq!(histogram.quantile(0.5), Some(3));
+    assert_eq!(histogram.quantile(1.0), Some(100));
+    assert_eq!(histogram.quantile(0.0), Some(0));
+}
+
+#[test_log::test]
+fn test_line_stats() -> Result<()> {
+    let script = ScriptedReader::new().data(b"1\n22\n333\n");
+    let mut reader = LineReader::from_nonblocking(script)?.with_stats();
+    reader.read_once()?;
+    std::thread::sleep(Duration::from_millis(5));
+    reader.lines_get();
+    let stats = reader.stats().ok_or_else(|| eyre!("no stats"))?;
+    assert_eq!(stats.line_len.count(), 3);
+    assert_eq!(stats.line_len.sum(), 9);
+    assert_eq!(stats.latency_us.count(), 3);
+    assert!(stats.latency_us.max() >= 5000);
+    let reader = LineReader::from_nonblocking(ScriptedReader::new())?;
+    assert!(reader.stats().is_none());
+    Ok(())
+}
@@ -0,0 +1,49 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::env;
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::os::fd::IntoRawFd;
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+// Single test, as it manipulates the environment and fd 3.
+#[test_log::test]
+fn test_listen_fds() -> Result<()> {
+    env::set_var("LISTEN_PID", "1");
+    env::set_var("LISTEN_FDS", "1");
+    assert!(listen_fds(false)?.is_empty());
+    if unsafe { libc::fcntl(3, libc::F_GETFD) } >= 0 {
+        eprintln!("fd 3 already in use, skipping");
+        return Ok(());
+    }
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    // Fd 3 is now owned by systemd's side of the protocol:
+    let fd = listener.into_raw_fd();
+    if fd != 3 {
+        assert_eq!(unsafe { libc::dup2(fd, 3) }, 3);
+        unsafe { libc::close(fd) };
+    }
+    env::set_var("LISTEN_PID", std::process::id().to_string());
+    env::set_var("LISTEN_FDNAMES", "control");
+    let mut fds = listen_fds(true)?;
+    assert!(env::var("LISTEN_FDS").is_err());
+    assert_eq!(fds.len(), 1);
+    let fd = fds.remove(0);
+    assert_eq!(fd.name(), "control");
+    let listener = fd.into_tcp_listener()?;
+    assert_eq!(listener.local_addr()?, addr);
+    let mut client = TcpStream::connect(addr)?;
+    client.write_all(b"hello\n")?;
+    drop(client);
+    let (stream, _) = listener.accept()?;
+    let mut reader = LineReader::new(stream)?;
+    while reader.read_once()? {}
+    assert_eq!(reader.lines_get(), vec!["hello\n"]);
+    Ok(())
+}
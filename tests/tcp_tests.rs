@@ -0,0 +1,73 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::Read;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::os::fd::AsRawFd;
+use std::time::Duration;
+
+use color_eyre::Result;
+
+use ::lineriver::*;
+
+fn getsockopt(stream: &TcpStream, level: libc::c_int, name: libc::c_int) -> libc::c_int {
+    let mut value: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let r = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            level,
+            name,
+            &mut value as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    assert_eq!(r, 0);
+    value
+}
+
+#[test_log::test]
+fn test_tcp_options() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let stream = TcpOptions::new().connect(listener.local_addr()?)?;
+    assert!(stream.nodelay()?);
+    assert_eq!(getsockopt(&stream, libc::SOL_SOCKET, libc::SO_KEEPALIVE), 0);
+    let options = TcpOptions::new()
+        .with_nodelay(false)
+        .with_keepalive(Some(Duration::from_secs(30)))
+        .with_connect_timeout(Some(Duration::from_secs(5)));
+    let stream = options.connect(listener.local_addr()?)?;
+    assert!(!stream.nodelay()?);
+    assert_ne!(getsockopt(&stream, libc::SOL_SOCKET, libc::SO_KEEPALIVE), 0);
+    #[cfg(target_os = "linux")]
+    assert_eq!(
+        getsockopt(&stream, libc::IPPROTO_TCP, libc::TCP_KEEPIDLE),
+        30
+    );
+    Ok(())
+}
+
+#[test_log::test]
+fn test_tcp_options_refused() -> Result<()> {
+    let addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+    let options = TcpOptions::new().with_connect_timeout(Some(Duration::from_secs(5)));
+    assert!(options.connect(addr).is_err());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_tcp_options_forwarder() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let mut forwarder =
+        LineForwarder::tcp_with_options(listener.local_addr()?, &TcpOptions::new())?;
+    let (mut server, _) = listener.accept()?;
+    forwarder.send_line("1")?;
+    assert!(forwarder.flush()?);
+    drop(forwarder);
+    let mut received = String::new();
+    server.read_to_string(&mut received)?;
+    assert_eq!(received, "1\n");
+    server.shutdown(Shutdown::Both)?;
+    Ok(())
+}
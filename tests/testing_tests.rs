@@ -0,0 +1,90 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::io::{ErrorKind, Read};
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::{LineRead, LineReadExt};
+
+#[test_log::test]
+fn test_scripted_reader_split_data() -> Result<()> {
+    let mut script = ScriptedReader::new().data(b"abcde").eof();
+    let mut buf = [0_u8; 2];
+    assert_eq!(script.read(&mut buf)?, 2);
+    assert_eq!(&buf, b"ab");
+    assert_eq!(script.read(&mut buf)?, 2);
+    assert_eq!(&buf, b"cd");
+    assert_eq!(script.read(&mut buf)?, 1);
+    assert_eq!(&buf[..1], b"e");
+    assert_eq!(script.remaining(), 1);
+    assert_eq!(script.read(&mut buf)?, 0);
+    assert_eq!(script.read(&mut buf)?, 0);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_scripted_reader_errors() {
+    let mut script: ScriptedReader = [
+        ScriptEvent::WouldBlock,
+        ScriptEvent::Interrupted,
+        ScriptEvent::Error(ErrorKind::BrokenPipe),
+    ]
+    .into_iter()
+    .collect();
+    let mut buf = [0_u8; 2];
+    let kinds = (0..3)
+        .map(|_| script.read(&mut buf).map_err(|e| e.kind()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            Err(ErrorKind::WouldBlock),
+            Err(ErrorKind::Interrupted),
+            Err(ErrorKind::BrokenPipe)
+        ]
+    );
+}
+
+#[test_log::test]
+fn test_mock_line_read() {
+    let mut mock = MockLineRead::new()
+        .lines(&["1\n", "2\n"])
+        .pending()
+        .error(ErrorKind::ConnectionReset)
+        .line("3")
+        .with_label("mock");
+    let reader: &mut dyn LineRead = &mut mock;
+    assert_eq!(reader.label(), Some("mock"));
+    assert!(reader.read_once().unwrap());
+    assert_eq!(reader.lines_get(), vec!["1\n", "2\n"]);
+    assert!(reader.read_once().unwrap());
+    assert!(!reader.has_lines());
+    let err = reader.read_once().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConnectionReset);
+    reader.read_available().unwrap();
+    assert_eq!(reader.lines_get(), vec!["3"]);
+    assert!(reader.read_once().unwrap());
+    assert!(reader.eof());
+    assert!(!reader.read_once().unwrap());
+    assert_eq!(mock.reads(), 5);
+    assert_eq!(mock.remaining(), 0);
+}
+
+#[test_log::test]
+fn test_pop_line() -> Result<()> {
+    let mut mock = MockLineRead::new().lines(&["a\n", "b\n"]).line("c\n");
+    let mut reader = mock.clone().map_lines(|line| line.to_uppercase());
+    assert_eq!(mock.pop_line(), None);
+    mock.read_once()?;
+    assert_eq!(mock.pop_line().as_deref(), Some("a\n"));
+    mock.read_once()?;
+    assert_eq!(mock.lines_get(), vec!["b\n", "c\n"]);
+    reader.read_once()?;
+    assert_eq!(reader.pop_line().as_deref(), Some("A\n"));
+    assert_eq!(reader.pop_line().as_deref(), Some("B\n"));
+    assert_eq!(reader.pop_line(), None);
+    Ok(())
+}
@@ -0,0 +1,73 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+fn epoch_plus(secs: u64, nanos: u32) -> Option<SystemTime> {
+    Some(UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+#[test_log::test]
+fn test_rfc3339() {
+    let format = TimestampFormat::Rfc3339;
+    assert_eq!(
+        format.parse_prefix("2024-02-18T10:20:30Z hello"),
+        epoch_plus(1708251630, 0)
+    );
+    assert_eq!(
+        format.parse_prefix("2024-02-18 11:20:30.5+01:00 hello"),
+        epoch_plus(1708251630, 500_000_000)
+    );
+    assert_eq!(
+        format.parse_prefix("1969-12-31T23:59:59Z"),
+        UNIX_EPOCH.checked_sub(Duration::from_secs(1))
+    );
+    assert_eq!(format.parse_prefix("2024-02-18"), None);
+    assert_eq!(format.parse_prefix("2024-13-18T10:20:30Z"), None);
+    assert_eq!(format.parse_prefix("2024-02-31T10:20:30Z"), None);
+    assert_eq!(format.parse_prefix("2023-04-31T10:20:30Z"), None);
+    assert_eq!(format.parse_prefix("2023-02-29T10:20:30Z"), None);
+    assert_eq!(format.parse_prefix("1900-02-29T10:20:30Z"), None);
+    assert_eq!(
+        format.parse_prefix("2024-02-29T00:00:00Z"),
+        epoch_plus(1709164800, 0)
+    );
+    assert!(format.parse_prefix("2000-02-29T00:00:00Z").is_some());
+    assert_eq!(format.parse_prefix("hello"), None);
+}
+
+#[test_log::test]
+fn test_strftime() {
+    let format = TimestampFormat::Strftime("[%F %T.%f %z]".into());
+    assert_eq!(
+        format.parse_prefix("[2024-02-18 10:20:30.25 -0100] x"),
+        epoch_plus(1708251630 + 3600, 250_000_000)
+    );
+    let format = TimestampFormat::Strftime("%b %e %H:%M:%S".into());
+    assert_eq!(
+        format.parse_prefix("Jan  2 00:00:01 host"),
+        epoch_plus(86401, 0)
+    );
+    assert_eq!(format.parse_prefix("Foo  2 00:00:01 host"), None);
+}
+
+#[test_log::test]
+fn test_timestamps_meta() -> Result<()> {
+    let script = ScriptedReader::new().data(b"2024-02-18T10:20:30Z a\nb\n");
+    let mut reader =
+        LineReader::from_nonblocking(script)?.with_timestamps(TimestampFormat::Rfc3339);
+    let before = SystemTime::now();
+    reader.read_once()?;
+    let lines = reader.lines_get_with_meta();
+    assert_eq!(lines[0].1.timestamp, epoch_plus(1708251630, 0));
+    assert!(lines[0].1.timestamp_from_line);
+    assert!(!lines[1].1.timestamp_from_line);
+    assert!(lines[1].1.timestamp >= Some(before));
+    Ok(())
+}
@@ -0,0 +1,92 @@
+// Copyright (C) 2023 Leandro Lisboa Penz <lpenz@lpenz.org>
+// This file is subject to the terms and conditions defined in
+// file 'LICENSE', which is part of this source code package.
+
+#![cfg(feature = "wasm")]
+
+use color_eyre::Result;
+
+use ::lineriver::testing::*;
+use ::lineriver::*;
+
+/// Keeps the lines as they are.
+const IDENTITY: &str = r#"(module
+    (memory (export "memory") 1)
+    (func (export "alloc") (param i32) (result i32) i32.const 0)
+    (func (export "transform") (param $ptr i32) (param $len i32) (result i64)
+        (i64.or
+            (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+            (i64.extend_i32_u (local.get $len)))))"#;
+
+/// Drops lines that start with '#', and prefixes the others with '>'
+/// by writing the result right before the input.
+const PREFIX: &str = r#"(module
+    (memory (export "memory") 1)
+    (func (export "alloc") (param i32) (result i32) i32.const 1)
+    (func (export "transform") (param $ptr i32) (param $len i32) (result i64)
+        (if (i32.eq (i32.load8_u (local.get $ptr)) (i32.const 35))
+            (then (return (i64.const -1))))
+        (i32.store8 (i32.const 0) (i32.const 62))
+        (i64.add (i64.extend_i32_u (local.get $len)) (i64.const 1))))"#;
+
+/// Traps on every line.
+const TRAP: &str = r#"(module
+    (memory (export "memory") 1)
+    (func (export "alloc") (param i32) (result i32) i32.const 0)
+    (func (export "transform") (param i32) (param i32) (result i64) unreachable))"#;
+
+#[test_log::test]
+fn test_wasm_plugin() -> Result<()> {
+    let mut plugin = WasmPlugin::new(PREFIX)?;
+    assert_eq!(plugin.apply("# comment\n")?, None);
+    assert_eq!(plugin.apply("data\n")?, Some(">data\n".to_string()));
+    assert!(WasmPlugin::new("(module)").is_err());
+    assert!(WasmPlugin::new(TRAP)?.apply("x").is_err());
+    Ok(())
+}
+
+#[test_log::test]
+fn test_wasm_filter() -> Result<()> {
+    let script = ScriptedReader::new()
+        .data(b"# a\nb\n")
+        .would_block()
+        .data(b"c\n")
+        .eof();
+    let reader = LineReader::from_nonblocking(script)?;
+    let mut filter = WasmFilter::new(reader, WasmPlugin::new(PREFIX)?);
+    filter.read_once()?;
+    assert_eq!(filter.lines_get(), vec![">b\n"]);
+    filter.read_once()?;
+    // Hot swap:
+    filter.set_plugin(WasmPlugin::new(IDENTITY)?);
+    filter.read_once()?;
+    assert_eq!(filter.lines_get(), vec!["c\n"]);
+    // The delimiter of the inner reader is kept:
+    let reader = LineReader::from_nonblocking(&b"a\0"[..])?.with_delimiter(b'\0');
+    let filter = WasmFilter::new(reader, WasmPlugin::new(IDENTITY)?);
+    assert!(filter.is_terminated("a\0"));
+    assert!(!filter.is_terminated("a\n"));
+    Ok(())
+}
+
+#[test_log::test]
+fn test_wasm_filter_error() -> Result<()> {
+    let script = ScriptedReader::new().data(b"a\nb\n").eof();
+    let reader = LineReader::from_nonblocking(script)?;
+    let mut filter = WasmFilter::new(reader, WasmPlugin::new(TRAP)?);
+    assert!(filter.read_once().is_err());
+    filter.set_plugin(WasmPlugin::new(IDENTITY)?);
+    filter.read_once()?;
+    assert_eq!(filter.lines_get(), vec!["b\n"]);
+    Ok(())
+}
+
+#[test_log::test]
+fn test_wasm_load() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("lineriver-wasm-{}.wat", std::process::id()));
+    std::fs::write(&path, IDENTITY)?;
+    let mut plugin = WasmPlugin::load(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(plugin.apply("x")?, Some("x".to_string()));
+    Ok(())
+}